@@ -2,26 +2,140 @@ use crossterm::event::KeyEvent;
 use serde::{Deserialize, Serialize};
 use strum::Display;
 
+use crate::{error::Error, git::pull_request::PullRequest, mode::Mode, utils::now_timestamp};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Display, Deserialize)]
 pub enum Action {
+  /// A branch, the tip it was compared at and its commits ahead of and behind its upstream.
+  AheadBehindLoaded(String, String, (usize, usize)),
+  BusyProgress(String),
+  CancelOperation,
+  CancelDetachedCheckout,
+  CancelForceCheckout,
+  CancelPendingDeletion,
+  CancelRenamePush,
+  CheckoutPreviousBranch,
   CheckoutSelectedBranch,
+  CheckoutSelectedCommit,
+  CherryPickSelectedCommit,
+  CloseBranchComparison,
+  CloseBranchDetails,
+  CloseCommitLog,
+  CloseContextMenu,
+  CloseHistory,
+  CloseReflog,
+  /// Commit counts by the range they were counted over, see [`crate::git::git_repo::GitRepo::count_commits`].
+  CommitCountsLoaded(Vec<(String, usize)>),
+  CompareWithBase,
+  ConfirmDetachedCheckout,
+  ConfirmForceCheckout,
+  ConfirmPendingDeletion,
+  ConfirmRenamePush,
+  CopySelectedBranchName,
+  CopySelectedSha,
   CreateBranch(String),
+  /// The new branch's name and the branch it starts from.
+  CreateBranchFrom(String, String),
+  CreateBranchWithEmptyCommit(String),
+  CreateBranchWithoutCheckout(String),
+  CycleUpstreamFilter,
   DeleteBranch,
   DeleteStagedBranches,
-  EndInputMod,
+  EditConfig,
+  EndBranchJump,
+  EndBusy,
   Error(String),
+  Fetch,
+  InitEditDescription,
   InitNewBranch,
+  InitNewBranchFromReflogEntry,
+  InitNewBranchFromSelected,
+  InitPruneRemote,
+  InitRenameBranch,
+  /// What `git rebase -i` was rebasing onto, and whether it exited successfully.
+  InteractiveRebaseExited(String, bool),
+  JumpToBranch(char),
+  MarkCompareBase,
+  OpenInBrowser,
+  OpenInPager(String),
+  OpenPullRequestInBrowser,
+  OperationCancelled,
+  PruneRemote(String),
+  /// The branch and its open pull request, if it has one.
+  PullRequestLoaded(String, Option<PullRequest>),
+  PullRequestUnavailable(String),
+  QuickCheckout(String),
   Quit,
+  RebaseOntoSelected,
+  RecordOperation(HistoryEntry),
   Refresh,
+  /// The remote and the remote tracking branches that were pruned from it.
+  RemotePruned(String, Vec<String>),
+  /// The branch being renamed and its new name.
+  RenameBranch(String, String),
+  /// A renamed branch, and the upstream it replaced on the remote.
+  RenamePushed(String, String),
   Render,
   Resize(u16, u16),
   Resume,
+  ReverseBranchSort,
+  /// Hand the terminal to `git rebase -i` onto the branch.
+  RunInteractiveRebase(String),
+  RunSelectedMenuItem,
+  ScrollComparisonDown,
+  ScrollComparisonUp,
+  ScrollErrorDown,
+  ScrollErrorUp,
+  ScrollHistoryDown,
+  ScrollHistoryUp,
   SelectNextBranch,
+  SelectNextColumn,
+  SelectNextCommit,
+  SelectNextMenuItem,
+  SelectNextReflogEntry,
   SelectPreviousBranch,
+  SelectPreviousColumn,
+  SelectPreviousCommit,
+  SelectPreviousMenuItem,
+  SelectPreviousReflogEntry,
+  /// The branch and its new description, empty to remove it.
+  SetBranchDescription(String, String),
+  SetMode(Mode),
+  ShowBranchDetails,
+  ShowCommitLog,
+  ShowContextMenu,
+  ShowHistory,
+  ShowRecentBranches,
+  ShowReflog,
   StageBranchForDeletion,
-  StartInputMode,
+  StageGoneBranches,
+  StartBranchJump,
+  StartBusy(String),
+  StartQuickCheckout,
+  SubmodulesUpdated,
   Suspend,
   Tick,
+  ToggleBranchSort,
+  ToggleKeymap,
+  ToggleShowAllBranches,
+  ToggleShowRemoteBranches,
   UnstageBranchForDeletion,
+  UpdateDescription(KeyEvent),
   UpdateNewBranchName(KeyEvent),
+  UpdateQuickCheckout(KeyEvent),
+}
+
+/// A git operation the app ran on the user's behalf and how it went.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+  /// Seconds since the unix epoch when the operation finished.
+  pub time: i64,
+  pub description: String,
+  pub error: Option<String>,
+}
+
+impl HistoryEntry {
+  pub fn new<T>(description: String, result: &Result<T, Error>) -> Self {
+    HistoryEntry { time: now_timestamp(), description, error: result.as_ref().err().map(|err| err.to_string()) }
+  }
 }
@@ -1,16 +1,26 @@
+use std::{ops::RangeInclusive, path::PathBuf, process::Command};
+
 use color_eyre::eyre::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::prelude::Rect;
-use tokio::sync::mpsc;
+use tokio::{
+  sync::{mpsc, mpsc::UnboundedSender},
+  task::JoinHandle,
+};
 
 use crate::{
   action::Action,
-  components::{branch_list::BranchList, stash_list::StashList, Component},
-  config::Config,
-  git::{git2_repo::Git2Repo, git_cli_repo::GitCliRepo},
+  cli::Cli,
+  components::{branch_list::BranchList, history::History, spinner::Spinner, stash_list::StashList, Component},
+  config::{editable_config_path, write_default_config, Config},
+  git::{
+    git2_repo::Git2Repo,
+    git_cli_repo::{ensure_git_installed, GitCliRepo},
+  },
   mode::Mode,
-  tui,
+  theme, tui,
   tui::Tui,
+  utils::editor_command,
 };
 
 pub enum View {
@@ -18,8 +28,13 @@ pub enum View {
   Stashes,
 }
 
+// Per second, used unless configured. Ticks advance the spinner and end branch jumps, frames redraw the screen.
 const TICK_RATE: f64 = 10.0;
 const FRAME_RATE: f64 = 30.0;
+// Configured rates are kept to these. Below one a spinner stops looking alive, and past the upper bounds most terminals
+// can't keep up anyway.
+const TICK_RATES: RangeInclusive<f64> = 1.0..=30.0;
+const FRAME_RATES: RangeInclusive<f64> = 1.0..=60.0;
 
 pub struct App {
   pub config: Config,
@@ -29,27 +44,86 @@ pub struct App {
   pub should_suspend: bool,
   pub mode: Mode,
   pub view: View,
+  // Shown once the UI is up, a broken config falls back to defaults rather than refusing to start.
+  config_error: Option<String>,
+  // Shown while a long running operation is in flight, input is ignored until it finishes.
+  busy: Option<Spinner>,
+  // Every git operation run this session, shown over everything while `show_history` is set.
+  history: History,
+  show_history: bool,
+  git_path: PathBuf,
+  // Like the git path these are only read on start, the terminal is set up once.
+  alternate_screen: bool,
+  tick_rate: f64,
+  frame_rate: f64,
+  // Kept so the flags still apply after the config is reloaded.
+  cli_read_only: bool,
+  cli_compact: bool,
+  should_edit_config: bool,
 }
 
 impl App {
-  pub fn new() -> Result<Self> {
-    let config = Config::new()?;
+  /// Command line flags win over their config equivalents. A `config` that failed to load is reported once the
+  /// interface is up, and the defaults are used meanwhile.
+  pub fn new(cli: &Cli, config: Result<Config, config::ConfigError>) -> Result<Self> {
+    let (mut config, config_error) = match config {
+      Ok(config) => (config, None),
+      Err(err) => {
+        log::error!("Failed to load config: {}", err);
+        (Config::default(), Some(format!("Failed to load config, using defaults. {}", err)))
+      },
+    };
+    config.config.read_only |= cli.read_only;
+    config.config.compact |= cli.compact;
+    let git_path = cli.git_path(&config.config);
+    ensure_git_installed(&git_path)?;
+    // Opened first so running outside a repository fails before any git command does.
+    let stash_repo = Git2Repo::from_cwd()?;
     // TODO only have a single repo that is shared
-    let branch_list = Box::new(BranchList::new(Box::new(GitCliRepo::from_cwd().unwrap())));
-    let stash_list = Box::new(StashList::new(Box::new(Git2Repo::from_cwd().unwrap())));
+    let branch_list =
+      Box::new(BranchList::new(Box::new(GitCliRepo::from_cwd().unwrap().with_git_path(git_path.clone()))));
+    let stash_list = Box::new(StashList::new(Box::new(stash_repo)));
     let mode = Mode::Default;
-    Ok(Self { config, branch_list, stash_list, should_quit: false, should_suspend: false, mode, view: View::Branches })
+    let alternate_screen = !(cli.no_alternate_screen || config.config.no_alternate_screen);
+    let tick_rate = rate_within(config.config.tick_rate, TICK_RATE, TICK_RATES);
+    let frame_rate = rate_within(config.config.frame_rate, FRAME_RATE, FRAME_RATES);
+    Ok(Self {
+      config,
+      branch_list,
+      stash_list,
+      should_quit: false,
+      should_suspend: false,
+      mode,
+      view: View::Branches,
+      config_error,
+      busy: None,
+      history: History::default(),
+      show_history: false,
+      git_path,
+      alternate_screen,
+      tick_rate,
+      frame_rate,
+      cli_read_only: cli.read_only,
+      cli_compact: cli.compact,
+      should_edit_config: false,
+    })
   }
 
   pub async fn run(&mut self) -> Result<()> {
     let (action_tx, mut action_rx) = mpsc::unbounded_channel();
 
-    let mut tui = tui::Tui::new()?.tick_rate(TICK_RATE).frame_rate(FRAME_RATE);
+    let mut tui = self.new_tui()?;
     // tui.mouse(true);
     tui.enter()?;
 
     self.branch_list.register_action_handler(action_tx.clone())?;
     self.stash_list.register_action_handler(action_tx.clone())?;
+    self.branch_list.register_config_handler(self.config.clone())?;
+    self.stash_list.register_config_handler(self.config.clone())?;
+    if let Some(config_error) = self.config_error.take() {
+      action_tx.send(Action::Error(config_error))?;
+    }
+    let signals = quit_on_signals(action_tx.clone())?;
 
     loop {
       if let Some(e) = tui.next().await {
@@ -58,13 +132,35 @@ impl App {
           tui::Event::Tick => action_tx.send(Action::Tick)?,
           tui::Event::Render => action_tx.send(Action::Render)?,
           tui::Event::Resize(x, y) => action_tx.send(Action::Resize(x, y))?,
+          tui::Event::Key(key) if self.busy.is_some() => {
+            // Only cancelling is allowed while an operation is in flight, it shouldn't quit the app.
+            if let KeyEvent { code: KeyCode::Esc, modifiers: _, state: _, kind: _ }
+            | KeyEvent { code: KeyCode::Char('c' | 'C'), modifiers: KeyModifiers::CONTROL, state: _, kind: _ } = key
+            {
+              action_tx.send(Action::CancelOperation)?;
+            }
+          },
+          tui::Event::Key(key) if self.show_history => {
+            if let Some(action) = self.history.handle_key_events(key)? {
+              action_tx.send(action)?;
+            }
+          },
           tui::Event::Key(key) => {
             if self.mode == Mode::Default {
               let action = match key {
-                KeyEvent { code: KeyCode::Esc, modifiers: _, state: _, kind: _ } => Some(Action::Quit),
+                KeyEvent { code: KeyCode::Esc, modifiers: _, state: _, kind: _ }
+                | KeyEvent { code: KeyCode::Char('q'), modifiers: KeyModifiers::NONE, state: _, kind: _ } => {
+                  Some(Action::Quit)
+                },
                 KeyEvent { code: KeyCode::Char('c' | 'C'), modifiers: KeyModifiers::CONTROL, state: _, kind: _ } => {
                   Some(Action::Quit)
                 },
+                KeyEvent { code: KeyCode::Char(','), modifiers: KeyModifiers::NONE, state: _, kind: _ } => {
+                  Some(Action::EditConfig)
+                },
+                KeyEvent { code: KeyCode::Char('h' | 'H'), modifiers: KeyModifiers::SHIFT, state: _, kind: _ } => {
+                  Some(Action::ShowHistory)
+                },
                 _ => None,
               };
               if action.is_some() {
@@ -79,8 +175,11 @@ impl App {
           View::Branches => &mut self.branch_list,
           View::Stashes => &mut self.stash_list,
         };
-        if let Some(action) = component.handle_events(Some(e.clone()))? {
-          action_tx.send(action)?;
+        let is_input = matches!(e, tui::Event::Key(_) | tui::Event::Mouse(_) | tui::Event::Paste(_));
+        if !(is_input && (self.busy.is_some() || self.show_history)) {
+          if let Some(action) = component.handle_events(Some(e.clone()))? {
+            action_tx.send(action)?;
+          }
         }
       }
 
@@ -94,11 +193,58 @@ impl App {
         };
 
         match action {
-          Action::StartInputMode => self.mode = Mode::Input,
-          Action::EndInputMod => self.mode = Mode::Default,
+          Action::SetMode(mode) => self.mode = mode,
           Action::Quit => self.should_quit = true,
           Action::Suspend => self.should_suspend = true,
           Action::Resume => self.should_suspend = false,
+          Action::StartBusy(ref label) => self.busy = Some(Spinner::new(label.clone())),
+          Action::BusyProgress(ref label) => {
+            if let Some(busy) = self.busy.as_mut() {
+              busy.set_label(label.clone());
+            }
+          },
+          Action::CancelOperation => {
+            if self.busy.is_some() {
+              self.busy = Some(Spinner::new(String::from("Cancelling")));
+            }
+          },
+          Action::EndBusy => self.busy = None,
+          Action::EditConfig => self.should_edit_config = true,
+          Action::RecordOperation(ref entry) => self.history.push(entry.clone()),
+          Action::ShowHistory => {
+            self.show_history = true;
+            self.history.update(Action::ShowHistory)?;
+          },
+          Action::CloseHistory => self.show_history = false,
+          Action::ScrollHistoryUp | Action::ScrollHistoryDown => {
+            self.history.update(action.clone())?;
+          },
+          Action::OpenInPager(ref revision) => {
+            // Left to git to page, so the user's configured pager is used.
+            let status =
+              tui.run_external(Command::new(&self.git_path).args(["show", "--end-of-options", revision.as_str()]));
+            match status {
+              Ok(status) if !status.success() => {
+                action_tx.send(Action::Error(format!("`git show {}` exited with {}", revision, status)))?
+              },
+              Err(err) => action_tx.send(Action::Error(format!("Failed to run `git show {}`: {}", revision, err)))?,
+              _ => {},
+            }
+          },
+          Action::RunInteractiveRebase(ref onto) => {
+            // Git opens the editor itself, for the todo list and any commits being reworded.
+            let status =
+              tui.run_external(Command::new(&self.git_path).args(["rebase", "-i", "--end-of-options", onto.as_str()]));
+            match status {
+              Ok(status) => action_tx.send(Action::InteractiveRebaseExited(onto.clone(), status.success()))?,
+              Err(err) => action_tx.send(Action::Error(format!("Failed to run `git rebase -i {}`: {}", onto, err)))?,
+            }
+          },
+          Action::Tick => {
+            if let Some(busy) = self.busy.as_mut() {
+              busy.update(Action::Tick)?;
+            }
+          },
           Action::Resize(w, h) => {
             tui.resize(Rect::new(0, 0, w, h))?;
             tui.draw(|f| {
@@ -106,6 +252,17 @@ impl App {
               if let Err(e) = r {
                 action_tx.send(Action::Error(format!("Failed to draw: {:?}", e))).unwrap();
               }
+              if self.show_history {
+                if let Err(e) = self.history.draw(f, f.area()) {
+                  action_tx.send(Action::Error(format!("Failed to draw: {:?}", e))).unwrap();
+                }
+              }
+              if let Some(busy) = self.busy.as_mut() {
+                if let Err(e) = busy.draw(f, f.area()) {
+                  action_tx.send(Action::Error(format!("Failed to draw: {:?}", e))).unwrap();
+                }
+              }
+              theme::finish_frame(f.buffer_mut());
             })?;
           },
           Action::Render => {
@@ -114,6 +271,17 @@ impl App {
               if let Err(e) = r {
                 action_tx.send(Action::Error(format!("Failed to draw: {:?}", e))).unwrap();
               }
+              if self.show_history {
+                if let Err(e) = self.history.draw(f, f.area()) {
+                  action_tx.send(Action::Error(format!("Failed to draw: {:?}", e))).unwrap();
+                }
+              }
+              if let Some(busy) = self.busy.as_mut() {
+                if let Err(e) = busy.draw(f, f.area()) {
+                  action_tx.send(Action::Error(format!("Failed to draw: {:?}", e))).unwrap();
+                }
+              }
+              theme::finish_frame(f.buffer_mut());
             })?;
           },
           _ => {},
@@ -122,17 +290,99 @@ impl App {
           action_tx.send(action)?
         };
       }
+      if self.should_edit_config {
+        self.should_edit_config = false;
+        self.edit_config(&mut tui, &action_tx)?;
+      }
       if self.should_suspend {
         tui.suspend()?;
         action_tx.send(Action::Resume)?;
-        tui = Tui::new()?.tick_rate(TICK_RATE).frame_rate(FRAME_RATE);
+        tui = self.new_tui()?;
         tui.enter()?;
       } else if self.should_quit {
         tui.stop()?;
         break;
       }
     }
+    if let Some(signals) = signals {
+      signals.abort();
+    }
     tui.exit()?;
     Ok(())
   }
+
+  fn new_tui(&self) -> Result<Tui> {
+    Ok(Tui::new()?.tick_rate(self.tick_rate).frame_rate(self.frame_rate).alternate_screen(self.alternate_screen))
+  }
+
+  /// Open the config in the user's editor, then reload it so changes apply without a restart.
+  fn edit_config(&mut self, tui: &mut Tui, action_tx: &UnboundedSender<Action>) -> Result<()> {
+    let path = editable_config_path();
+    if !path.exists() {
+      if let Err(err) = write_default_config(&path) {
+        action_tx.send(Action::Error(format!("Failed to create {}: {}", path.display(), err)))?;
+        return Ok(());
+      }
+    }
+    let mut editor = editor_command();
+    editor.arg(&path);
+    match tui.run_external(&mut editor) {
+      Ok(status) if !status.success() => {
+        action_tx.send(Action::Error(format!("Editor exited with {}, config was not reloaded", status)))?;
+        return Ok(());
+      },
+      Err(err) => {
+        action_tx.send(Action::Error(format!(
+          "Failed to run editor {}: {}. Set $EDITOR to the editor you want to use",
+          editor.get_program().to_string_lossy(),
+          err
+        )))?;
+        return Ok(());
+      },
+      _ => {},
+    }
+    match Config::new() {
+      Ok(mut config) => {
+        config.config.read_only |= self.cli_read_only;
+        config.config.compact |= self.cli_compact;
+        self.branch_list.register_config_handler(config.clone())?;
+        self.stash_list.register_config_handler(config.clone())?;
+        self.config = config;
+      },
+      Err(err) => {
+        action_tx.send(Action::Error(format!("Failed to reload config, keeping the previous one. {}", err)))?
+      },
+    }
+    Ok(())
+  }
+}
+
+/// A configured rate kept within `bounds`, or `default` when it's unset or not a number.
+fn rate_within(configured: Option<f64>, default: f64, bounds: RangeInclusive<f64>) -> f64 {
+  configured.filter(|rate| rate.is_finite()).map_or(default, |rate| rate.clamp(*bounds.start(), *bounds.end()))
+}
+
+/// Quit the way `q` does when the terminal is closed or the process is asked to stop, so the terminal is restored
+/// rather than left in raw mode. Any operation in flight is cancelled first, exiting waits for it to finish.
+#[cfg(unix)]
+fn quit_on_signals(action_tx: UnboundedSender<Action>) -> Result<Option<JoinHandle<()>>> {
+  use tokio::signal::unix::{signal, SignalKind};
+  let mut terminate = signal(SignalKind::terminate())?;
+  let mut hangup = signal(SignalKind::hangup())?;
+  Ok(Some(tokio::spawn(async move {
+    loop {
+      tokio::select! {
+        _ = terminate.recv() => log::info!("Quitting on SIGTERM"),
+        _ = hangup.recv() => log::info!("Quitting on SIGHUP"),
+      }
+      // The app may already be gone, there's nothing left to restore then.
+      let _ = action_tx.send(Action::CancelOperation);
+      let _ = action_tx.send(Action::Quit);
+    }
+  })))
+}
+
+#[cfg(not(unix))]
+fn quit_on_signals(_action_tx: UnboundedSender<Action>) -> Result<Option<JoinHandle<()>>> {
+  Ok(None)
 }
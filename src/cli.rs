@@ -1,7 +1,67 @@
-use clap::Parser;
+use std::path::PathBuf;
 
-use crate::utils::version;
+use clap::{Parser, Subcommand};
+
+use crate::{config::AppConfig, git::git_cli_repo::DEFAULT_GIT_PATH, utils::version};
 
 #[derive(Parser, Debug)]
 #[command(author, version = version(), about)]
-pub struct Cli {}
+pub struct Cli {
+  /// Directory to read config from, takes precedence over GIT_BRANCH_MANAGER_CONFIG
+  #[arg(long, value_name = "DIR")]
+  pub config_dir: Option<PathBuf>,
+
+  /// Directory to write logs and data to, takes precedence over GIT_BRANCH_MANAGER_DATA
+  #[arg(long, value_name = "DIR")]
+  pub data_dir: Option<PathBuf>,
+
+  /// Git executable to run instead of `git` on the PATH, takes precedence over the git_path config
+  #[arg(long, value_name = "PATH")]
+  pub git_path: Option<PathBuf>,
+
+  /// Only list and inspect, every action that would change the repository is disabled
+  #[arg(long)]
+  pub read_only: bool,
+
+  /// Drop the borders and footer to fit as many branches as possible, for small panes
+  #[arg(long)]
+  pub compact: bool,
+
+  /// Draw in the terminal's normal screen instead of the alternate one, so the list stays in the scrollback on exit
+  #[arg(long)]
+  pub no_alternate_screen: bool,
+
+  /// Run one operation and exit instead of opening the interface
+  #[command(subcommand)]
+  pub command: Option<Command>,
+}
+
+impl Cli {
+  /// The git to run, the flag wins over the config.
+  pub fn git_path(&self, config: &AppConfig) -> PathBuf {
+    self.git_path.clone().or_else(|| config.git_path.clone()).unwrap_or_else(|| PathBuf::from(DEFAULT_GIT_PATH))
+  }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+  /// Delete local branches without opening the interface, for scripts and hooks. Protected branches are kept
+  Delete {
+    /// Delete the branches already merged into the base
+    #[arg(long, required = true)]
+    merged: bool,
+
+    /// Branch to check against, takes precedence over the default_base config and the detected default branch
+    #[arg(long, value_name = "BRANCH")]
+    base: Option<String>,
+
+    /// Print what would be deleted without deleting anything
+    #[arg(long)]
+    dry_run: bool,
+  },
+  /// Check out the local branch a partial name resolves to, it has to be the whole name or a prefix of just one branch
+  Checkout {
+    /// The start of the branch's name
+    partial: String,
+  },
+}
@@ -0,0 +1,40 @@
+use std::io::{self, Write};
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Put `text` on the system clipboard through the terminal with an OSC 52 escape sequence. This needs no clipboard
+/// libraries and keeps working over ssh, terminals that don't support it silently ignore the sequence.
+pub fn copy(text: &str) -> io::Result<()> {
+  let mut out = crate::tui::io();
+  write!(out, "\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))?;
+  out.flush()
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+  let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+  for chunk in bytes.chunks(3) {
+    let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+    let indexes = [b[0] >> 2, (b[0] & 0b11) << 4 | b[1] >> 4, (b[1] & 0b1111) << 2 | b[2] >> 6, b[2] & 0b11_1111];
+    for (i, index) in indexes.iter().enumerate() {
+      // A chunk of n bytes fills n + 1 characters, the rest is padding.
+      encoded.push(if i <= chunk.len() { char::from(BASE64_ALPHABET[usize::from(*index)]) } else { '=' });
+    }
+  }
+  encoded
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn base64_encode_pads_partial_chunks() {
+    let cases = vec![("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("feature/ui", "ZmVhdHVyZS91aQ==")];
+
+    for (text, expected) in cases {
+      assert_eq!(base64_encode(text.as_bytes()), expected, "{}", text);
+    }
+  }
+}
@@ -5,10 +5,14 @@ use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{
   action::Action,
+  config::Config,
   tui::{Event, Frame},
 };
 
 pub mod branch_list;
+pub mod history;
+pub mod popup;
+pub mod spinner;
 pub mod stash_list;
 
 /// `Component` is a trait that represents a visual and interactive element of the user interface.
@@ -29,6 +33,19 @@ pub trait Component {
   fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
     Ok(())
   }
+  /// Register a configuration handler that provides configuration settings if necessary.
+  ///
+  /// # Arguments
+  ///
+  /// * `config` - Configuration settings.
+  ///
+  /// # Returns
+  ///
+  /// * `Result<()>` - An Ok result or an error.
+  #[allow(unused_variables)]
+  fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    Ok(())
+  }
   /// Handle incoming events and produce actions if necessary.
   ///
   /// # Arguments
@@ -1,44 +1,327 @@
+use std::{
+  cell::Cell,
+  cmp::Ordering,
+  collections::{HashMap, HashSet},
+  iter::Peekable,
+  str::Chars,
+  time::{Duration, Instant},
+};
+
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
   layout::{Constraint, Direction, Layout, Rect},
-  style::{Color, Modifier, Style},
-  text::Text,
-  widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+  style::{Color, Style},
+  text::{Line, Text},
+  widgets::{Block, Borders, Clear, List, ListItem, ListState, Padding, Paragraph, Wrap},
 };
+use regex::Regex;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
 use tracing::error;
+use unicode_width::UnicodeWidthStr;
 
 use crate::{
-  action::Action,
+  action::{Action, HistoryEntry},
+  clipboard,
   components::{
-    branch_list::{branch_input::BranchInput, branch_item::BranchItem, instruction_footer::InstructionFooter},
+    branch_list::{
+      branch_comparison::BranchComparison,
+      branch_details::{BranchDetailsPopup, PullRequestStatus},
+      branch_input::BranchInput,
+      branch_item::BranchItem,
+      commit_log::CommitLog,
+      context_menu::{ContextMenu, MenuItem},
+      description_input::DescriptionInput,
+      head_sync::HeadSync,
+      instruction_footer::InstructionFooter,
+      keymap_pin::KeymapPin,
+      query_cache::QueryCache,
+      quick_checkout_input::QuickCheckoutInput,
+      reflog::Reflog,
+    },
+    popup::centered_rect,
     Component,
   },
+  config::{BranchSort, Config, SortDirection},
   error::Error,
-  git::git_repo::{GitBranch, GitRepo},
+  git::{
+    git_repo::{
+      detect_default_branch, merged_into_default_branch, resolve_branch_prefix, short_sha, GitBranch, GitBranchDetails,
+      GitCommit, GitRemoteBranch, GitRepo,
+    },
+    pull_request::pull_request_lookup,
+    web_url::WebRepo,
+  },
+  mode::Mode,
+  theme::Highlight,
   tui::Frame,
+  utils::{glob_to_regex, now_timestamp, open_in_browser},
 };
 
+mod branch_comparison;
+mod branch_details;
 mod branch_input;
 mod branch_item;
+mod commit_log;
+mod context_menu;
+mod description_input;
+mod head_sync;
 mod instruction_footer;
+mod keymap_pin;
+mod query_cache;
+mod quick_checkout_input;
+mod reflog;
+
+// Below this there isn't room for a usable list, a hint to resize is shown instead.
+const MIN_WIDTH: u16 = 30;
+const MIN_HEIGHT: u16 = 8;
+// Without borders and the footer a few rows are still usable.
+const MIN_COMPACT_HEIGHT: u16 = 4;
+
+// Shown on start in a shallow clone, where anything that walks history can come up short.
+const SHALLOW_WARNING: &str =
+  "Shallow clone: merged branch detection, the log and ahead/behind counts may be incomplete";
+
+// How far back the reflog view goes, older entries are rarely what's being looked for.
+const REFLOG_LIMIT: usize = 200;
+
+// Branches offered by the recent branches picker, enough for the few being worked on at once.
+const RECENT_BRANCH_LIMIT: usize = 5;
+
+// How many commits the log loads at a time, the next page is loaded once the selection reaches the end.
+const COMMIT_LOG_PAGE_SIZE: usize = 50;
+
+// How long to wait between key presses before a jump prefix is discarded.
+const JUMP_TIMEOUT: Duration = Duration::from_millis(1500);
+
+// Least time between fetch progress updates, libgit2 reports far more often than the spinner is drawn.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+// Branches without a commit this recent are highlighted as stale, unless configured otherwise.
+const DEFAULT_STALE_AFTER_DAYS: u64 = 90;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Narrows the list by how branches track a remote, for triaging them: setting upstreams on the ones without, deleting
+/// the gone ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum UpstreamFilter {
+  #[default]
+  All,
+  Tracking,
+  NoUpstream,
+  Gone,
+}
+
+impl UpstreamFilter {
+  fn next(self) -> Self {
+    match self {
+      UpstreamFilter::All => UpstreamFilter::Tracking,
+      UpstreamFilter::Tracking => UpstreamFilter::NoUpstream,
+      UpstreamFilter::NoUpstream => UpstreamFilter::Gone,
+      UpstreamFilter::Gone => UpstreamFilter::All,
+    }
+  }
+
+  /// Remote branches aren't local ones tracking anything, so only show without a filter.
+  fn matches(self, item: &BranchItem) -> bool {
+    let branch = &item.branch;
+    match self {
+      UpstreamFilter::All => true,
+      _ if item.remote.is_some() => false,
+      UpstreamFilter::Tracking => branch.upstream.is_some() && !branch.upstream_gone,
+      UpstreamFilter::NoUpstream => branch.upstream.is_none(),
+      UpstreamFilter::Gone => branch.upstream_gone,
+    }
+  }
+
+  fn label(self) -> &'static str {
+    match self {
+      UpstreamFilter::All => "all",
+      UpstreamFilter::Tracking => "tracking",
+      UpstreamFilter::NoUpstream => "no upstream",
+      UpstreamFilter::Gone => "gone",
+    }
+  }
+}
+
+/// How the list is ordered, a key and direction with optionally a second pair to break ties.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct BranchOrder {
+  key: BranchSort,
+  direction: SortDirection,
+  tiebreaker: Option<(BranchSort, SortDirection)>,
+}
+
+impl BranchOrder {
+  fn compare(&self, a: &GitBranch, b: &GitBranch) -> Ordering {
+    let ordering = compare_by(a, b, self.key, self.direction);
+    match self.tiebreaker {
+      Some((key, direction)) => ordering.then_with(|| compare_by(a, b, key, direction)),
+      None => ordering,
+    }
+  }
+
+  /// How the order is shown in the list's title, nothing for the default.
+  fn describe(&self) -> Option<&'static str> {
+    match (self.key, self.direction) {
+      (BranchSort::Name, SortDirection::Ascending) => None,
+      (BranchSort::Name, SortDirection::Descending) => Some("by name, reversed"),
+      (BranchSort::Age, SortDirection::Ascending) => Some("by age, oldest first"),
+      (BranchSort::Age, SortDirection::Descending) => Some("by age, newest first"),
+    }
+  }
+}
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum Mode {
-  Selection,
-  Input,
+fn compare_by(a: &GitBranch, b: &GitBranch, key: BranchSort, direction: SortDirection) -> Ordering {
+  match key {
+    BranchSort::Name => direction.apply(compare_names(&a.name, &b.name)),
+    BranchSort::Age => {
+      match (a.last_commit_time, b.last_commit_time) {
+        (Some(a_time), Some(b_time)) => direction.apply(a_time.cmp(&b_time)),
+        // Branches without a known commit time go last whichever way round the list is.
+        (a_time, b_time) => a_time.is_none().cmp(&b_time.is_none()),
+      }
+    },
+  }
+}
+
+/// Order names the way people read them, ignoring case and comparing runs of digits by value so `Feature-2` comes before
+/// `feature-10`. Names that only differ in case or leading zeros fall back to byte order, so no two names tie.
+fn compare_names(a: &str, b: &str) -> Ordering {
+  let mut a_chars = a.chars().peekable();
+  let mut b_chars = b.chars().peekable();
+  loop {
+    let ordering = match (a_chars.peek().copied(), b_chars.peek().copied()) {
+      (None, None) => return a.cmp(b),
+      (None, Some(_)) => return Ordering::Less,
+      (Some(_), None) => return Ordering::Greater,
+      (Some(a_char), Some(b_char)) if a_char.is_ascii_digit() && b_char.is_ascii_digit() => {
+        let a_number = take_number(&mut a_chars);
+        let b_number = take_number(&mut b_chars);
+        // Without leading zeros a longer number is a bigger one, which avoids overflowing on long digit runs.
+        a_number.len().cmp(&b_number.len()).then_with(|| a_number.cmp(&b_number))
+      },
+      (Some(a_char), Some(b_char)) => {
+        a_chars.next();
+        b_chars.next();
+        a_char.to_lowercase().cmp(b_char.to_lowercase())
+      },
+    };
+    if ordering != Ordering::Equal {
+      return ordering;
+    }
+  }
+}
+
+/// Consume a run of digits, without its leading zeros.
+fn take_number(chars: &mut Peekable<Chars>) -> String {
+  let mut digits = String::new();
+  while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+    digits.push(digit);
+  }
+  String::from(digits.trim_start_matches('0'))
+}
+
+/// An in progress "type to jump" search, the selection moves to the first branch matching the typed prefix.
+#[derive(Debug, Clone)]
+struct BranchJump {
+  prefix: String,
+  last_input: Instant,
 }
 
 pub struct BranchList {
   mode: Mode,
   repo: Box<dyn GitRepo>,
+  action_tx: Option<UnboundedSender<Action>>,
+  // Cancels the operation running in the background, if there is one.
+  operation: Option<CancellationToken>,
+  // A bare repo has no worktree, so anything that needs to checkout is disabled
+  is_bare: bool,
+  // Older history is missing, so anything that walks it may be incomplete.
+  is_shallow: bool,
+  // Shown in the title until the rebase is continued or aborted.
+  rebase_in_progress: bool,
+  interactive_rebase: bool,
+  read_only: bool,
+  // Only the list is drawn, without borders or the footer.
+  compact: bool,
+  // Whether the footer lists keys, when hidden it only shows status messages and how to bring them back.
+  keymap: KeymapPin,
+  // A short notice shown in place of the footer until the next key press.
+  status: Option<String>,
   error: Option<String>,
+  error_scroll: u16,
   // List state
   branches: Vec<BranchItem>,
-  list_state: ListState,
-  selected_index: usize,
+  // First visible row of the list, only the rows that fit are turned into list items.
+  list_offset: usize,
+  // The grid the list was last drawn in, so left and right know how far a column is.
+  list_rows: usize,
+  list_columns: usize,
+  // The selected branch by name rather than position, so it stays put as the list is reloaded, sorted and filtered.
+  selected_name: Option<String>,
+  // Where `selected_name` was last found in `branches`, checked before it's trusted so reloads and filters can't
+  // leave it pointing at the wrong row.
+  resolved_selection: Cell<Option<usize>>,
+  jump: Option<BranchJump>,
+  // Filtering, hidden branches are only skipped when rendering and navigating
+  hidden_patterns: Vec<Regex>,
+  // Never staged or deleted, on top of HEAD which is always kept
+  protected_patterns: Vec<Regex>,
+  default_base: Option<String>,
+  max_name_width: Option<usize>,
+  column_width: Option<u16>,
+  abbreviated_prefixes: Vec<String>,
+  web_hosts: HashMap<String, String>,
+  github_token: Option<String>,
+  copy_full_sha: bool,
+  show_commit_counts: bool,
+  update_submodules: bool,
+  query_cache: QueryCache,
+  // The tip of the default branch when counts were last started, counts are of what a branch has beyond it.
+  commit_count_base: Option<String>,
+  // The default branch once detected, detecting it reads config and the remote's HEAD so it isn't redone every reload.
+  detected_base: Option<Option<String>>,
+  // Cancels the counts still running when they're started again, the newer ones cover everything still uncounted.
+  commit_counts_job: Option<CancellationToken>,
+  empty_commit_message: Option<String>,
+  merged_branches: HashSet<String>,
+  show_all: bool,
+  // Remote branches that no local branch tracks are mixed into the list.
+  show_remotes: bool,
+  // Only this remote's branches are mixed in, when set.
+  shown_remote: Option<String>,
+  upstream_filter: UpstreamFilter,
+  sort: BranchOrder,
+  stale_after_days: u64,
+  stale_color: Color,
+  highlight: Highlight,
+  details: Option<GitBranchDetails>,
+  // Only looked up while the details are open, and only when pull requests are configured.
+  pull_request: Option<PullRequestStatus>,
+  compare_base: Option<String>,
+  comparison: Option<BranchComparison>,
+  commit_log: Option<CommitLog>,
+  show_commit_notes: bool,
+  reflog: Option<Reflog>,
+  context_menu: Option<ContextMenu>,
+  // Kept up to date with every change to HEAD or the branches, it's always on screen.
+  head_sync: Option<HeadSync>,
+  // A deletion that would remove the default branch, held back until the user confirms it.
+  pending_deletion: Option<(Action, String)>,
+  default_deletion_confirmed: bool,
+  // A checkout refused because it would overwrite local changes, held until the user confirms throwing them away.
+  pending_force_checkout: Option<String>,
+  // A commit from the log to check out detached, held until the user confirms leaving their branch.
+  pending_detached_checkout: Option<GitCommit>,
+  // A renamed branch and the upstream still under its old name, held until the user confirms renaming that too.
+  pending_rename_push: Option<(String, GitRemoteBranch)>,
   // Components
   branch_input: BranchInput,
+  branch_details_popup: BranchDetailsPopup,
+  description_input: DescriptionInput,
   instruction_footer: InstructionFooter,
+  quick_checkout_input: QuickCheckoutInput,
 }
 
 impl BranchList {
@@ -46,261 +329,2421 @@ impl BranchList {
     // Assume branch names are all valid as they come from git
     let branches: Vec<BranchItem> =
       repo.local_branches().unwrap().iter().map(|branch| BranchItem::new(branch.clone(), true)).collect();
-    BranchList {
+    let is_bare = repo.is_bare().unwrap_or_else(|err| {
+      error!("Failed to check if the repo is bare: {}", err);
+      false
+    });
+    let is_shallow = repo.is_shallow().unwrap_or_else(|err| {
+      error!("Failed to check if the repo is shallow: {}", err);
+      false
+    });
+    let rebase_in_progress = repo.rebase_in_progress().unwrap_or_else(|err| {
+      error!("Failed to check for a rebase in progress: {}", err);
+      false
+    });
+    let mut branch_list = BranchList {
       repo,
-      mode: Mode::Selection,
+      action_tx: None,
+      operation: None,
+      is_bare,
+      is_shallow,
+      rebase_in_progress,
+      interactive_rebase: false,
+      read_only: false,
+      compact: false,
+      keymap: KeymapPin::default(),
+      status: is_shallow.then(|| String::from(SHALLOW_WARNING)),
+      mode: Mode::Default,
       error: None,
+      error_scroll: 0,
       branches,
-      list_state: ListState::default(),
-      selected_index: 0,
+      list_offset: 0,
+      list_rows: 1,
+      list_columns: 1,
+      selected_name: None,
+      resolved_selection: Cell::new(None),
+      jump: None,
+      hidden_patterns: Vec::new(),
+      protected_patterns: Vec::new(),
+      default_base: None,
+      max_name_width: None,
+      column_width: None,
+      abbreviated_prefixes: Vec::new(),
+      web_hosts: HashMap::new(),
+      github_token: None,
+      copy_full_sha: false,
+      show_commit_counts: false,
+      update_submodules: false,
+      query_cache: QueryCache::default(),
+      commit_count_base: None,
+      detected_base: None,
+      commit_counts_job: None,
+      empty_commit_message: None,
+      merged_branches: HashSet::new(),
+      show_all: false,
+      upstream_filter: UpstreamFilter::All,
+      show_remotes: false,
+      shown_remote: None,
+      sort: BranchOrder::default(),
+      stale_after_days: DEFAULT_STALE_AFTER_DAYS,
+      stale_color: Color::DarkGray,
+      highlight: Highlight::default(),
+      details: None,
+      pull_request: None,
+      compare_base: None,
+      comparison: None,
+      commit_log: None,
+      show_commit_notes: false,
+      reflog: None,
+      context_menu: None,
+      head_sync: None,
+      pending_deletion: None,
+      default_deletion_confirmed: false,
+      pending_force_checkout: None,
+      pending_detached_checkout: None,
+      pending_rename_push: None,
       branch_input: BranchInput::new(),
+      branch_details_popup: BranchDetailsPopup::default(),
+      description_input: DescriptionInput::new(),
       instruction_footer: InstructionFooter::default(),
-    }
+      quick_checkout_input: QuickCheckoutInput::new(),
+    };
+    branch_list.refresh_head_sync();
+    branch_list
   }
 
   pub fn clear_error(&mut self) {
     self.error = None;
   }
 
-  pub fn select_previous(&mut self) {
-    if self.selected_index == 0 {
-      self.selected_index = self.branches.len() - 1;
-      return;
+  fn set_error(&mut self, message: String) {
+    self.error = Some(message);
+    self.error_scroll = 0;
+  }
+
+  fn apply_config(&mut self, config: &Config) {
+    self.hidden_patterns = config
+      .config
+      .hidden_branch_patterns
+      .iter()
+      .filter_map(|pattern| {
+        Regex::new(pattern).map_err(|err| error!("Ignoring invalid hidden branch pattern {}: {}", pattern, err)).ok()
+      })
+      .collect();
+    self.protected_patterns = config
+      .config
+      .protected_branch_patterns
+      .iter()
+      .filter_map(|glob| {
+        glob_to_regex(glob).map_err(|err| error!("Ignoring invalid protected branch pattern {}: {}", glob, err)).ok()
+      })
+      .collect();
+    // Anything staged under an older config shouldn't be one key away from deletion.
+    for item in self.branches.iter_mut() {
+      if self.protected_patterns.iter().any(|pattern| pattern.is_match(&item.branch.name)) {
+        item.stage_for_deletion(false);
+      }
+    }
+    self.default_base = config.config.default_base.clone();
+    self.detected_base = None;
+    self.read_only = config.config.read_only;
+    self.compact = config.config.compact;
+    if !config.config._data_dir.as_os_str().is_empty() {
+      self.keymap = KeymapPin::load(&config.config._data_dir);
+    }
+    self.max_name_width = config.config.branch_name_max_width;
+    self.column_width = config.config.column_width.filter(|width| *width > 0);
+    self.abbreviated_prefixes = config.config.abbreviated_branch_prefixes.clone();
+    self.web_hosts = config.config.web_hosts.clone();
+    self.github_token = config.config.github_token.clone();
+    self.copy_full_sha = config.config.copy_full_sha;
+    self.show_commit_counts = config.config.show_commit_counts;
+    self.update_submodules = config.config.update_submodules_on_checkout;
+    self.interactive_rebase = config.config.interactive_rebase;
+    self.show_commit_notes = config.config.show_commit_notes;
+    self.empty_commit_message = config.config.empty_commit_message.clone();
+    self.stale_after_days = config.config.stale_after_days.unwrap_or(DEFAULT_STALE_AFTER_DAYS);
+    self.stale_color = config.config.stale_branch_color.unwrap_or(Color::DarkGray);
+    self.highlight = Highlight::new(config.config.highlight_symbol.clone(), config.config.selection_style);
+    self.branch_input.empty_commit_enabled = self.empty_commit_message.is_some();
+    self.set_sort(BranchOrder {
+      key: config.config.sort_by,
+      direction: config.config.sort_direction,
+      tiebreaker: config.config.then_sort_by.map(|key| (key, config.config.then_sort_direction)),
+    });
+    self.merged_branches =
+      if config.config.hide_merged_branches { self.load_merged_branches() } else { HashSet::new() };
+    if self.shown_remote != config.config.shown_remote {
+      self.shown_remote = config.config.shown_remote.clone();
+      if self.show_remotes {
+        if let Err(err) = self.reload_branches() {
+          error!("Failed to reload the branches of {:?}: {}", self.shown_remote, err);
+        }
+      }
+    }
+    self.ensure_selection_visible();
+    self.start_commit_counts();
+  }
+
+  fn load_merged_branches(&self) -> HashSet<String> {
+    match merged_into_default_branch(&*self.repo, self.default_base.as_deref()) {
+      Ok(merged) => merged.into_iter().collect(),
+      Err(err) => {
+        error!("Failed to load merged branches: {}", err);
+        HashSet::new()
+      },
+    }
+  }
+
+  fn is_hidden(&self, item: &BranchItem) -> bool {
+    if item.branch.is_head {
+      return false;
+    }
+    if !self.upstream_filter.matches(item) {
+      return true;
+    }
+    if self.show_all {
+      return false;
     }
-    if self.selected_index >= self.branches.len() {
-      self.selected_index = self.branches.len() - 1;
+    self.merged_branches.contains(&item.branch.name)
+      || self.hidden_patterns.iter().any(|pattern| pattern.is_match(&item.branch.name))
+  }
+
+  fn has_hidden_rules(&self) -> bool {
+    !self.hidden_patterns.is_empty() || !self.merged_branches.is_empty()
+  }
+
+  /// Indexes into `branches` of the items that are currently shown.
+  fn visible_indexes(&self) -> Vec<usize> {
+    (0..self.branches.len()).filter(|index| !self.is_hidden(&self.branches[*index])).collect()
+  }
+
+  /// Where the selected branch is in `branches`, or the first shown branch when it's hidden or gone, so the selection
+  /// always lands on a row of the current view.
+  fn selected_index(&self) -> Option<usize> {
+    let selected = self.selected_name.as_ref().and_then(|name| {
+      let is_selected =
+        |index: usize| self.branches.get(index).is_some_and(|item| item.branch.name == *name && !self.is_hidden(item));
+      if let Some(index) = self.resolved_selection.get().filter(|index| is_selected(*index)) {
+        return Some(index);
+      }
+      let index = (0..self.branches.len()).find(|index| is_selected(*index))?;
+      self.resolved_selection.set(Some(index));
+      Some(index)
+    });
+    selected.or_else(|| (0..self.branches.len()).find(|index| !self.is_hidden(&self.branches[*index])))
+  }
+
+  fn select_index(&mut self, index: usize) {
+    self.selected_name = self.branches.get(index).map(|item| item.branch.name.clone());
+    self.resolved_selection.set(Some(index));
+  }
+
+  /// Move the selection onto the branch it's shown on, so it doesn't come back to a branch once that's shown again.
+  fn ensure_selection_visible(&mut self) {
+    if let Some(index) = self.selected_index() {
+      self.select_index(index);
+    }
+  }
+
+  fn toggle_show_all(&mut self) {
+    self.show_all = !self.show_all;
+    self.ensure_selection_visible();
+  }
+
+  fn cycle_upstream_filter(&mut self) {
+    self.upstream_filter = self.upstream_filter.next();
+    self.ensure_selection_visible();
+  }
+
+  fn toggle_keymap(&mut self) {
+    if let Err(err) = self.keymap.toggle() {
+      self.set_error(format!("Failed to remember whether the keys are shown: {}", err));
+    }
+  }
+
+  fn toggle_show_remotes(&mut self) -> Result<(), Error> {
+    self.show_remotes = !self.show_remotes;
+    let result = self.reload_branches();
+    if result.is_err() {
+      self.show_remotes = !self.show_remotes;
+    }
+    result
+  }
+
+  fn toggle_sort(&mut self) {
+    let key = match self.sort.key {
+      BranchSort::Name => BranchSort::Age,
+      BranchSort::Age => BranchSort::Name,
+    };
+    self.set_sort(BranchOrder { key, ..self.sort });
+  }
+
+  fn reverse_sort(&mut self) {
+    self.set_sort(BranchOrder { direction: self.sort.direction.reversed(), ..self.sort });
+  }
+
+  /// Reorder the list, keeping the same branch selected.
+  fn set_sort(&mut self, sort: BranchOrder) {
+    self.sort = sort;
+    sort_branches(&mut self.branches, &self.sort);
+    self.ensure_selection_visible();
+  }
+
+  /// Whether the branch hasn't seen a commit in longer than the stale threshold.
+  fn is_stale(&self, item: &BranchItem, now: i64) -> bool {
+    let threshold = i64::try_from(self.stale_after_days).unwrap_or(i64::MAX).saturating_mul(SECONDS_PER_DAY);
+    item.branch.last_commit_time.is_some_and(|time| now.saturating_sub(time) > threshold)
+  }
+
+  pub fn select_previous(&mut self) {
+    let visible = self.visible_indexes();
+    if visible.is_empty() {
       return;
     }
-    self.selected_index -= 1;
+    let selected = self.selected_index();
+    let index = match visible.iter().position(|index| Some(*index) == selected) {
+      Some(0) | None => visible[visible.len() - 1],
+      Some(position) => visible[position - 1],
+    };
+    self.select_index(index);
   }
 
   pub fn select_next(&mut self) {
-    if self.selected_index == self.branches.len() - 1 {
-      self.selected_index = 0;
+    let visible = self.visible_indexes();
+    if visible.is_empty() {
       return;
     }
-    if self.selected_index >= self.branches.len() {
-      self.selected_index = 0;
+    let selected = self.selected_index();
+    let index = match visible.iter().position(|index| Some(*index) == selected) {
+      Some(position) if position + 1 < visible.len() => visible[position + 1],
+      _ => visible[0],
+    };
+    self.select_index(index);
+  }
+
+  /// Move a whole column across when the list is drawn in columns, onto the last branch when the next column is short.
+  pub fn select_column(&mut self, forward: bool) {
+    let visible = self.visible_indexes();
+    let selected = self.selected_index();
+    let Some(position) = visible.iter().position(|index| Some(*index) == selected) else {
+      return;
+    };
+    let rows = self.list_rows.max(1);
+    let target = if forward {
+      let last = visible.len() - 1;
+      if position / rows == last / rows {
+        return;
+      }
+      (position + rows).min(last)
+    } else {
+      let Some(target) = position.checked_sub(rows) else {
+        return;
+      };
+      target
+    };
+    self.select_index(visible[target]);
+  }
+
+  fn start_jump(&mut self) {
+    self.jump = Some(BranchJump { prefix: String::new(), last_input: Instant::now() });
+  }
+
+  fn jump_to_branch(&mut self, c: char) {
+    let Some(jump) = self.jump.as_mut() else {
       return;
+    };
+    jump.prefix.push(c);
+    jump.last_input = Instant::now();
+    // Prefer a branch starting with the prefix, but fall back to one containing it so `/1234` finds `feature/1234`.
+    let prefix = jump.prefix.to_lowercase();
+    let visible = self.visible_indexes();
+    let name_at = |index: &usize| self.branches[*index].branch.name.to_lowercase();
+    let position = visible
+      .iter()
+      .find(|index| name_at(index).starts_with(&prefix))
+      .or_else(|| visible.iter().find(|index| name_at(index).contains(&prefix)))
+      .copied();
+    if let Some(index) = position {
+      self.select_index(index);
     }
-    self.selected_index += 1;
+  }
+
+  fn jump_expired(&self) -> bool {
+    self.jump.as_ref().is_some_and(|jump| jump.last_input.elapsed() > JUMP_TIMEOUT)
+  }
+
+  /// Whether the footer should offer toggling hidden branches, and if so whether they are currently shown.
+  fn hide_toggle(&self) -> Option<bool> {
+    self.has_hidden_rules().then_some(self.show_all)
   }
 
   fn get_selected_branch(&self) -> Option<&BranchItem> {
-    self.branches.get(self.selected_index)
+    self.branches.get(self.selected_index()?)
   }
 
-  fn checkout_selected(&mut self) -> Result<(), Error> {
-    let maybe_selected = self.get_selected_branch();
-    if maybe_selected.is_none() {
+  fn load_selected_details(&mut self) -> Result<(), Error> {
+    let Some(branch) = self.get_selected_branch().map(|selected| selected.branch.clone()) else {
       return Ok(());
-    }
-    let name_to_checkout = maybe_selected.unwrap().branch.name.clone();
-    self.repo.checkout_branch_from_name(&name_to_checkout)?;
-    for existing_branch in self.branches.iter_mut() {
-      existing_branch.branch.is_head = existing_branch.branch.name == name_to_checkout;
-    }
+    };
+    let details = self.query_cache.details(&branch, || self.repo.branch_details(&branch))?;
+    self.details = Some(details);
     Ok(())
   }
 
-  pub fn stage_selected_for_deletion(&mut self, stage: bool) {
-    let maybe_selected = self.branches.get_mut(self.selected_index);
-    if maybe_selected.is_none() {
+  /// Look up the selected branch's open pull request off the UI thread, for the details popup.
+  fn start_pull_request_lookup(&mut self) {
+    self.pull_request = None;
+    let Some(action_tx) = self.action_tx.clone() else {
+      return;
+    };
+    let Some(selected) = self.get_selected_branch() else {
+      return;
+    };
+    let Some((remote, remote_branch)) =
+      selected.branch.upstream.as_ref().and_then(|upstream| Some((upstream.remote.clone()?, upstream.branch_name())))
+    else {
+      return;
+    };
+    let name = selected.branch.name.clone();
+    let remote_branch = String::from(remote_branch);
+    let remote_url = self.repo.remote_url(&remote).ok().flatten().unwrap_or_default();
+    let web_repo = WebRepo::from_remote_url(&remote_url, &self.web_hosts);
+    let lookup = pull_request_lookup(web_repo.as_ref(), self.github_token.as_deref());
+    if !lookup.is_enabled() {
       return;
     }
-    let selected = maybe_selected.unwrap();
-    if selected.branch.is_head {
+    self.pull_request = Some(PullRequestStatus::Loading);
+    // Unlike a background operation nothing waits on this, the popup stays usable and closing it drops the result.
+    tokio::task::spawn_blocking(move || {
+      let action = match lookup.open_pull_request(&remote_branch) {
+        Ok(pull_request) => Action::PullRequestLoaded(name, pull_request),
+        Err(err) => {
+          // Usually a missing or expired token, which shouldn't get in the way of the rest of the details.
+          error!("{}", err);
+          Action::PullRequestUnavailable(name)
+        },
+      };
+      let _ = action_tx.send(action);
+    });
+  }
+
+  /// Whether the details popup is still open on `name`, lookups can finish after it closed or moved on.
+  fn showing_details_of(&self, name: &str) -> bool {
+    self.details.is_some() && self.get_selected_branch().is_some_and(|item| item.branch.name == name)
+  }
+
+  /// Mark the selected branch as the base to compare against, or unmark it if it already is.
+  fn toggle_compare_base(&mut self) {
+    let Some(selected) = self.get_selected_branch() else {
       return;
+    };
+    let name = selected.branch.name.clone();
+    if self.compare_base.as_ref() == Some(&name) {
+      self.compare_base = None;
+    } else {
+      self.compare_base = Some(name);
     }
-    selected.stage_for_deletion(stage);
   }
 
-  pub fn deleted_selected(&mut self) -> Result<(), Error> {
-    let selected = self.branches.get(self.selected_index);
-    if selected.is_none() {
+  fn compare_selected_with_base(&mut self) -> Result<(), Error> {
+    let (Some(base), Some(selected)) = (self.compare_base.clone(), self.get_selected_branch()) else {
       return Ok(());
-    }
-    let delete_result = self.repo.delete_branch(&selected.unwrap().branch);
-    if delete_result.is_err() {
+    };
+    let compare = selected.branch.name.clone();
+    let commits = self.repo.commits_between(&base, &compare)?;
+    let diff_stat = self.repo.diff_stat(&base, &compare)?;
+    self.comparison = Some(BranchComparison::new(base, compare, commits, diff_stat));
+    Ok(())
+  }
+
+  fn load_selected_commit_log(&mut self) -> Result<(), Error> {
+    let Some(selected) = self.get_selected_branch() else {
       return Ok(());
-    }
-    self.branches.remove(self.selected_index);
-    if self.selected_index >= self.branches.len() {
-      self.selected_index -= 1;
-    }
+    };
+    let name = selected.branch.name.clone();
+    let commits = self.repo.commit_log(&name, 0, COMMIT_LOG_PAGE_SIZE, self.show_commit_notes)?;
+    self.commit_log = Some(CommitLog::new(name, commits, COMMIT_LOG_PAGE_SIZE));
     Ok(())
   }
 
-  pub fn delete_staged_branches(&mut self) -> Result<(), Error> {
-    let mut indexes_to_delete: Vec<usize> = Vec::new();
+  /// Fetch the next page of the open log once the selection reaches the last loaded commit.
+  fn load_more_commits(&mut self) -> Result<(), Error> {
+    let Some(commit_log) = self.commit_log.as_mut() else {
+      return Ok(());
+    };
+    if !commit_log.needs_more() {
+      return Ok(());
+    }
+    let commits = self.repo.commit_log(
+      &commit_log.branch,
+      commit_log.commits.len(),
+      COMMIT_LOG_PAGE_SIZE,
+      self.show_commit_notes,
+    )?;
+    commit_log.append(commits, COMMIT_LOG_PAGE_SIZE);
+    Ok(())
+  }
 
-    for branch_index in 0..self.branches.len() {
-      let branch_item = &self.branches[branch_index];
-      if !branch_item.staged_for_deletion {
-        continue;
-      }
-      let del_result = self.repo.delete_branch(&branch_item.branch);
-      if del_result.is_ok() {
-        indexes_to_delete.push(branch_index);
+  /// What can be done with the selected branch in its current state, the same operations the footer offers.
+  fn context_menu_items(&self, selected: &BranchItem) -> Vec<MenuItem> {
+    let branch = &selected.branch;
+    let mut items = Vec::new();
+    if !self.read_only && !self.is_bare && !branch.is_head {
+      items.push(MenuItem::new("c", "Checkout", Action::CheckoutSelectedBranch));
+      items.push(MenuItem::new("⇧ + r", "Rebase HEAD onto this", Action::RebaseOntoSelected));
+    }
+    let is_local = selected.remote.is_none();
+    if !self.read_only {
+      items.push(MenuItem::new("n", "New branch from here", Action::InitNewBranchFromSelected));
+    }
+    if !self.read_only && is_local {
+      items.push(MenuItem::new("e", "Edit description", Action::InitEditDescription));
+      items.push(MenuItem::new("⇧ + e", "Rename", Action::InitRenameBranch));
+    }
+    if !self.read_only && is_local && !branch.is_head && !self.is_protected(&branch.name) {
+      if selected.staged_for_deletion {
+        items.push(MenuItem::new("d", "Delete", Action::DeleteBranch));
+        items.push(MenuItem::new("⇧ + d", "Unstage for deletion", Action::UnstageBranchForDeletion));
       } else {
-        // TODO communicate deletion error
+        items.push(MenuItem::new("d", "Stage for deletion", Action::StageBranchForDeletion));
       }
     }
-
-    // Sort and reverse, so we remove branches starting from the end,
-    // which means we don't need to worry about changing array positions.
-    indexes_to_delete.reverse();
-    for index in indexes_to_delete {
-      self.branches.remove(index);
+    if is_local {
+      items.push(MenuItem::new("enter", "Details", Action::ShowBranchDetails));
+    }
+    items.push(MenuItem::new("l", "Log", Action::ShowCommitLog));
+    items.push(MenuItem::new("v", "View in pager", Action::OpenInPager(branch.name.clone())));
+    if branch.upstream.is_some() {
+      items.push(MenuItem::new("w", "Open in browser", Action::OpenInBrowser));
+      items.push(MenuItem::new("⇧ + w", "Open pull request", Action::OpenPullRequestInBrowser));
     }
-    if self.selected_index >= self.branches.len() {
-      self.selected_index = self.branches.len() - 1
-    } else if self.selected_index != 0 {
-      self.selected_index -= 1
+    items.push(MenuItem::new("y", "Copy name", Action::CopySelectedBranchName));
+    items.push(MenuItem::new("⇧ + y", "Copy sha", Action::CopySelectedSha));
+    match &self.compare_base {
+      Some(base) if *base != branch.name => {
+        items.push(MenuItem::new("b", format!("Compare with {}", base), Action::CompareWithBase));
+      },
+      Some(_) => items.push(MenuItem::new("b", "Unmark compare base", Action::MarkCompareBase)),
+      None => items.push(MenuItem::new("b", "Mark as compare base", Action::MarkCompareBase)),
     }
+    items
+  }
+
+  fn load_reflog(&mut self) -> Result<(), Error> {
+    self.reflog = Some(Reflog::new(self.repo.reflog(REFLOG_LIMIT)?));
     Ok(())
   }
 
-  fn create_branch(&mut self, name: String) -> Result<(), Error> {
-    let branch = GitBranch { name: name.clone(), is_head: false, upstream: None };
-    self.repo.create_branch(&branch)?;
-    self.branches.push(BranchItem::new(branch, true));
-    self.branches.sort_by(|a, b| a.branch.name.cmp(&b.branch.name));
-    self.repo.checkout_branch_from_name(&name)?;
-    for existing_branch in self.branches.iter_mut() {
-      existing_branch.branch.is_head = existing_branch.branch.name == name;
-    }
-    self.selected_index = self.branches.iter().position(|b| b.branch.name == name).unwrap_or(0);
+  fn cherry_pick_selected_commit(&mut self) -> Result<(), Error> {
+    let Some(commit) = self.commit_log.as_ref().and_then(|log| log.selected()).cloned() else {
+      return Ok(());
+    };
+    let head = self.branches.iter().find(|item| item.branch.is_head).map(|item| item.branch.name.clone());
+    let head = head.as_deref();
+    let result = self.repo.cherry_pick(&commit.sha);
+    self.record(format!("Cherry-pick {} onto {}", commit.short_sha(), head.unwrap_or("HEAD")), &result);
+    result?;
+    self.status = Some(format!("Cherry-picked {} onto {}", commit.short_sha(), head.unwrap_or("HEAD")));
     Ok(())
   }
 
-  fn maybe_handle_git_error(&mut self, err: Option<Error>) {
-    if err.is_some() {
-      let error = err.unwrap();
-      error!("{}", error);
-      self.error = Some(error.to_string());
+  /// The selected branch to rebase HEAD onto, unless it's HEAD's own.
+  fn rebase_target(&mut self) -> Option<String> {
+    let selected = self.get_selected_branch()?;
+    let name = selected.branch.name.clone();
+    if selected.branch.is_head {
+      self.status = Some(format!("{} is checked out, select the branch to rebase it onto", name));
+      return None;
     }
+    Some(name)
   }
 
-  fn render_list(&mut self, f: &mut Frame<'_>, area: Rect) {
-    // TODO don't clone, figure out the index to place the pseudo branch in the list
-    let mut branches = self.branches.clone();
-    let input_state = self.branch_input.input_state.clone();
-    if input_state.value.is_some() && self.mode == Mode::Input {
-      let content = input_state.value.unwrap();
-      branches.push(BranchItem {
-        branch: GitBranch::new(content.clone()),
-        staged_for_creation: true,
-        staged_for_deletion: false,
-        is_valid_name: self.branch_input.input_state.is_valid.unwrap_or(false),
-      });
-      branches.sort_by(|a, b| a.branch.name.cmp(&b.branch.name));
-      self.list_state.select(branches.iter().position(|bi| bi.staged_for_creation))
+  fn rebase_onto(&mut self, onto: &str) -> Result<(), Error> {
+    let head = self.branches.iter().find(|item| item.branch.is_head).map(|item| item.branch.name.clone());
+    let head = head.as_deref().unwrap_or("HEAD");
+    let result = self.repo.rebase(onto);
+    self.record(format!("Rebase {} onto {}", head, onto), &result);
+    // Either way HEAD moved, onto new commits or part way through them.
+    let reloaded = self.reload_branches();
+    result?;
+    reloaded?;
+    self.status = Some(format!("Rebased {} onto {}", head, onto));
+    Ok(())
+  }
+
+  /// Report how `git rebase -i` ended once it hands the terminal back.
+  fn finish_interactive_rebase(&mut self, onto: &str, success: bool) -> Result<(), Error> {
+    self.reload_branches()?;
+    let result = if self.rebase_in_progress {
+      Err(Error::RebaseConflict(String::from(onto)))
+    } else if success {
+      Ok(())
     } else {
-      self.list_state.select(Some(self.selected_index));
-    }
+      // An emptied todo list or a refusal to start, like with local changes in the way. Either way nothing changed.
+      Err(Error::Git(format!("`git rebase -i {}` exited without rebasing anything", onto)))
+    };
+    self.record(format!("Interactive rebase onto {}", onto), &result);
+    result?;
+    self.status = Some(format!("Rebased onto {}", onto));
+    Ok(())
+  }
 
-    let render_items: Vec<ListItem> = branches.iter().map(|git_branch| git_branch.render()).collect();
-    let list = List::new(render_items)
-      .block(Block::default().title("Local Branches").borders(Borders::ALL))
-      .style(Style::default().fg(Color::White))
-      .highlight_style(Style::default().add_modifier(Modifier::BOLD))
-      .highlight_symbol("→")
-      .repeat_highlight_symbol(true);
+  /// Check out `commit` on no branch, for looking at the repository as it was.
+  fn checkout_detached(&mut self, commit: &GitCommit) -> Result<(), Error> {
+    let previous_tip = self.head_tip();
+    let result = self.repo.checkout_detached(&commit.sha);
+    self.record(format!("Checkout {} detached", commit.short_sha()), &result);
+    result?;
+    for item in self.branches.iter_mut() {
+      item.branch.is_head = false;
+    }
+    self.refresh_head_sync();
+    self.sync_submodules(previous_tip)
+  }
 
-    f.render_stateful_widget(list, area, &mut self.list_state);
+  fn checkout_selected(&mut self) -> Result<(), Error> {
+    match self.selected_index() {
+      Some(index) => self.checkout_at(index),
+      None => Ok(()),
+    }
   }
 
-  fn render_error(&mut self, f: &mut Frame<'_>, area: Rect) {
-    if self.error.is_none() {
-      return;
+  /// Checkout the branch at `index` in `branches`, which may be hidden when it was picked by name.
+  fn checkout_at(&mut self, index: usize) -> Result<(), Error> {
+    let item = &self.branches[index];
+    if let Some(remote_branch) = item.remote.clone() {
+      return self.checkout_remote(&remote_branch);
     }
-    let error_message = self.error.as_ref().unwrap().clone();
-    let text = Text::from(error_message);
-    let component = Paragraph::new(text)
-      .block(Block::bordered().title("Error"))
-      .style(Style::from(Color::Red))
-      .wrap(Wrap { trim: true });
-    f.render_widget(component, area);
+    let name_to_checkout = item.branch.name.clone();
+    let previous_tip = self.head_tip();
+    let result = self.repo.checkout_branch_from_name(&name_to_checkout);
+    self.record(format!("Checkout {}", name_to_checkout), &result);
+    result?;
+    self.mark_head(&name_to_checkout);
+    self.sync_submodules(previous_tip)
   }
-}
 
-impl Component for BranchList {
-  fn handle_key_events(&mut self, key: KeyEvent) -> color_eyre::Result<Option<Action>> {
-    self.clear_error();
+  /// Checkout a remote branch by creating a local branch tracking it, which then takes its place in the list.
+  fn checkout_remote(&mut self, remote_branch: &GitRemoteBranch) -> Result<(), Error> {
+    let local_name = String::from(remote_branch.branch_name());
+    let previous_tip = self.head_tip();
+    let result = self.repo.checkout_remote_branch(remote_branch);
+    self.record(format!("Checkout {} tracking {}", local_name, remote_branch.name), &result);
+    result?;
+    self.reload_branches()?;
+    self.selected_name = Some(local_name);
+    self.sync_submodules(previous_tip)
+  }
 
-    if self.mode == Mode::Input {
-      return Ok(Some(Action::UpdateNewBranchName(key)));
+  /// Checkout `name` after the user confirmed losing the local changes in the way.
+  fn force_checkout(&mut self, name: &str) -> Result<(), Error> {
+    let previous_tip = self.head_tip();
+    let result = self.repo.force_checkout_branch_from_name(name);
+    self.record(format!("Force checkout {}", name), &result);
+    result?;
+    self.mark_head(name);
+    self.sync_submodules(previous_tip)
+  }
+
+  /// The commit the checked out branch is at, to compare with after a checkout.
+  fn head_tip(&self) -> Option<String> {
+    self.branches.iter().find(|item| item.branch.is_head).and_then(|item| item.branch.tip_sha.clone())
+  }
+
+  /// Git leaves submodules where they were on a checkout, so after one that added or removed them from
+  /// `previous_tip`, update them if configured to or warn that they may not match the branch.
+  fn sync_submodules(&mut self, previous_tip: Option<String>) -> Result<(), Error> {
+    // Nothing to compare with coming from a detached HEAD or an unborn branch.
+    let Some(previous_tip) = previous_tip else {
+      return Ok(());
+    };
+    if !self.repo.gitmodules_changed(&previous_tip)? {
+      return Ok(());
     }
-    match key {
-      KeyEvent { code: KeyCode::Down, modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
-        Ok(Some(Action::SelectNextBranch))
-      },
-      KeyEvent { code: KeyCode::Up, modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
-        Ok(Some(Action::SelectPreviousBranch))
-      },
-      KeyEvent { code: KeyCode::Char('c' | 'C'), modifiers: KeyModifiers::SHIFT, kind: _, state: _ } => {
-        Ok(Some(Action::InitNewBranch))
-      },
-      KeyEvent { code: KeyCode::Char('c' | 'C'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
-        Ok(Some(Action::CheckoutSelectedBranch))
-      },
-      KeyEvent { code: KeyCode::Char('d' | 'D'), modifiers: KeyModifiers::SHIFT, kind: _, state: _ } => {
-        Ok(Some(Action::UnstageBranchForDeletion))
-      },
-      KeyEvent { code: KeyCode::Char('d' | 'D'), modifiers: KeyModifiers::CONTROL, kind: _, state: _ } => {
-        Ok(Some(Action::DeleteStagedBranches))
+    if !self.update_submodules {
+      self.status =
+        Some(String::from("Submodules changed, run `git submodule update --init --recursive` to bring them in line"));
+      return Ok(());
+    }
+    // A recursive update can clone whole repositories, so keep it off the UI thread like a fetch.
+    self.run_in_background(
+      "Updating submodules",
+      String::from("Update submodules"),
+      |repo, cancel| repo.update_submodules(cancel),
+      |()| Action::SubmodulesUpdated,
+    )
+  }
+
+  /// Hold a checkout that was refused because of local changes for confirmation, or report any other failure.
+  fn handle_checkout_error(&mut self, err: Option<Error>) -> Option<Action> {
+    match err {
+      // A refused checkout of a remote branch never created the local branch, so there's nothing to force.
+      Some(Error::CheckoutConflict(name))
+        if !self.read_only && self.branches.iter().any(|item| item.remote.is_none() && item.branch.name == name) =>
+      {
+        self.pending_force_checkout = Some(name);
+        Some(Action::SetMode(Mode::Overlay))
       },
-      KeyEvent { code: KeyCode::Char('d' | 'D'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
-        if self.get_selected_branch().is_none() {
-          return Ok(None);
-        }
-        let selected = self.get_selected_branch().unwrap();
-        if selected.staged_for_deletion {
-          return Ok(Some(Action::DeleteBranch));
-        }
-        Ok(Some(Action::StageBranchForDeletion))
+      err => {
+        self.maybe_handle_git_error(err);
+        None
       },
-      _ => Ok(None),
     }
   }
 
-  fn update(&mut self, action: Action) -> color_eyre::Result<Option<Action>> {
-    match action {
-      Action::SelectPreviousBranch => {
-        self.select_previous();
-        Ok(None)
-      },
-      Action::SelectNextBranch => {
-        self.select_next();
-        Ok(None)
+  fn mark_head(&mut self, name: &str) {
+    for existing_branch in self.branches.iter_mut() {
+      existing_branch.branch.is_head = existing_branch.branch.name == name;
+    }
+    self.refresh_head_sync();
+  }
+
+  fn set_branch_description(&mut self, name: &str, description: &str) -> Result<(), Error> {
+    let result = self.repo.set_branch_description(name, description);
+    let change = if description.is_empty() { "Remove description of" } else { "Describe" };
+    self.record(format!("{} {}", change, name), &result);
+    result?;
+    if let Some(item) = self.branches.iter_mut().find(|item| item.branch.name == name) {
+      item.branch.description = (!description.is_empty()).then(|| String::from(description));
+    }
+    Ok(())
+  }
+
+  /// Rename `from` to `to`, then hold the push that renames its upstream too for confirmation. Branches without an
+  /// upstream on a remote, or whose upstream is gone or already named `to`, are done once renamed locally.
+  fn rename_branch(&mut self, from: &str, to: &str) -> Result<(), Error> {
+    if from == to {
+      return Ok(());
+    }
+    let upstream = self
+      .branches
+      .iter()
+      .find(|item| item.remote.is_none() && item.branch.name == from)
+      .filter(|item| !item.branch.upstream_gone)
+      .and_then(|item| item.branch.upstream.clone());
+    let result = self.repo.rename_branch(from, to);
+    self.record(format!("Rename {} to {}", from, to), &result);
+    result?;
+    if self.compare_base.as_deref() == Some(from) {
+      self.compare_base = Some(String::from(to));
+    }
+    self.selected_name = Some(String::from(to));
+    self.reload_branches()?;
+    match upstream {
+      Some(upstream) if upstream.remote.is_some() && upstream.branch_name() != to => {
+        self.pending_rename_push = Some((String::from(to), upstream));
       },
-      Action::InitNewBranch => {
-        self.mode = Mode::Input;
-        self.branch_input.init_style();
-        Ok(Some(Action::StartInputMode))
+      _ => self.status = Some(format!("Renamed {} to {}", from, to)),
+    }
+    Ok(())
+  }
+
+  /// Push the rename held for confirmation in the background, its upstream is deleted from the remote with it.
+  fn push_rename(&mut self) -> Result<(), Error> {
+    let Some((branch, upstream)) = self.pending_rename_push.take() else {
+      return Ok(());
+    };
+    let remote = upstream.remote.clone().unwrap_or_default();
+    let description = format!("Push {} to {} and delete {}", branch, remote, upstream.name);
+    let (to_push, replaced) = (branch.clone(), upstream.name.clone());
+    self.run_in_background(
+      "Pushing",
+      description,
+      move |repo, cancel| repo.push_renamed_branch(&to_push, &upstream, cancel),
+      move |()| Action::RenamePushed(branch, replaced),
+    )
+  }
+
+  fn quick_checkout(&mut self, partial: &str) -> Result<(), Error> {
+    let name = resolve_branch_prefix(self.branches.iter().map(|item| item.branch.name.as_str()), partial)?;
+    let Some(index) = self.branches.iter().position(|item| item.branch.name == name) else {
+      return Ok(());
+    };
+    self.select_index(index);
+    self.checkout_at(index)
+  }
+
+  /// Switch back to the branch checked out before the current one, found in the reflog like `git checkout -` does.
+  fn checkout_previous(&mut self) -> Result<(), Error> {
+    let reflog = self.repo.reflog(REFLOG_LIMIT)?;
+    let previous = reflog.iter().find_map(|entry| entry.checkout_from());
+    // Coming back from a detached HEAD leaves a sha there, which only the log can check out again.
+    let index =
+      previous.and_then(|name| self.branches.iter().position(|item| item.remote.is_none() && item.branch.name == name));
+    let Some(index) = index else {
+      self.status = Some(String::from("No previous branch to switch back to"));
+      return Ok(());
+    };
+    self.select_index(index);
+    self.checkout_at(index)
+  }
+
+  /// Local branches checked out before the current one, most recent first, found in the reflog so checkouts from
+  /// outside this session count too.
+  fn recent_branches(&self) -> Result<Vec<String>, Error> {
+    let reflog = self.repo.reflog(REFLOG_LIMIT)?;
+    let mut recent: Vec<String> = Vec::new();
+    for name in reflog.iter().filter_map(|entry| entry.checkout_from()) {
+      let is_listed =
+        self.branches.iter().any(|item| item.remote.is_none() && !item.branch.is_head && item.branch.name == name);
+      if is_listed && !recent.iter().any(|seen| seen == name) {
+        recent.push(String::from(name));
+      }
+      if recent.len() == RECENT_BRANCH_LIMIT {
+        break;
+      }
+    }
+    Ok(recent)
+  }
+
+  /// Load the branches again after git changed them behind our back, keeping the selection and staged deletions.
+  fn reload_branches(&mut self) -> Result<(), Error> {
+    // The items are rebuilt, so staging is carried over by name. Sorting and filtering keep the items themselves.
+    let staged: HashSet<String> =
+      self.branches.iter().filter(|item| item.staged_for_deletion).map(|item| item.branch.name.clone()).collect();
+    let local_branches = self.repo.local_branches()?;
+    let remote_items = if self.show_remotes { self.remote_only_items(&local_branches)? } else { Vec::new() };
+    self.branches = local_branches
+      .into_iter()
+      .map(|branch| {
+        let mut item = BranchItem::new(branch, true);
+        item.stage_for_deletion(staged.contains(&item.branch.name));
+        item
+      })
+      .chain(remote_items)
+      .collect();
+    sort_branches(&mut self.branches, &self.sort);
+    self.ensure_selection_visible();
+    self.query_cache.retain_current(self.branches.iter().map(|item| &item.branch));
+    self.rebase_in_progress = self.repo.rebase_in_progress()?;
+    self.refresh_head_sync();
+    self.start_commit_counts();
+    Ok(())
+  }
+
+  /// Entries for the remote branches that none of `local_branches` track, from the shown remote when there is one.
+  fn remote_only_items(&self, local_branches: &[GitBranch]) -> Result<Vec<BranchItem>, Error> {
+    let tracked: HashSet<&str> =
+      local_branches.iter().filter_map(|branch| Some(branch.upstream.as_ref()?.name.as_str())).collect();
+    let remotes = self.repo.remotes()?;
+    let items = self
+      .repo
+      .remote_branches()?
+      .into_iter()
+      .filter(|branch| !tracked.contains(branch.name.as_str()))
+      .filter_map(|branch| {
+        let remote_branch = GitRemoteBranch::with_remotes(branch.name.clone(), &remotes);
+        let is_shown = self.shown_remote.is_none() || remote_branch.remote == self.shown_remote;
+        is_shown.then(|| BranchItem::remote(branch, remote_branch))
+      })
+      .collect();
+    Ok(items)
+  }
+
+  /// The range `item`'s commit count is taken over, what it has beyond the default branch or everything without one.
+  fn commit_count_range(&self, item: &BranchItem) -> Option<String> {
+    let tip = item.branch.tip_sha.as_deref()?;
+    match &self.commit_count_base {
+      Some(base) => Some(format!("{}..{}", base, tip)),
+      None => Some(String::from(tip)),
+    }
+  }
+
+  /// Count the commits of every branch that hasn't been counted yet off the UI thread, a count walks the branch's
+  /// whole history so they're too slow to take while loading.
+  fn start_commit_counts(&mut self) {
+    if !self.show_commit_counts {
+      return;
+    }
+    let Some(action_tx) = self.action_tx.clone() else {
+      return;
+    };
+    self.commit_count_base = self.detected_default_branch().and_then(|base| {
+      let item = self.branches.iter().find(|item| item.remote.is_none() && item.branch.name == base)?;
+      item.branch.tip_sha.clone()
+    });
+    let ranges: HashSet<String> = self
+      .branches
+      .iter()
+      .filter_map(|item| self.commit_count_range(item))
+      .filter(|range| self.query_cache.commit_count(range).is_none())
+      .collect();
+    if ranges.is_empty() {
+      return;
+    }
+    let repo = match self.repo.try_clone() {
+      Ok(repo) => repo,
+      Err(err) => {
+        error!("Failed to open the repo to count commits: {}", err);
+        return;
+      },
+    };
+    if let Some(previous) = self.commit_counts_job.replace(CancellationToken::new()) {
+      previous.cancel();
+    }
+    let cancel = self.commit_counts_job.clone().unwrap_or_default();
+    tokio::task::spawn_blocking(move || {
+      // Whatever was counted before a cancel is still sent, so none of it has to be counted again.
+      let counts = ranges
+        .into_iter()
+        .take_while(|_| !cancel.is_cancelled())
+        .filter_map(|range| {
+          repo
+            .count_commits(&range)
+            .map(|count| (range.clone(), count))
+            .map_err(|err| error!("Failed to count commits in {}: {}", range, err))
+            .ok()
+        })
+        .collect();
+      let _ = action_tx.send(Action::CommitCountsLoaded(counts));
+    });
+  }
+
+  /// The default branch, detected on first use and kept until the config changes or a fetch could have moved the
+  /// remote's HEAD.
+  fn detected_default_branch(&mut self) -> Option<String> {
+    if let Some(detected) = &self.detected_base {
+      return detected.clone();
+    }
+    match detect_default_branch(&*self.repo, self.default_base.as_deref()) {
+      Ok(detected) => {
+        self.detected_base = Some(detected.clone());
+        detected
+      },
+      // Not cached, so it's tried again next time.
+      Err(err) => {
+        error!("Failed to detect the default branch: {}", err);
+        None
+      },
+    }
+  }
+
+  /// Work out how the checked out branch compares with its upstream, nothing is shown without one. Unless it's cached
+  /// for HEAD's tip the comparison walks both histories, so it's done off the UI thread like commit counts.
+  fn refresh_head_sync(&mut self) {
+    let Some(branch) = self.branches.iter().find(|item| item.branch.is_head).map(|item| item.branch.clone()) else {
+      self.head_sync = None;
+      return;
+    };
+    let (Some(upstream), Some(tip_sha)) = (branch.upstream.clone(), branch.tip_sha.clone()) else {
+      self.head_sync = None;
+      return;
+    };
+    if branch.upstream_gone {
+      self.head_sync = Some(HeadSync { branch: branch.name, ahead_behind: None });
+      return;
+    }
+    if let Some(ahead_behind) = self.query_cache.ahead_behind(&branch) {
+      self.head_sync = Some(HeadSync { branch: branch.name, ahead_behind: Some(ahead_behind) });
+      return;
+    }
+    // The last comparison of the same branch stays up until the new one arrives, rather than flickering off.
+    if self.head_sync.as_ref().is_some_and(|head_sync| head_sync.branch != branch.name) {
+      self.head_sync = None;
+    }
+    let Some(action_tx) = self.action_tx.clone() else {
+      return;
+    };
+    let repo = match self.repo.try_clone() {
+      Ok(repo) => repo,
+      Err(err) => {
+        error!("Failed to open the repo to compare {} with its upstream: {}", branch.name, err);
+        return;
+      },
+    };
+    tokio::task::spawn_blocking(move || {
+      match repo.ahead_behind(&branch.name, &upstream.name) {
+        Ok(ahead_behind) => {
+          let _ = action_tx.send(Action::AheadBehindLoaded(branch.name, tip_sha, ahead_behind));
+        },
+        Err(err) => error!("Failed to compare {} with {}: {}", branch.name, upstream.name, err),
+      }
+    });
+  }
+
+  /// Run `operation` on its own handle to the repo off the UI thread, with the busy spinner labelled `label` up until
+  /// it finishes or is cancelled. The action `on_success` makes of its result is sent when it succeeds, otherwise the
+  /// error is.
+  fn run_in_background<T: Send + 'static>(
+    &mut self,
+    label: &str,
+    description: String,
+    operation: impl FnOnce(&dyn GitRepo, &CancellationToken) -> Result<T, Error> + Send + 'static,
+    on_success: impl FnOnce(T) -> Action + Send + 'static,
+  ) -> Result<(), Error> {
+    let Some(action_tx) = self.action_tx.clone() else {
+      return Ok(());
+    };
+    let repo = self.repo.try_clone()?;
+    let cancel = CancellationToken::new();
+    self.operation = Some(cancel.clone());
+    // Sent before the work starts so it can't arrive after the operation has already ended.
+    let _ = action_tx.send(Action::StartBusy(String::from(label)));
+    tokio::task::spawn_blocking(move || {
+      let result = operation(&*repo, &cancel);
+      let _ = action_tx.send(Action::RecordOperation(HistoryEntry::new(description, &result)));
+      let _ = action_tx.send(Action::EndBusy);
+      let _ = action_tx.send(match result {
+        Ok(value) => on_success(value),
+        Err(Error::Cancelled) => Action::OperationCancelled,
+        Err(err) => {
+          error!("{}", err);
+          Action::Error(err.to_string())
+        },
+      });
+    });
+    Ok(())
+  }
+
+  /// The default branch if `deletion` would delete it, and it hasn't been confirmed yet.
+  fn unconfirmed_default_deletion(&mut self, deletion: &Action) -> Option<String> {
+    if self.default_deletion_confirmed {
+      return None;
+    }
+    let default_branch = self.detected_default_branch()?;
+    let deletes_default = match deletion {
+      Action::DeleteBranch => self.get_selected_branch().is_some_and(|item| item.branch.name == default_branch),
+      _ => self.branches.iter().any(|item| item.staged_for_deletion && item.branch.name == default_branch),
+    };
+    deletes_default.then_some(default_branch)
+  }
+
+  fn render_deletion_confirmation(&self, f: &mut Frame<'_>, area: Rect) {
+    let Some((_, default_branch)) = &self.pending_deletion else {
+      return;
+    };
+    let text = format!(
+      "{} is the repository's default branch. Other branches, tooling and your teammates expect it to exist, deleting \
+       it is almost always a mistake.\n\ny: Delete it anyway | any other key: Cancel",
+      default_branch
+    );
+    let popup_area = centered_rect(area, 60, 8);
+    let popup = Paragraph::new(text)
+      .block(Block::bordered().title("Delete the default branch?"))
+      .style(Style::default().fg(Color::Red))
+      .wrap(Wrap { trim: true });
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup, popup_area);
+  }
+
+  fn render_force_checkout_confirmation(&self, f: &mut Frame<'_>, area: Rect) {
+    let Some(name) = &self.pending_force_checkout else {
+      return;
+    };
+    let text = format!(
+      "Checking out {} would overwrite uncommitted changes. Forcing it throws those changes away for good, commit or \
+       stash them first to keep them.\n\ny: Discard changes and checkout | any other key: Cancel",
+      name
+    );
+    let popup_area = centered_rect(area, 60, 8);
+    let popup = Paragraph::new(text)
+      .block(Block::bordered().title("Discard local changes?"))
+      .style(Style::default().fg(Color::Red))
+      .wrap(Wrap { trim: true });
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup, popup_area);
+  }
+
+  fn render_rename_push_confirmation(&self, f: &mut Frame<'_>, area: Rect) {
+    let Some((branch, upstream)) = &self.pending_rename_push else {
+      return;
+    };
+    let remote = upstream.remote.as_deref().unwrap_or_default();
+    let text = format!(
+      "{remote} still has {branch} as {old}. Pushing it as {branch} and deleting {old} keeps the two in step. Commits \
+       on {old} that {branch} doesn't have are lost from {remote}, and anyone else using it will have to switch \
+       over.\n\ny: Push and delete | any other key: Leave {remote} as it is",
+      old = upstream.name,
+    );
+    let popup_area = centered_rect(area, 60, 9);
+    let popup = Paragraph::new(text)
+      .block(Block::bordered().title(format!("Rename on {} too?", remote)))
+      .style(Style::default().fg(Color::Yellow))
+      .wrap(Wrap { trim: true });
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup, popup_area);
+  }
+
+  fn render_detached_checkout_confirmation(&self, f: &mut Frame<'_>, area: Rect) {
+    let Some(commit) = &self.pending_detached_checkout else {
+      return;
+    };
+    let text = format!(
+      "Checking out {} ({}) detaches HEAD, leaving it on no branch. Commits made there are easily lost, create a \
+       branch to keep them.\n\ny: Checkout | any other key: Cancel",
+      commit.short_sha(),
+      commit.subject
+    );
+    let popup_area = centered_rect(area, 60, 8);
+    let popup = Paragraph::new(text)
+      .block(Block::bordered().title("Detach HEAD?"))
+      .style(Style::default().fg(Color::Yellow))
+      .wrap(Wrap { trim: true });
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup, popup_area);
+  }
+
+  /// Add a finished git operation to the session history.
+  fn record(&self, description: String, result: &Result<(), Error>) {
+    if let Some(action_tx) = &self.action_tx {
+      let _ = action_tx.send(Action::RecordOperation(HistoryEntry::new(description, result)));
+    }
+  }
+
+  pub fn is_protected(&self, name: &str) -> bool {
+    self.protected_patterns.iter().any(|pattern| pattern.is_match(name))
+  }
+
+  pub fn stage_selected_for_deletion(&mut self, stage: bool) {
+    let Some(index) = self.selected_index() else {
+      return;
+    };
+    let name = self.branches[index].branch.name.clone();
+    if stage && self.is_protected(&name) {
+      self.status = Some(format!("{} is protected from deletion", name));
+      return;
+    }
+    let selected = &mut self.branches[index];
+    if selected.branch.is_head {
+      return;
+    }
+    selected.stage_for_deletion(stage);
+  }
+
+  /// Copy the selected branch's name, or the sha it points at, to the clipboard.
+  fn copy_selected(&mut self, sha: bool) {
+    let Some(selected) = self.get_selected_branch().map(|item| item.branch.clone()) else {
+      return;
+    };
+    let text = if !sha {
+      selected.name
+    } else {
+      match selected.tip_sha {
+        Some(tip_sha) if self.copy_full_sha => tip_sha,
+        Some(tip_sha) => String::from(short_sha(&tip_sha)),
+        None => {
+          self.status = Some(format!("The sha of {} isn't known yet, refresh to load it", selected.name));
+          return;
+        },
+      }
+    };
+    match clipboard::copy(&text) {
+      Ok(()) => self.status = Some(format!("Copied {} to the clipboard", text)),
+      Err(err) => self.set_error(format!("Failed to copy to the clipboard: {}", err)),
+    }
+  }
+
+  /// Open the selected branch's page on its upstream's code review site, or the page for a new pull request from it.
+  fn open_selected_in_browser(&mut self, pull_request: bool) -> Result<(), Error> {
+    let Some(selected) = self.get_selected_branch() else {
+      return Ok(());
+    };
+    let Some(upstream) = selected.branch.upstream.as_ref() else {
+      self.status = Some(format!("{} has no upstream to open", selected.branch.name));
+      return Ok(());
+    };
+    let Some(remote) = upstream.remote.as_deref() else {
+      self.status = Some(format!("{} tracks a local branch, there's nothing to open", selected.branch.name));
+      return Ok(());
+    };
+    let remote_branch = upstream.branch_name();
+    let remote_url = self.repo.remote_url(remote)?.unwrap_or_default();
+    let Some(web_repo) = WebRepo::from_remote_url(&remote_url, &self.web_hosts) else {
+      self.status = Some(format!("Don't know how to open {} in a browser, see web_hosts in the config", remote));
+      return Ok(());
+    };
+    let url =
+      if pull_request { web_repo.new_pull_request_url(remote_branch) } else { web_repo.branch_url(remote_branch) };
+    open_in_browser(&url).map_err(|err| Error::Git(format!("Failed to open {}: {}", url, err)))?;
+    self.status = Some(format!("Opened {}", url));
+    Ok(())
+  }
+
+  /// Stage every branch whose upstream was deleted, the usual leftovers of merged pull requests.
+  pub fn stage_gone_branches(&mut self) {
+    let mut staged = 0;
+    for index in 0..self.branches.len() {
+      let item = &self.branches[index];
+      if !item.branch.upstream_gone || item.branch.is_head || self.is_protected(&item.branch.name) {
+        continue;
+      }
+      self.branches[index].stage_for_deletion(true);
+      staged += 1;
+    }
+    self.status = Some(match staged {
+      0 => String::from("No branches with a gone upstream to stage"),
+      1 => String::from("Staged 1 branch with a gone upstream"),
+      n => format!("Staged {} branches with a gone upstream", n),
+    });
+  }
+
+  pub fn deleted_selected(&mut self) -> Result<(), Error> {
+    let Some(index) = self.selected_index() else {
+      return Ok(());
+    };
+    let selected = &self.branches[index];
+    if self.is_protected(&selected.branch.name) {
+      self.status = Some(format!("{} is protected from deletion", selected.branch.name));
+      return Ok(());
+    }
+    let delete_result = self.repo.delete_branch(&selected.branch);
+    self.record(format!("Delete {}", selected.branch.name), &delete_result);
+    match delete_result {
+      // Already gone, which is all the delete was for.
+      Err(Error::BranchNotFound(name)) => {
+        self.reconcile_missing_branches(&[name]);
+        return Ok(());
+      },
+      Err(err) => {
+        self.set_error(err.to_string());
+        return Ok(());
+      },
+      Ok(()) => {},
+    }
+    // The next branch slides into its place and takes the selection.
+    self.branches.remove(index);
+    self.select_index(index.min(self.branches.len().saturating_sub(1)));
+    self.ensure_selection_visible();
+    Ok(())
+  }
+
+  pub fn delete_staged_branches(&mut self) -> Result<(), Error> {
+    let mut indexes_to_delete: Vec<usize> = Vec::new();
+    let mut failures: Vec<String> = Vec::new();
+    let mut missing: Vec<String> = Vec::new();
+
+    for branch_index in 0..self.branches.len() {
+      let branch_item = &self.branches[branch_index];
+      if !branch_item.staged_for_deletion || self.is_protected(&branch_item.branch.name) {
+        continue;
+      }
+      let del_result = self.repo.delete_branch(&branch_item.branch);
+      self.record(format!("Delete {}", branch_item.branch.name), &del_result);
+      match del_result {
+        Ok(()) => indexes_to_delete.push(branch_index),
+        Err(Error::BranchNotFound(name)) => missing.push(name),
+        Err(err) => failures.push(err.to_string()),
+      }
+    }
+    if !failures.is_empty() {
+      self.set_error(failures.join("\n"));
+    }
+
+    // Sort and reverse, so we remove branches starting from the end,
+    // which means we don't need to worry about changing array positions.
+    // A deleted selection moves on to the next branch that's left, or the one before it at the end of the list.
+    if let Some(selected) = self.selected_index().filter(|index| indexes_to_delete.contains(index)) {
+      let visible = self.visible_indexes();
+      let mut remaining = visible.iter().filter(|index| !indexes_to_delete.contains(index));
+      let replacement = remaining.clone().find(|index| **index > selected).or_else(|| remaining.next_back());
+      self.selected_name = replacement.map(|index| self.branches[*index].branch.name.clone());
+    }
+    indexes_to_delete.reverse();
+    for index in indexes_to_delete {
+      self.branches.remove(index);
+    }
+    if !missing.is_empty() {
+      self.reconcile_missing_branches(&missing);
+    }
+    Ok(())
+  }
+
+  /// Create a branch at `start_point`, or HEAD, and select it, checking it out unless the user wants to stay where
+  /// they are.
+  fn create_branch(&mut self, name: String, start_point: Option<&str>, checkout: bool) -> Result<(), Error> {
+    let branch = GitBranch::new(name.clone());
+    let result = match start_point {
+      Some(start_point) => self.repo.create_branch_from(&branch, start_point),
+      None => self.repo.create_branch(&branch),
+    };
+    let description = match start_point {
+      Some(start_point) => format!("Create {} from {}", name, start_point),
+      None => format!("Create {}", name),
+    };
+    self.record(description, &result);
+    result?;
+    self.branches.push(BranchItem::new(branch, true));
+    sort_branches(&mut self.branches, &self.sort);
+    // Sorting moved everything after the new branch, select it now so a failed checkout can't leave the selection on
+    // whichever branch slid into the old index.
+    self.selected_name = Some(name.clone());
+    if !checkout {
+      return Ok(());
+    }
+    let result = self.repo.checkout_branch_from_name(&name);
+    self.record(format!("Checkout {}", name), &result);
+    result?;
+    self.mark_head(&name);
+    Ok(())
+  }
+
+  /// Create and checkout a branch, then commit nothing onto it so it starts from a tip of its own.
+  fn create_branch_with_empty_commit(&mut self, name: String) -> Result<(), Error> {
+    let message = self.empty_commit_message.clone().unwrap_or_default().replace("{branch}", &name);
+    self.create_branch(name.clone(), None, true)?;
+    let result = self.repo.commit_empty(&message);
+    self.record(format!("Empty commit on {}", name), &result);
+    result?;
+    // Picks up the new tip.
+    self.reload_branches()
+  }
+
+  fn is_listed_locally(&self, name: &str) -> bool {
+    self.branches.iter().any(|item| item.remote.is_none() && item.branch.name == name)
+  }
+
+  /// Branches deleted outside of the app since the list loaded, from another terminal or tool. Reload so the list
+  /// matches git again, instead of reporting a failure about branches that are just gone.
+  fn reconcile_missing_branches(&mut self, names: &[String]) {
+    if let Err(err) = self.reload_branches() {
+      error!("{}", err);
+      self.set_error(err.to_string());
+      return;
+    }
+    self.status = Some(format!("{} was deleted elsewhere, reloaded the branches", names.join(", ")));
+  }
+
+  fn maybe_handle_git_error(&mut self, err: Option<Error>) {
+    if let Some(Error::BranchNotFound(name)) = &err {
+      if self.is_listed_locally(name) {
+        self.reconcile_missing_branches(&[name.clone()]);
+        return;
+      }
+    }
+    if err.is_some() {
+      let error = err.unwrap();
+      error!("{}", error);
+      self.set_error(error.to_string());
+    }
+  }
+
+  /// Move the window of rendered rows just enough to keep `selected` in it. Run on every draw, so after a resize the
+  /// selection stays on screen and a taller list fills up from the top rather than leaving blank rows.
+  fn scroll_list_to(&mut self, selected: Option<usize>, total: usize, height: usize) {
+    if let Some(selected) = selected {
+      if selected < self.list_offset {
+        self.list_offset = selected;
+      } else if selected >= self.list_offset + height {
+        self.list_offset = selected + 1 - height;
+      }
+    }
+    self.list_offset = self.list_offset.min(total.saturating_sub(height));
+  }
+
+  /// Like [`Self::scroll_list_to`] a whole column of `rows` at a time, so branches stay in the column they were in.
+  fn scroll_columns_to(&mut self, selected: Option<usize>, total: usize, rows: usize, columns: usize) {
+    self.list_offset /= rows;
+    self.scroll_list_to(selected.map(|row| row / rows), total.div_ceil(rows), columns);
+    self.list_offset *= rows;
+  }
+
+  /// The branch being typed in input mode and the row it sorts into among `visible`, after any branch with the same
+  /// name, so it can be drawn in place without copying the list.
+  fn pending_branch(&self, visible: &[usize]) -> Option<(usize, BranchItem)> {
+    if self.mode != Mode::BranchInput {
+      return None;
+    }
+    let input_state = &self.branch_input.input_state;
+    let name = input_state.value.as_ref()?;
+    // A new branch has no commits of its own, so by age it goes with the others whose age isn't known.
+    let branch = GitBranch::new(name.clone());
+    let position =
+      visible.partition_point(|index| self.sort.compare(&self.branches[*index].branch, &branch) != Ordering::Greater);
+    let pending = BranchItem {
+      branch,
+      staged_for_creation: true,
+      staged_for_deletion: false,
+      is_valid_name: input_state.is_valid.unwrap_or(false),
+      remote: None,
+    };
+    Some((position, pending))
+  }
+
+  fn render_list(&mut self, f: &mut Frame<'_>, area: Rect) {
+    // A branch being named still goes in the list, so it's clear where it will land. A lone remote branch is listed
+    // too, it's something to check out.
+    if self.branches.len() <= 1
+      && self.branches.iter().all(|item| item.remote.is_none())
+      && self.mode != Mode::BranchInput
+    {
+      self.render_empty_state(f, area);
+      return;
+    }
+    let visible = self.visible_indexes();
+    let pending = self.pending_branch(&visible);
+    let total = visible.len() + usize::from(pending.is_some());
+    let selected = match &pending {
+      Some((position, _)) => Some(*position),
+      None => {
+        let selected = self.selected_index();
+        visible.iter().position(|index| Some(*index) == selected)
+      },
+    };
+    let border_size = if self.compact { 0 } else { 2 };
+    let height = usize::from(area.height.saturating_sub(border_size)).max(1);
+    let inner_width = area.width.saturating_sub(border_size);
+    // Columns are a cell apart.
+    let columns = match self.column_width {
+      Some(column_width) => usize::from((inner_width + 1) / column_width.saturating_add(1)).max(1),
+      None => 1,
+    };
+    self.list_rows = height;
+    self.list_columns = columns;
+    if columns == 1 {
+      self.scroll_list_to(selected, total, height);
+    } else {
+      self.scroll_columns_to(selected, total, height, columns);
+    }
+
+    // Less the borders and the highlight symbol.
+    let column_width = (usize::from(inner_width) + 1) / columns - 1;
+    let width = column_width.saturating_sub(self.highlight.symbol.width());
+    let max_name_width = self.max_name_width;
+    let now = now_timestamp();
+    let highlight = self.jump.as_ref().map(|jump| jump.prefix.as_str());
+    let render = |item: &BranchItem| {
+      let stale_color = if self.is_stale(item, now) { Some(self.stale_color) } else { None };
+      let commit_count = if self.show_commit_counts {
+        self.commit_count_range(item).and_then(|range| self.query_cache.commit_count(&range))
+      } else {
+        None
+      };
+      item.render(width, max_name_width, &self.abbreviated_prefixes, stale_color, highlight, commit_count)
+    };
+    let window = self.list_offset..total.min(self.list_offset + height * columns);
+    let mut render_items: Vec<ListItem> = window
+      .map(|row| {
+        match &pending {
+          Some((position, item)) if row == *position => render(item),
+          Some((position, _)) if row > *position => render(&self.branches[visible[row - 1]]),
+          _ => render(&self.branches[visible[row]]),
+        }
+      })
+      .collect();
+    let selected_in_window = selected.map(|row| row - self.list_offset);
+
+    let block = self.list_block(self.branches.len() - visible.len());
+    if columns == 1 {
+      let mut list_state = ListState::default().with_selected(selected_in_window);
+      f.render_stateful_widget(self.styled_list(render_items).block(block), area, &mut list_state);
+      return;
+    }
+    let inner_area = block.inner(area);
+    f.render_widget(block, area);
+    let column_areas = Layout::horizontal(vec![Constraint::Fill(1); columns]).spacing(1).split(inner_area);
+    for (column, column_area) in column_areas.iter().enumerate() {
+      let rest = render_items.split_off(height.min(render_items.len()));
+      let items = std::mem::replace(&mut render_items, rest);
+      let selected_row =
+        selected_in_window.and_then(|row| if row / height == column { Some(row % height) } else { None });
+      let mut list_state = ListState::default().with_selected(selected_row);
+      f.render_stateful_widget(self.styled_list(items), *column_area, &mut list_state);
+    }
+  }
+
+  /// The border around the list, titled with what's listed and the state of the repo, with how HEAD compares with its
+  /// upstream along the bottom. Compact drops it all.
+  fn list_block(&self, hidden_count: usize) -> Block<'static> {
+    let heading = match &self.shown_remote {
+      _ if !self.show_remotes => String::from("Local Branches"),
+      Some(remote) => format!("Local and {} Branches", remote),
+      None => String::from("Local and Remote Branches"),
+    };
+    let mut title = match &self.jump {
+      Some(jump) => format!("{} (jump: {})", heading, jump.prefix),
+      None if hidden_count > 0 => format!("{} ({} hidden)", heading, hidden_count),
+      None => heading,
+    };
+    if self.is_bare {
+      title.push_str(" [bare]");
+    }
+    if self.is_shallow {
+      title.push_str(" [shallow]");
+    }
+    if self.rebase_in_progress {
+      title.push_str(" [rebase in progress]");
+    }
+    if self.read_only {
+      title.push_str(" [read-only]");
+    }
+    if let Some(base) = &self.compare_base {
+      title.push_str(&format!(" [compare base: {}]", base));
+    }
+    if let Some(order) = self.sort.describe() {
+      title.push_str(&format!(" [{}]", order));
+    }
+    match &self.head_sync {
+      _ if self.compact => Block::default(),
+      Some(head_sync) => Block::default().title(title).title_bottom(head_sync.to_line()).borders(Borders::ALL),
+      None => Block::default().title(title).borders(Borders::ALL),
+    }
+  }
+
+  fn styled_list<'a>(&'a self, items: Vec<ListItem<'a>>) -> List<'a> {
+    List::new(items)
+      .style(Style::default().fg(Color::White))
+      .highlight_style(self.highlight.style)
+      .highlight_symbol(&self.highlight.symbol)
+      .repeat_highlight_symbol(true)
+  }
+
+  /// Shown in place of a list with at most one branch, where there's nothing to choose between yet.
+  fn render_empty_state(&self, f: &mut Frame<'_>, area: Rect) {
+    let mut lines = vec![match self.branches.first() {
+      Some(item) => Line::from(format!("Only one branch, {}.", item.branch.name)),
+      None => Line::from("No branches yet."),
+    }];
+    if !self.is_bare && !self.read_only {
+      lines.push(Line::from("Press ⇧ + c to create a new branch."));
+    }
+    let border_size = if self.compact { 0 } else { 2 };
+    let top_padding = area.height.saturating_sub(border_size + 2) / 2;
+    let block = self.list_block(0);
+    let paragraph = Paragraph::new(lines)
+      .centered()
+      .block(block.padding(Padding::top(top_padding)))
+      .style(Style::default().fg(Color::White));
+    f.render_widget(paragraph, area);
+  }
+
+  fn render_error(&mut self, f: &mut Frame<'_>, area: Rect) {
+    if self.error.is_none() {
+      return;
+    }
+    let error_message = self.error.as_ref().unwrap().clone();
+    // Scrolling stops at the end of the wrapped message.
+    let wrapped_lines = wrapped_line_count(&error_message, area.width.saturating_sub(2));
+    let max_scroll = wrapped_lines.saturating_sub(area.height.saturating_sub(2));
+    self.error_scroll = self.error_scroll.min(max_scroll);
+    let title = if max_scroll > 0 { "Error (pgup/pgdn: Scroll)" } else { "Error" };
+    let text = Text::from(error_message);
+    let component = Paragraph::new(text)
+      .block(Block::bordered().title(title))
+      .style(Style::from(Color::Red))
+      .wrap(Wrap { trim: true })
+      .scroll((self.error_scroll, 0));
+    f.render_widget(component, area);
+  }
+}
+
+/// Roughly how many rows `text` takes once wrapped to `width`, enough to size and scroll the error panel.
+fn wrapped_line_count(text: &str, width: u16) -> u16 {
+  let width = usize::from(width).max(1);
+  let lines: usize = text.lines().map(|line| line.width().div_ceil(width).max(1)).sum();
+  u16::try_from(lines).unwrap_or(u16::MAX)
+}
+
+/// Rows for the error panel with its borders. Conflicts can make git very verbose, so the panel is capped at a third of
+/// the screen and the rest of the message is scrolled to, leaving the list usable.
+fn error_panel_height(error: &str, width: u16, screen_height: u16) -> u16 {
+  let needed = wrapped_line_count(error, width.saturating_sub(2)).saturating_add(2);
+  needed.min((screen_height / 3).max(3))
+}
+
+fn sort_branches(branches: &mut [BranchItem], sort: &BranchOrder) {
+  branches.sort_by(|a, b| sort.compare(&a.branch, &b.branch));
+}
+
+/// Actions that change the repository, or lead straight to a change, and are refused in read-only mode.
+fn is_mutating(action: &Action) -> bool {
+  matches!(
+    action,
+    Action::CheckoutPreviousBranch
+      | Action::CheckoutSelectedBranch
+      | Action::CheckoutSelectedCommit
+      | Action::CherryPickSelectedCommit
+      | Action::ConfirmDetachedCheckout
+      | Action::ConfirmForceCheckout
+      | Action::ConfirmPendingDeletion
+      | Action::ConfirmRenamePush
+      | Action::CreateBranch(_)
+      | Action::CreateBranchFrom(_, _)
+      | Action::CreateBranchWithEmptyCommit(_)
+      | Action::CreateBranchWithoutCheckout(_)
+      | Action::DeleteBranch
+      | Action::DeleteStagedBranches
+      | Action::Fetch
+      | Action::InitEditDescription
+      | Action::InitNewBranch
+      | Action::InitNewBranchFromReflogEntry
+      | Action::InitNewBranchFromSelected
+      | Action::InitPruneRemote
+      | Action::InitRenameBranch
+      | Action::PruneRemote(_)
+      | Action::QuickCheckout(_)
+      | Action::RebaseOntoSelected
+      | Action::RenameBranch(_, _)
+      | Action::SetBranchDescription(_, _)
+      | Action::ShowRecentBranches
+      | Action::StageBranchForDeletion
+      | Action::StageGoneBranches
+      | Action::StartQuickCheckout
+  )
+}
+
+/// Actions on the selected branch that only make sense for a local one.
+fn needs_local_branch(action: &Action) -> bool {
+  matches!(
+    action,
+    Action::DeleteBranch
+      | Action::InitEditDescription
+      | Action::InitRenameBranch
+      | Action::ShowBranchDetails
+      | Action::StageBranchForDeletion
+  )
+}
+
+impl Component for BranchList {
+  fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> color_eyre::Result<()> {
+    self.action_tx = Some(tx);
+    Ok(())
+  }
+
+  fn register_config_handler(&mut self, config: Config) -> color_eyre::Result<()> {
+    self.apply_config(&config);
+    Ok(())
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) -> color_eyre::Result<Option<Action>> {
+    if self.error.is_some() {
+      match key.code {
+        KeyCode::PageUp => return Ok(Some(Action::ScrollErrorUp)),
+        KeyCode::PageDown => return Ok(Some(Action::ScrollErrorDown)),
+        _ => {},
+      }
+    }
+    self.clear_error();
+    self.status = None;
+
+    if self.mode == Mode::BranchInput {
+      return Ok(Some(Action::UpdateNewBranchName(key)));
+    }
+    if self.mode == Mode::QuickCheckout {
+      return Ok(Some(Action::UpdateQuickCheckout(key)));
+    }
+    if self.mode == Mode::DescriptionInput {
+      return Ok(Some(Action::UpdateDescription(key)));
+    }
+    if self.pending_deletion.is_some() {
+      return match key {
+        KeyEvent { code: KeyCode::Char('y'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+          Ok(Some(Action::ConfirmPendingDeletion))
+        },
+        _ => Ok(Some(Action::CancelPendingDeletion)),
+      };
+    }
+    if self.pending_detached_checkout.is_some() {
+      return match key {
+        KeyEvent { code: KeyCode::Char('y'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+          Ok(Some(Action::ConfirmDetachedCheckout))
+        },
+        _ => Ok(Some(Action::CancelDetachedCheckout)),
+      };
+    }
+    if self.pending_force_checkout.is_some() {
+      return match key {
+        KeyEvent { code: KeyCode::Char('y'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+          Ok(Some(Action::ConfirmForceCheckout))
+        },
+        _ => Ok(Some(Action::CancelForceCheckout)),
+      };
+    }
+    if self.pending_rename_push.is_some() {
+      return match key {
+        KeyEvent { code: KeyCode::Char('y'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+          Ok(Some(Action::ConfirmRenamePush))
+        },
+        _ => Ok(Some(Action::CancelRenamePush)),
+      };
+    }
+    if self.context_menu.is_some() {
+      return match key {
+        KeyEvent { code: KeyCode::Up, modifiers: _, kind: _, state: _ } => Ok(Some(Action::SelectPreviousMenuItem)),
+        KeyEvent { code: KeyCode::Down, modifiers: _, kind: _, state: _ } => Ok(Some(Action::SelectNextMenuItem)),
+        KeyEvent { code: KeyCode::Enter, modifiers: _, kind: _, state: _ } => Ok(Some(Action::RunSelectedMenuItem)),
+        KeyEvent { code: KeyCode::Esc | KeyCode::Char('m'), modifiers: _, kind: _, state: _ } => {
+          Ok(Some(Action::CloseContextMenu))
+        },
+        _ => Ok(None),
+      };
+    }
+    if self.commit_log.is_some() {
+      return match key {
+        KeyEvent { code: KeyCode::Up, modifiers: _, kind: _, state: _ } => Ok(Some(Action::SelectPreviousCommit)),
+        KeyEvent { code: KeyCode::Down, modifiers: _, kind: _, state: _ } => Ok(Some(Action::SelectNextCommit)),
+        KeyEvent { code: KeyCode::Char('p'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+          if self.is_bare {
+            return Ok(None);
+          }
+          Ok(Some(Action::CherryPickSelectedCommit))
+        },
+        KeyEvent { code: KeyCode::Char('c'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+          if self.is_bare {
+            return Ok(None);
+          }
+          Ok(Some(Action::CheckoutSelectedCommit))
+        },
+        KeyEvent { code: KeyCode::Esc | KeyCode::Enter, modifiers: _, kind: _, state: _ } => {
+          Ok(Some(Action::CloseCommitLog))
+        },
+        _ => Ok(None),
+      };
+    }
+    if self.reflog.is_some() {
+      return match key {
+        KeyEvent { code: KeyCode::Up, modifiers: _, kind: _, state: _ } => Ok(Some(Action::SelectPreviousReflogEntry)),
+        KeyEvent { code: KeyCode::Down, modifiers: _, kind: _, state: _ } => Ok(Some(Action::SelectNextReflogEntry)),
+        KeyEvent { code: KeyCode::Char('n'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+          Ok(Some(Action::InitNewBranchFromReflogEntry))
+        },
+        KeyEvent { code: KeyCode::Esc | KeyCode::Enter, modifiers: _, kind: _, state: _ } => {
+          Ok(Some(Action::CloseReflog))
+        },
+        _ => Ok(None),
+      };
+    }
+    if self.comparison.is_some() {
+      return match key {
+        KeyEvent { code: KeyCode::Up, modifiers: _, kind: _, state: _ } => Ok(Some(Action::ScrollComparisonUp)),
+        KeyEvent { code: KeyCode::Down, modifiers: _, kind: _, state: _ } => Ok(Some(Action::ScrollComparisonDown)),
+        KeyEvent { code: KeyCode::Esc | KeyCode::Enter, modifiers: _, kind: _, state: _ } => {
+          Ok(Some(Action::CloseBranchComparison))
+        },
+        _ => Ok(None),
+      };
+    }
+    if self.details.is_some() {
+      return match key {
+        KeyEvent { code: KeyCode::Esc | KeyCode::Enter, modifiers: _, kind: _, state: _ } => {
+          Ok(Some(Action::CloseBranchDetails))
+        },
+        _ => Ok(None),
+      };
+    }
+    if self.jump.is_some() {
+      return match key {
+        KeyEvent { code: KeyCode::Char(c), modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT, kind: _, state: _ } => {
+          Ok(Some(Action::JumpToBranch(c)))
+        },
+        _ => Ok(Some(Action::EndBranchJump)),
+      };
+    }
+    match key {
+      KeyEvent { code: KeyCode::Down, modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        Ok(Some(Action::SelectNextBranch))
+      },
+      KeyEvent { code: KeyCode::Up, modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        Ok(Some(Action::SelectPreviousBranch))
+      },
+      KeyEvent { code: KeyCode::Right, modifiers: KeyModifiers::NONE, kind: _, state: _ } if self.list_columns > 1 => {
+        Ok(Some(Action::SelectNextColumn))
+      },
+      KeyEvent { code: KeyCode::Left, modifiers: KeyModifiers::NONE, kind: _, state: _ } if self.list_columns > 1 => {
+        Ok(Some(Action::SelectPreviousColumn))
+      },
+      KeyEvent { code: KeyCode::Char('c' | 'C'), modifiers: KeyModifiers::SHIFT, kind: _, state: _ } => {
+        if self.is_bare {
+          return Ok(None);
+        }
+        Ok(Some(Action::InitNewBranch))
+      },
+      // Nothing is checked out, so unlike `⇧ + c` this works in a bare repo too.
+      KeyEvent { code: KeyCode::Char('n'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        if self.get_selected_branch().is_none() {
+          return Ok(None);
+        }
+        Ok(Some(Action::InitNewBranchFromSelected))
+      },
+      KeyEvent { code: KeyCode::Char('c' | 'C'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        if self.is_bare {
+          return Ok(None);
+        }
+        Ok(Some(Action::CheckoutSelectedBranch))
+      },
+      KeyEvent { code: KeyCode::Enter, modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        if self.get_selected_branch().is_none() {
+          return Ok(None);
+        }
+        Ok(Some(Action::ShowBranchDetails))
+      },
+      KeyEvent { code: KeyCode::Char('m'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        if self.get_selected_branch().is_none() {
+          return Ok(None);
+        }
+        Ok(Some(Action::ShowContextMenu))
+      },
+      KeyEvent { code: KeyCode::Char('e'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        if self.get_selected_branch().is_none() {
+          return Ok(None);
+        }
+        Ok(Some(Action::InitEditDescription))
+      },
+      KeyEvent { code: KeyCode::Char('e' | 'E'), modifiers: KeyModifiers::SHIFT, kind: _, state: _ } => {
+        if self.get_selected_branch().is_none() {
+          return Ok(None);
+        }
+        Ok(Some(Action::InitRenameBranch))
+      },
+      KeyEvent { code: KeyCode::Char('/'), modifiers: _, kind: _, state: _ } => Ok(Some(Action::StartBranchJump)),
+      KeyEvent { code: KeyCode::Char('b'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        let Some(selected) = self.get_selected_branch() else {
+          return Ok(None);
+        };
+        match &self.compare_base {
+          Some(base) if *base != selected.branch.name => Ok(Some(Action::CompareWithBase)),
+          _ => Ok(Some(Action::MarkCompareBase)),
+        }
+      },
+      KeyEvent { code: KeyCode::Char('f'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        Ok(Some(Action::Fetch))
+      },
+      KeyEvent { code: KeyCode::Char('p'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        Ok(Some(Action::InitPruneRemote))
+      },
+      KeyEvent { code: KeyCode::Char('v'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        let Some(selected) = self.get_selected_branch() else {
+          return Ok(None);
+        };
+        Ok(Some(Action::OpenInPager(selected.branch.name.clone())))
+      },
+      KeyEvent { code: KeyCode::Char('l'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        if self.get_selected_branch().is_none() {
+          return Ok(None);
+        }
+        Ok(Some(Action::ShowCommitLog))
+      },
+      KeyEvent { code: KeyCode::Char('l' | 'L'), modifiers: KeyModifiers::SHIFT, kind: _, state: _ } => {
+        Ok(Some(Action::ShowReflog))
+      },
+      KeyEvent { code: KeyCode::Char('g'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        if self.is_bare {
+          return Ok(None);
+        }
+        Ok(Some(Action::StartQuickCheckout))
+      },
+      KeyEvent { code: KeyCode::Char('-'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        if self.is_bare {
+          return Ok(None);
+        }
+        Ok(Some(Action::CheckoutPreviousBranch))
+      },
+      KeyEvent { code: KeyCode::Char('o'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        if self.is_bare {
+          return Ok(None);
+        }
+        Ok(Some(Action::ShowRecentBranches))
+      },
+      KeyEvent { code: KeyCode::Char('y'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        if self.get_selected_branch().is_none() {
+          return Ok(None);
+        }
+        Ok(Some(Action::CopySelectedBranchName))
+      },
+      KeyEvent { code: KeyCode::Char('y' | 'Y'), modifiers: KeyModifiers::SHIFT, kind: _, state: _ } => {
+        if self.get_selected_branch().is_none() {
+          return Ok(None);
+        }
+        Ok(Some(Action::CopySelectedSha))
+      },
+      KeyEvent { code: KeyCode::Char('w'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        if self.get_selected_branch().is_none() {
+          return Ok(None);
+        }
+        Ok(Some(Action::OpenInBrowser))
+      },
+      KeyEvent { code: KeyCode::Char('w' | 'W'), modifiers: KeyModifiers::SHIFT, kind: _, state: _ } => {
+        if self.get_selected_branch().is_none() {
+          return Ok(None);
+        }
+        Ok(Some(Action::OpenPullRequestInBrowser))
+      },
+      KeyEvent { code: KeyCode::Char('s'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        Ok(Some(Action::ToggleBranchSort))
+      },
+      KeyEvent { code: KeyCode::Char('s' | 'S'), modifiers: KeyModifiers::SHIFT, kind: _, state: _ } => {
+        Ok(Some(Action::ReverseBranchSort))
+      },
+      KeyEvent { code: KeyCode::Char('a'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        if !self.has_hidden_rules() {
+          return Ok(None);
+        }
+        Ok(Some(Action::ToggleShowAllBranches))
+      },
+      KeyEvent { code: KeyCode::Char('r'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        Ok(Some(Action::ToggleShowRemoteBranches))
+      },
+      KeyEvent { code: KeyCode::Char('r' | 'R'), modifiers: KeyModifiers::SHIFT, kind: _, state: _ } => {
+        if self.is_bare || self.get_selected_branch().is_none() {
+          return Ok(None);
+        }
+        Ok(Some(Action::RebaseOntoSelected))
+      },
+      KeyEvent { code: KeyCode::Char('u'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        Ok(Some(Action::CycleUpstreamFilter))
+      },
+      KeyEvent { code: KeyCode::Char('?'), modifiers: _, kind: _, state: _ } => Ok(Some(Action::ToggleKeymap)),
+      KeyEvent { code: KeyCode::Char('d' | 'D'), modifiers: KeyModifiers::SHIFT, kind: _, state: _ } => {
+        Ok(Some(Action::UnstageBranchForDeletion))
+      },
+      KeyEvent { code: KeyCode::Char('g' | 'G'), modifiers: KeyModifiers::SHIFT, kind: _, state: _ } => {
+        Ok(Some(Action::StageGoneBranches))
+      },
+      KeyEvent { code: KeyCode::Char('d' | 'D'), modifiers: KeyModifiers::CONTROL, kind: _, state: _ } => {
+        Ok(Some(Action::DeleteStagedBranches))
+      },
+      KeyEvent { code: KeyCode::Char('d' | 'D'), modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        if self.get_selected_branch().is_none() {
+          return Ok(None);
+        }
+        let selected = self.get_selected_branch().unwrap();
+        if selected.staged_for_deletion {
+          return Ok(Some(Action::DeleteBranch));
+        }
+        Ok(Some(Action::StageBranchForDeletion))
+      },
+      _ => Ok(None),
+    }
+  }
+
+  fn update(&mut self, action: Action) -> color_eyre::Result<Option<Action>> {
+    if self.read_only && is_mutating(&action) {
+      self.status = Some(String::from("read-only mode"));
+      return Ok(None);
+    }
+    if needs_local_branch(&action) {
+      if let Some(item) = self.get_selected_branch().filter(|item| item.remote.is_some()) {
+        self.status = Some(format!("{} is a remote branch, check it out to work on it locally", item.branch.name));
+        return Ok(None);
+      }
+    }
+    if matches!(action, Action::DeleteBranch | Action::DeleteStagedBranches) {
+      if let Some(default_branch) = self.unconfirmed_default_deletion(&action) {
+        self.pending_deletion = Some((action, default_branch));
+        return Ok(Some(Action::SetMode(Mode::Overlay)));
+      }
+    }
+    match action {
+      Action::Tick => {
+        if self.jump_expired() {
+          return Ok(Some(Action::EndBranchJump));
+        }
+        Ok(None)
+      },
+      Action::StartBranchJump => {
+        self.start_jump();
+        // Typed characters belong to the jump, so stop the app treating them as global keys.
+        Ok(Some(Action::SetMode(Mode::BranchJump)))
+      },
+      Action::ShowBranchDetails => {
+        let result = self.load_selected_details();
+        if self.details.is_none() {
+          self.maybe_handle_git_error(result.err());
+          return Ok(None);
+        }
+        self.start_pull_request_lookup();
+        // The popup handles esc itself rather than letting it quit the app.
+        Ok(Some(Action::SetMode(Mode::Overlay)))
+      },
+      Action::CloseBranchDetails => {
+        self.details = None;
+        self.pull_request = None;
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
+      Action::PullRequestLoaded(name, pull_request) => {
+        if self.showing_details_of(&name) {
+          self.pull_request = Some(match pull_request {
+            Some(pull_request) => PullRequestStatus::Open(pull_request),
+            None => PullRequestStatus::NoneOpen,
+          });
+        }
+        Ok(None)
+      },
+      Action::AheadBehindLoaded(name, tip_sha, ahead_behind) => {
+        // Dropped if the branch has moved on since, the comparison of its new tip is already on its way.
+        let loaded = self.branches.iter().find(|item| {
+          item.remote.is_none() && item.branch.name == name && item.branch.tip_sha.as_ref() == Some(&tip_sha)
+        });
+        if let Some(item) = loaded {
+          self.query_cache.insert_ahead_behind(&item.branch, ahead_behind);
+          self.refresh_head_sync();
+        }
+        Ok(None)
+      },
+      Action::CommitCountsLoaded(counts) => {
+        self.query_cache.insert_commit_counts(counts);
+        Ok(None)
+      },
+      Action::PullRequestUnavailable(name) => {
+        if self.showing_details_of(&name) {
+          self.pull_request = None;
+        }
+        Ok(None)
+      },
+      Action::Error(message) => {
+        self.set_error(message);
+        Ok(None)
+      },
+      Action::ScrollErrorUp => {
+        self.error_scroll = self.error_scroll.saturating_sub(1);
+        Ok(None)
+      },
+      Action::ScrollErrorDown => {
+        // Clamped to the message length when rendering.
+        self.error_scroll = self.error_scroll.saturating_add(1);
+        Ok(None)
+      },
+      Action::MarkCompareBase => {
+        self.toggle_compare_base();
+        Ok(None)
+      },
+      Action::CompareWithBase => {
+        let result = self.compare_selected_with_base();
+        if self.comparison.is_none() {
+          self.maybe_handle_git_error(result.err());
+          return Ok(None);
+        }
+        Ok(Some(Action::SetMode(Mode::Overlay)))
+      },
+      Action::Fetch => {
+        let progress_tx = self.action_tx.clone();
+        let result = self.run_in_background(
+          "Fetching",
+          String::from("Fetch"),
+          move |repo, cancel| {
+            let mut last_update: Option<Instant> = None;
+            repo.fetch(cancel, &mut |progress| {
+              // Leave the spinner saying it's cancelling.
+              if cancel.is_cancelled() || last_update.is_some_and(|last| last.elapsed() < PROGRESS_INTERVAL) {
+                return;
+              }
+              last_update = Some(Instant::now());
+              if let Some(progress_tx) = &progress_tx {
+                let _ = progress_tx.send(Action::BusyProgress(progress.label()));
+              }
+            })
+          },
+          |()| Action::Refresh,
+        );
+        self.maybe_handle_git_error(result.err());
+        Ok(None)
+      },
+      Action::InitPruneRemote => {
+        let remotes = match self.repo.remotes() {
+          Ok(remotes) => remotes,
+          Err(err) => {
+            self.maybe_handle_git_error(Some(err));
+            return Ok(None);
+          },
+        };
+        match &remotes[..] {
+          [] => {
+            self.status = Some(String::from("There are no remotes to prune"));
+            Ok(None)
+          },
+          [remote] => Ok(Some(Action::PruneRemote(remote.clone()))),
+          _ => {
+            let items = remotes
+              .iter()
+              .map(|remote| MenuItem::new("", remote.clone(), Action::PruneRemote(remote.clone())))
+              .collect();
+            self.context_menu = Some(ContextMenu::new(String::from("Prune which remote?"), items));
+            Ok(Some(Action::SetMode(Mode::Overlay)))
+          },
+        }
+      },
+      Action::PruneRemote(remote) => {
+        let to_prune = remote.clone();
+        let result = self.run_in_background(
+          "Pruning",
+          format!("Prune {}", remote),
+          move |repo, cancel| repo.prune_remote(&to_prune, cancel),
+          move |pruned| Action::RemotePruned(remote, pruned),
+        );
+        self.maybe_handle_git_error(result.err());
+        Ok(None)
+      },
+      Action::RemotePruned(remote, pruned) => {
+        self.status = Some(match pruned.len() {
+          0 => format!("Nothing to prune from {}", remote),
+          count => format!("Pruned {} from {}: {}", count, remote, pruned.join(", ")),
+        });
+        // Upstreams that were pruned now show as gone.
+        let result = self.reload_branches();
+        self.maybe_handle_git_error(result.err());
+        Ok(None)
+      },
+      Action::SubmodulesUpdated => {
+        self.status = Some(String::from("Submodules updated"));
+        Ok(None)
+      },
+      Action::CancelOperation => {
+        if let Some(operation) = self.operation.as_ref() {
+          operation.cancel();
+        }
+        Ok(None)
+      },
+      Action::EndBusy => {
+        self.operation = None;
+        Ok(None)
+      },
+      Action::OperationCancelled => {
+        self.status = Some(String::from("Operation cancelled"));
+        Ok(None)
+      },
+      Action::Refresh => {
+        // Sent after a fetch, which can move any upstream and the remote's HEAD.
+        self.query_cache.clear();
+        self.detected_base = None;
+        let result = self.reload_branches();
+        self.maybe_handle_git_error(result.err());
+        Ok(None)
+      },
+      Action::ShowCommitLog => {
+        let result = self.load_selected_commit_log();
+        if self.commit_log.is_none() {
+          self.maybe_handle_git_error(result.err());
+          return Ok(None);
+        }
+        Ok(Some(Action::SetMode(Mode::Overlay)))
+      },
+      Action::SelectPreviousCommit => {
+        if let Some(commit_log) = self.commit_log.as_mut() {
+          commit_log.select_previous();
+        }
+        Ok(None)
+      },
+      Action::SelectNextCommit => {
+        if let Some(commit_log) = self.commit_log.as_mut() {
+          commit_log.select_next();
+        }
+        let result = self.load_more_commits();
+        self.maybe_handle_git_error(result.err());
+        Ok(None)
+      },
+      Action::CherryPickSelectedCommit => {
+        let result = self.cherry_pick_selected_commit();
+        if result.is_ok() {
+          return Ok(None);
+        }
+        // Close the log so the conflict guidance in the error isn't hidden behind it.
+        self.commit_log = None;
+        self.maybe_handle_git_error(result.err());
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
+      Action::CheckoutSelectedCommit => {
+        self.pending_detached_checkout = self.commit_log.as_ref().and_then(|log| log.selected()).cloned();
+        Ok(None)
+      },
+      Action::ConfirmDetachedCheckout => {
+        let Some(commit) = self.pending_detached_checkout.take() else {
+          return Ok(None);
+        };
+        let result = self.checkout_detached(&commit);
+        // Either way the log is done with, and it would hide a conflict's guidance.
+        self.commit_log = None;
+        if result.is_err() {
+          self.maybe_handle_git_error(result.err());
+          return Ok(Some(Action::SetMode(Mode::Default)));
+        }
+        // Straight into naming a branch, the quickest way back onto one. Esc stays detached.
+        let detached = format!("HEAD detached at {}, name a branch to create it here", commit.short_sha());
+        // Keeping what happened to submodules along the way.
+        self.status = Some(match self.status.take() {
+          Some(submodules) => format!("{}. {}", submodules, detached),
+          None => detached,
+        });
+        Ok(Some(Action::InitNewBranch))
+      },
+      Action::CancelDetachedCheckout => {
+        self.pending_detached_checkout = None;
+        Ok(None)
+      },
+      Action::CloseCommitLog => {
+        self.commit_log = None;
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
+      Action::ShowContextMenu => {
+        let Some(selected) = self.get_selected_branch() else {
+          return Ok(None);
+        };
+        self.context_menu = Some(ContextMenu::new(selected.branch.name.clone(), self.context_menu_items(selected)));
+        Ok(Some(Action::SetMode(Mode::Overlay)))
+      },
+      Action::SelectPreviousMenuItem => {
+        if let Some(context_menu) = self.context_menu.as_mut() {
+          context_menu.select_previous();
+        }
+        Ok(None)
+      },
+      Action::SelectNextMenuItem => {
+        if let Some(context_menu) = self.context_menu.as_mut() {
+          context_menu.select_next();
+        }
+        Ok(None)
+      },
+      Action::RunSelectedMenuItem => {
+        let item = self.context_menu.take().and_then(|menu| menu.selected().cloned());
+        let Some(item) = item else {
+          return Ok(Some(Action::SetMode(Mode::Default)));
+        };
+        // Leave the overlay before the item runs, it may open one of its own or change the mode itself.
+        self.mode = Mode::Default;
+        if let Some(action_tx) = &self.action_tx {
+          let _ = action_tx.send(Action::SetMode(Mode::Default));
+        }
+        Ok(Some(item.action))
+      },
+      Action::CloseContextMenu => {
+        self.context_menu = None;
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
+      Action::ShowRecentBranches => {
+        let recent = match self.recent_branches() {
+          Ok(recent) => recent,
+          Err(err) => {
+            self.maybe_handle_git_error(Some(err));
+            return Ok(None);
+          },
+        };
+        if recent.is_empty() {
+          self.status = Some(String::from("No other branches checked out recently"));
+          return Ok(None);
+        }
+        let items =
+          recent.into_iter().map(|name| MenuItem::new("", name.clone(), Action::QuickCheckout(name))).collect();
+        self.context_menu = Some(ContextMenu::new(String::from("Recent branches"), items));
+        Ok(Some(Action::SetMode(Mode::Overlay)))
+      },
+      Action::ShowReflog => {
+        let result = self.load_reflog();
+        if self.reflog.is_none() {
+          self.maybe_handle_git_error(result.err());
+          return Ok(None);
+        }
+        Ok(Some(Action::SetMode(Mode::Overlay)))
+      },
+      Action::SelectPreviousReflogEntry => {
+        if let Some(reflog) = self.reflog.as_mut() {
+          reflog.select_previous();
+        }
+        Ok(None)
+      },
+      Action::SelectNextReflogEntry => {
+        if let Some(reflog) = self.reflog.as_mut() {
+          reflog.select_next();
+        }
+        Ok(None)
+      },
+      Action::InitNewBranchFromReflogEntry => {
+        let Some(entry) = self.reflog.as_ref().and_then(|reflog| reflog.selected()) else {
+          return Ok(None);
+        };
+        self.branch_input.start_point = Some(entry.sha.clone());
+        self.branch_input.init_style();
+        self.reflog = None;
+        Ok(Some(Action::SetMode(Mode::BranchInput)))
+      },
+      Action::CloseReflog => {
+        self.reflog = None;
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
+      Action::ScrollComparisonUp => {
+        if let Some(comparison) = self.comparison.as_mut() {
+          comparison.scroll_up();
+        }
+        Ok(None)
+      },
+      Action::ScrollComparisonDown => {
+        if let Some(comparison) = self.comparison.as_mut() {
+          comparison.scroll_down();
+        }
+        Ok(None)
+      },
+      Action::CloseBranchComparison => {
+        self.comparison = None;
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
+      Action::ToggleShowAllBranches => {
+        self.toggle_show_all();
+        Ok(None)
+      },
+      Action::ToggleShowRemoteBranches => {
+        let result = self.toggle_show_remotes();
+        self.maybe_handle_git_error(result.err());
+        Ok(None)
+      },
+      Action::CycleUpstreamFilter => {
+        self.cycle_upstream_filter();
+        Ok(None)
+      },
+      Action::ToggleKeymap => {
+        self.toggle_keymap();
+        Ok(None)
+      },
+      Action::ToggleBranchSort => {
+        self.toggle_sort();
+        Ok(None)
+      },
+      Action::ReverseBranchSort => {
+        self.reverse_sort();
+        Ok(None)
+      },
+      Action::JumpToBranch(c) => {
+        self.jump_to_branch(c);
+        Ok(None)
+      },
+      Action::EndBranchJump => {
+        if self.jump.take().is_none() {
+          return Ok(None);
+        }
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
+      Action::SelectPreviousBranch => {
+        self.select_previous();
+        Ok(None)
+      },
+      Action::SelectPreviousColumn => {
+        self.select_column(false);
+        Ok(None)
+      },
+      Action::SelectNextColumn => {
+        self.select_column(true);
+        Ok(None)
+      },
+      Action::SelectNextBranch => {
+        self.select_next();
+        Ok(None)
+      },
+      Action::InitNewBranch => {
+        self.branch_input.init_style();
+        Ok(Some(Action::SetMode(Mode::BranchInput)))
+      },
+      Action::InitNewBranchFromSelected => {
+        let Some(selected) = self.get_selected_branch() else {
+          return Ok(None);
+        };
+        self.branch_input.start_point = Some(selected.branch.name.clone());
+        self.branch_input.init_style();
+        Ok(Some(Action::SetMode(Mode::BranchInput)))
+      },
+      Action::SetMode(mode) => {
+        self.mode = mode;
+        Ok(None)
+      },
+      Action::UpdateNewBranchName(key_event) => Ok(self.branch_input.handle_key_event(key_event, &*self.repo)),
+      Action::StartQuickCheckout => {
+        self.quick_checkout_input.init_style();
+        Ok(Some(Action::SetMode(Mode::QuickCheckout)))
+      },
+      Action::UpdateQuickCheckout(key_event) => {
+        let names = self.branches.iter().map(|item| item.branch.name.as_str());
+        Ok(self.quick_checkout_input.handle_key_event(key_event, names))
+      },
+      Action::InitEditDescription => {
+        let Some(selected) = self.get_selected_branch() else {
+          return Ok(None);
+        };
+        let branch = selected.branch.name.clone();
+        let current = selected.branch.description.clone();
+        self.description_input.start(branch, current.as_deref());
+        Ok(Some(Action::SetMode(Mode::DescriptionInput)))
+      },
+      Action::UpdateDescription(key_event) => Ok(self.description_input.handle_key_event(key_event)),
+      Action::InitRenameBranch => {
+        let Some(selected) = self.get_selected_branch() else {
+          return Ok(None);
+        };
+        self.branch_input.start_rename(selected.branch.name.clone());
+        Ok(Some(Action::SetMode(Mode::BranchInput)))
       },
-      Action::EndInputMod => {
-        self.mode = Mode::Selection;
+      Action::RenameBranch(from, to) => {
+        let result = self.rename_branch(&from, &to);
+        self.maybe_handle_git_error(result.err());
+        let mode = if self.pending_rename_push.is_some() { Mode::Overlay } else { Mode::Default };
+        Ok(Some(Action::SetMode(mode)))
+      },
+      Action::ConfirmRenamePush => {
+        let result = self.push_rename();
+        self.maybe_handle_git_error(result.err());
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
+      Action::CancelRenamePush => {
+        if let Some((branch, upstream)) = self.pending_rename_push.take() {
+          self.status = Some(format!("Renamed {}, its upstream is still {}", branch, upstream.name));
+        }
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
+      Action::RenamePushed(branch, replaced) => {
+        self.status = Some(format!("Pushed {} and deleted {}", branch, replaced));
+        // The branch tracks its new name now.
+        let result = self.reload_branches();
+        self.maybe_handle_git_error(result.err());
         Ok(None)
       },
-      Action::UpdateNewBranchName(key_event) => {
-        Ok(self.branch_input.handle_key_event(
-          key_event,
-          &*self.repo,
-          self.branches.iter().map(|branch_item| &branch_item.branch).collect(),
-        ))
+      Action::SetBranchDescription(name, description) => {
+        let result = self.set_branch_description(&name, &description);
+        self.maybe_handle_git_error(result.err());
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
+      Action::QuickCheckout(partial) => {
+        let result = self.quick_checkout(&partial);
+        Ok(self.handle_checkout_error(result.err()).or(Some(Action::SetMode(Mode::Default))))
       },
       Action::CheckoutSelectedBranch => {
         let result = self.checkout_selected();
+        Ok(self.handle_checkout_error(result.err()))
+      },
+      Action::RebaseOntoSelected => {
+        let Some(onto) = self.rebase_target() else {
+          return Ok(None);
+        };
+        if self.interactive_rebase {
+          return Ok(Some(Action::RunInteractiveRebase(onto)));
+        }
+        let result = self.rebase_onto(&onto);
         self.maybe_handle_git_error(result.err());
         Ok(None)
       },
+      Action::InteractiveRebaseExited(onto, success) => {
+        let result = self.finish_interactive_rebase(&onto, success);
+        self.maybe_handle_git_error(result.err());
+        Ok(None)
+      },
+      Action::CheckoutPreviousBranch => {
+        let result = self.checkout_previous();
+        Ok(self.handle_checkout_error(result.err()))
+      },
+      Action::ConfirmForceCheckout => {
+        if let Some(name) = self.pending_force_checkout.take() {
+          let result = self.force_checkout(&name);
+          self.maybe_handle_git_error(result.err());
+        }
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
+      Action::CancelForceCheckout => {
+        self.pending_force_checkout = None;
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
       Action::CreateBranch(name) => {
-        self.mode = Mode::Selection;
-        let result = self.create_branch(name);
+        let result = self.create_branch(name, None, true);
+        self.maybe_handle_git_error(result.err());
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
+      Action::CreateBranchWithEmptyCommit(name) => {
+        let result = self.create_branch_with_empty_commit(name);
+        self.maybe_handle_git_error(result.err());
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
+      Action::CreateBranchWithoutCheckout(name) => {
+        let result = self.create_branch(name, None, false);
+        self.maybe_handle_git_error(result.err());
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
+      Action::CreateBranchFrom(name, start_point) => {
+        let result = self.create_branch(name, Some(&start_point), false);
         self.maybe_handle_git_error(result.err());
-        Ok(Some(Action::EndInputMod))
+        Ok(Some(Action::SetMode(Mode::Default)))
       },
       Action::StageBranchForDeletion => {
         self.stage_selected_for_deletion(true);
@@ -310,6 +2753,28 @@ impl Component for BranchList {
         self.stage_selected_for_deletion(false);
         Ok(None)
       },
+      Action::OpenInBrowser => {
+        let result = self.open_selected_in_browser(false);
+        self.maybe_handle_git_error(result.err());
+        Ok(None)
+      },
+      Action::OpenPullRequestInBrowser => {
+        let result = self.open_selected_in_browser(true);
+        self.maybe_handle_git_error(result.err());
+        Ok(None)
+      },
+      Action::CopySelectedBranchName => {
+        self.copy_selected(false);
+        Ok(None)
+      },
+      Action::CopySelectedSha => {
+        self.copy_selected(true);
+        Ok(None)
+      },
+      Action::StageGoneBranches => {
+        self.stage_gone_branches();
+        Ok(None)
+      },
       Action::DeleteBranch => {
         let result = self.deleted_selected();
         self.maybe_handle_git_error(result.err());
@@ -320,41 +2785,865 @@ impl Component for BranchList {
         self.maybe_handle_git_error(result.err());
         Ok(None)
       },
+      Action::ConfirmPendingDeletion => {
+        if let Some((deletion, _)) = self.pending_deletion.take() {
+          self.default_deletion_confirmed = true;
+          let result = self.update(deletion);
+          self.default_deletion_confirmed = false;
+          result?;
+        }
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
+      Action::CancelPendingDeletion => {
+        self.pending_deletion = None;
+        Ok(Some(Action::SetMode(Mode::Default)))
+      },
       _ => Ok(None),
     }
   }
 
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> color_eyre::Result<()> {
-    if self.mode == Mode::Input {
-      let layout =
-        Layout::new(Direction::Vertical, [Constraint::Fill(1), Constraint::Length(3), Constraint::Length(1)])
-          .margin(1)
-          .split(area);
+    let min_height = if self.compact { MIN_COMPACT_HEIGHT } else { MIN_HEIGHT };
+    if area.width < MIN_WIDTH || area.height < min_height {
+      let hint = Paragraph::new("Terminal too small, widen it to see branches")
+        .style(Style::default().fg(Color::Yellow))
+        .wrap(Wrap { trim: true });
+      f.render_widget(hint, area);
+      return Ok(());
+    }
+    // Inside the one cell margin around everything. Compact mode has no margin, and only shows the footer for a status
+    // message since there would be no other sign that something happened.
+    let margin = if self.compact { 0 } else { 1 };
+    let footer_height = match (self.compact, &self.status) {
+      (true, None) => 0,
+      (true, Some(_)) => self.instruction_footer.height(area.width, self),
+      (false, _) => self.instruction_footer.height(area.width.saturating_sub(2), self),
+    };
+
+    if matches!(self.mode, Mode::BranchInput | Mode::QuickCheckout | Mode::DescriptionInput) {
+      let layout = Layout::new(Direction::Vertical, [
+        Constraint::Fill(1),
+        Constraint::Length(3),
+        Constraint::Length(footer_height),
+      ])
+      .margin(margin)
+      .split(area);
       self.render_list(f, layout[0]);
-      self.branch_input.render(f, layout[1]);
-      self.instruction_footer.render(f, layout[2], &self.branches, self.get_selected_branch());
+      match self.mode {
+        Mode::BranchInput => self.branch_input.render(f, layout[1]),
+        Mode::DescriptionInput => self.description_input.render(f, layout[1]),
+        _ => self.quick_checkout_input.render(f, layout[1]),
+      }
+      self.instruction_footer.render(f, layout[2], self);
       return Ok(());
     }
 
     if self.error.is_some() {
-      let err_size = self.error.clone().unwrap().lines().count() + 2;
+      let err_size =
+        error_panel_height(self.error.as_deref().unwrap_or_default(), area.width - 2 * margin, area.height);
       let layout = Layout::new(Direction::Vertical, [
         Constraint::Fill(1),
-        Constraint::Length(u16::try_from(err_size)?),
-        Constraint::Length(1),
+        Constraint::Length(err_size),
+        Constraint::Length(footer_height),
       ])
-      .margin(1)
+      .margin(margin)
       .split(area);
       self.render_list(f, layout[0]);
       self.render_error(f, layout[1]);
-      self.instruction_footer.render(f, layout[2], &self.branches, self.get_selected_branch());
+      self.instruction_footer.render(f, layout[2], self);
       return Ok(());
     }
 
-    let layout = Layout::new(Direction::Vertical, [Constraint::Fill(1), Constraint::Length(1)]).margin(1).split(area);
+    let layout = Layout::new(Direction::Vertical, [Constraint::Fill(1), Constraint::Length(footer_height)])
+      .margin(margin)
+      .split(area);
     self.render_list(f, layout[0]);
-    self.instruction_footer.render(f, layout[1], &self.branches, self.get_selected_branch());
+    self.instruction_footer.render(f, layout[1], self);
+    if let Some(details) = &self.details {
+      self.branch_details_popup.render(f, area, details, self.is_shallow, self.pull_request.as_ref());
+    }
+    if let Some(comparison) = self.comparison.as_mut() {
+      comparison.render(f, area);
+    }
+    if let Some(commit_log) = self.commit_log.as_mut() {
+      let can_change = !self.is_bare && !self.read_only;
+      commit_log.render(f, area, self.is_shallow, can_change, can_change);
+    }
+    if let Some(reflog) = self.reflog.as_mut() {
+      reflog.render(f, area, !self.read_only);
+    }
+    if let Some(context_menu) = self.context_menu.as_mut() {
+      context_menu.render(f, area);
+    }
+    self.render_deletion_confirmation(f, area);
+    self.render_force_checkout_confirmation(f, area);
+    self.render_rename_push_confirmation(f, area);
+    self.render_detached_checkout_confirmation(f, area);
 
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::git::{git_cli_repo::GitCliRepo, test_repo::TestRepo};
+
+  /// The rows `render` draws into a `width` by `height` area, trailing blanks trimmed.
+  fn rendered_rows(width: u16, height: u16, render: impl FnOnce(&mut Frame<'_>)) -> Vec<String> {
+    let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(width, height)).unwrap();
+    terminal.draw(render).unwrap();
+    let buffer = terminal.backend().buffer();
+    (0..height)
+      .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect::<String>().trim_end().to_string())
+      .collect()
+  }
+
+  fn render_list_rows(branch_list: &mut BranchList, width: u16, height: u16) -> Vec<String> {
+    rendered_rows(width, height, |f| branch_list.render_list(f, f.area()))
+  }
+
+  #[test]
+  fn compact_layout_gives_every_row_to_the_list() {
+    let test_repo = TestRepo::new();
+    let long_name = "l".repeat(60);
+    test_repo.git(&["branch", "alpha"]);
+    test_repo.git(&["branch", &long_name]);
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    branch_list.compact = true;
+
+    let rows = rendered_rows(40, 4, |f| branch_list.draw(f, f.area()).unwrap());
+
+    // No borders or footer, and the long name is cut so the row is exactly as wide as the area.
+    assert_eq!(rows, vec![
+      String::from("→alpha just now"),
+      format!(" {}… just now", "l".repeat(29)),
+      String::from(" main (HEAD) just now"),
+      String::new(),
+    ]);
+    assert_eq!(branch_list.list_rows, 4);
+  }
+
+  #[test]
+  fn empty_state_keeps_the_list_title() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    branch_list.read_only = true;
+    branch_list.rebase_in_progress = true;
+
+    let rows = render_list_rows(&mut branch_list, 60, 7);
+
+    assert_eq!(rows[0], "┌Local Branches [rebase in progress] [read-only]───────────┐");
+    assert!(rows.iter().any(|row| row.contains("Only one branch, main.")), "{:#?}", rows);
+  }
+
+  #[test]
+  fn lone_remote_branch_is_listed() {
+    let origin = TestRepo::new();
+    let test_repo = TestRepo::with_origin(&origin);
+    test_repo.git(&["checkout", "--detach"]);
+    test_repo.git(&["branch", "-D", "main"]);
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    dispatch(&mut branch_list, Action::ToggleShowRemoteBranches);
+
+    let rows = render_list_rows(&mut branch_list, 60, 7);
+
+    assert!(rows[0].starts_with("┌Local and Remote Branches"), "{:#?}", rows);
+    assert!(rows.iter().any(|row| row.contains("origin/main")), "{:#?}", rows);
+    assert!(!rows.iter().any(|row| row.contains("Only one branch")), "{:#?}", rows);
+  }
+
+  /// Feed an action through `update` along with every action it leads to, like the app loop does.
+  fn dispatch(branch_list: &mut BranchList, action: Action) {
+    let mut next = Some(action);
+    while let Some(action) = next {
+      next = branch_list.update(action).unwrap();
+    }
+  }
+
+  fn press(branch_list: &mut BranchList, code: KeyCode) {
+    if let Some(action) = branch_list.handle_key_events(KeyEvent::new(code, KeyModifiers::NONE)).unwrap() {
+      dispatch(branch_list, action);
+    }
+  }
+
+  fn type_text(branch_list: &mut BranchList, text: &str) {
+    for c in text.chars() {
+      press(branch_list, KeyCode::Char(c));
+    }
+  }
+
+  fn selected_name(branch_list: &BranchList) -> &str {
+    &branch_list.get_selected_branch().unwrap().branch.name
+  }
+
+  /// A branch list over `alpha`, `main` and `zulu` with `zulu` selected.
+  fn branch_list_with_zulu_selected(test_repo: &TestRepo) -> BranchList {
+    test_repo.git(&["branch", "alpha"]);
+    test_repo.git(&["branch", "zulu"]);
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    dispatch(&mut branch_list, Action::SelectPreviousBranch);
+    assert_eq!(selected_name(&branch_list), "zulu");
+    branch_list
+  }
+
+  #[test]
+  fn deleting_the_default_branch_waits_for_confirmation() {
+    for deletion in [Action::DeleteBranch, Action::DeleteStagedBranches] {
+      let test_repo = TestRepo::new();
+      let mut branch_list = branch_list_with_zulu_selected(&test_repo);
+      test_repo.git(&["checkout", "alpha"]);
+      dispatch(&mut branch_list, Action::Refresh);
+      dispatch(&mut branch_list, Action::SelectPreviousBranch);
+      assert_eq!(selected_name(&branch_list), "main");
+      if deletion == Action::DeleteStagedBranches {
+        dispatch(&mut branch_list, Action::StageBranchForDeletion);
+      }
+
+      assert_eq!(branch_list.update(deletion.clone()).unwrap(), Some(Action::SetMode(Mode::Overlay)), "{}", deletion);
+      assert_eq!(branch_list.pending_deletion.as_ref().map(|(_, name)| name.as_str()), Some("main"), "{}", deletion);
+      assert_eq!(test_repo.git(&["branch", "--list", "main"]), "  main\n", "{}", deletion);
+
+      dispatch(&mut branch_list, Action::ConfirmPendingDeletion);
+      assert!(branch_list.pending_deletion.is_none(), "{}", deletion);
+      assert_eq!(test_repo.git(&["branch", "--list", "main"]), "", "{}", deletion);
+    }
+  }
+
+  #[test]
+  fn stage_gone_branches_skips_head_and_protected_branches() {
+    let origin = TestRepo::new();
+    for name in ["gone", "keep/gone", "head-gone", "live"] {
+      origin.git(&["branch", name]);
+    }
+    let test_repo = TestRepo::with_origin(&origin);
+    for name in ["gone", "keep/gone", "head-gone", "live"] {
+      test_repo.git(&["branch", "--track", name, &format!("origin/{}", name)]);
+    }
+    origin.git(&["branch", "-D", "gone", "keep/gone", "head-gone"]);
+    test_repo.git(&["checkout", "head-gone"]);
+    test_repo.git(&["fetch", "--prune", "origin"]);
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    branch_list.protected_patterns = vec![Regex::new("^keep/").unwrap()];
+
+    dispatch(&mut branch_list, Action::StageGoneBranches);
+
+    let staged: Vec<&str> = branch_list
+      .branches
+      .iter()
+      .filter(|item| item.staged_for_deletion)
+      .map(|item| item.branch.name.as_str())
+      .collect();
+    assert_eq!(staged, vec!["gone"]);
+    assert_eq!(branch_list.status.as_deref(), Some("Staged 1 branch with a gone upstream"));
+  }
+
+  #[test]
+  fn is_stale_only_past_the_threshold() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    branch_list.stale_after_days = 2;
+    let now = 1_700_000_000;
+    let item = |last_commit_time: Option<i64>| {
+      BranchItem::new(GitBranch { last_commit_time, ..GitBranch::new(String::from("topic")) }, true)
+    };
+    let cases = vec![
+      ("no commit time", None, false),
+      ("recent", Some(now - 60), false),
+      ("exactly at the threshold", Some(now - 2 * SECONDS_PER_DAY), false),
+      ("a second past the threshold", Some(now - 2 * SECONDS_PER_DAY - 1), true),
+      ("in the future", Some(now + 60), false),
+    ];
+
+    for (description, last_commit_time, expected) in cases {
+      assert_eq!(branch_list.is_stale(&item(last_commit_time), now), expected, "{}", description);
+    }
+
+    branch_list.stale_after_days = u64::MAX;
+    assert!(!branch_list.is_stale(&item(Some(i64::MIN)), now), "huge threshold");
+  }
+
+  #[test]
+  fn shallow_clone_warns_on_start() {
+    let source = TestRepo::new();
+    source.git(&["commit", "--allow-empty", "-m", "Second"]);
+    let shallow = TestRepo::shallow_clone(&source);
+
+    let branch_list = BranchList::new(Box::new(GitCliRepo::from_path(shallow.path())));
+    assert_eq!(branch_list.status.as_deref(), Some(SHALLOW_WARNING));
+    let branch_list = BranchList::new(Box::new(GitCliRepo::from_path(source.path())));
+    assert_eq!(branch_list.status, None);
+  }
+
+  #[test]
+  fn selection_keys_map_to_actions() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = branch_list_with_zulu_selected(&test_repo);
+    let cases = vec![
+      (KeyEvent::new(KeyCode::Down, KeyModifiers::NONE), Some(Action::SelectNextBranch)),
+      (KeyEvent::new(KeyCode::Up, KeyModifiers::NONE), Some(Action::SelectPreviousBranch)),
+      (KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE), Some(Action::CheckoutSelectedBranch)),
+      (KeyEvent::new(KeyCode::Char('C'), KeyModifiers::SHIFT), Some(Action::InitNewBranch)),
+      (KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE), Some(Action::InitNewBranchFromSelected)),
+      (KeyEvent::new(KeyCode::Char('L'), KeyModifiers::SHIFT), Some(Action::ShowReflog)),
+      (KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE), Some(Action::ShowContextMenu)),
+      (KeyEvent::new(KeyCode::Char('p'), KeyModifiers::NONE), Some(Action::InitPruneRemote)),
+      (KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE), Some(Action::ToggleShowRemoteBranches)),
+      (KeyEvent::new(KeyCode::Char('u'), KeyModifiers::NONE), Some(Action::CycleUpstreamFilter)),
+      (KeyEvent::new(KeyCode::Char('?'), KeyModifiers::SHIFT), Some(Action::ToggleKeymap)),
+      (KeyEvent::new(KeyCode::Char('e'), KeyModifiers::NONE), Some(Action::InitEditDescription)),
+      (KeyEvent::new(KeyCode::Char('E'), KeyModifiers::SHIFT), Some(Action::InitRenameBranch)),
+      (KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE), Some(Action::StageBranchForDeletion)),
+      (KeyEvent::new(KeyCode::Char('D'), KeyModifiers::SHIFT), Some(Action::UnstageBranchForDeletion)),
+      (KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL), Some(Action::DeleteStagedBranches)),
+      (KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE), Some(Action::CopySelectedBranchName)),
+      (KeyEvent::new(KeyCode::Char('Y'), KeyModifiers::SHIFT), Some(Action::CopySelectedSha)),
+      (KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE), None),
+    ];
+
+    for (key, expected) in cases {
+      assert_eq!(branch_list.handle_key_events(key).unwrap(), expected, "{:?}", key);
+    }
+  }
+
+  #[test]
+  fn d_deletes_a_staged_branch() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = branch_list_with_zulu_selected(&test_repo);
+    dispatch(&mut branch_list, Action::StageBranchForDeletion);
+
+    let action = branch_list.handle_key_events(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE)).unwrap();
+    assert_eq!(action, Some(Action::DeleteBranch));
+  }
+
+  #[test]
+  fn branches_deleted_elsewhere_are_reconciled_by_reloading() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = branch_list_with_zulu_selected(&test_repo);
+    let names =
+      |branch_list: &BranchList| branch_list.branches.iter().map(|item| item.branch.name.clone()).collect::<Vec<_>>();
+    test_repo.git(&["branch", "-D", "zulu"]);
+
+    dispatch(&mut branch_list, Action::CheckoutSelectedBranch);
+    assert!(branch_list.error.is_none());
+    assert_eq!(branch_list.status.as_deref(), Some("zulu was deleted elsewhere, reloaded the branches"));
+    assert_eq!(names(&branch_list), vec!["alpha", "main"]);
+
+    dispatch(&mut branch_list, Action::SelectPreviousBranch);
+    assert_eq!(selected_name(&branch_list), "alpha");
+    dispatch(&mut branch_list, Action::StageBranchForDeletion);
+    test_repo.git(&["branch", "-D", "alpha"]);
+    dispatch(&mut branch_list, Action::DeleteStagedBranches);
+    assert!(branch_list.error.is_none());
+    assert_eq!(names(&branch_list), vec!["main"]);
+  }
+
+  #[test]
+  fn input_mode_passes_keys_through() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = branch_list_with_zulu_selected(&test_repo);
+    dispatch(&mut branch_list, Action::InitNewBranch);
+
+    for key in [
+      KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL),
+      KeyEvent::new(KeyCode::Down, KeyModifiers::NONE),
+      KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+    ] {
+      assert_eq!(branch_list.handle_key_events(key).unwrap(), Some(Action::UpdateNewBranchName(key)), "{:?}", key);
+    }
+  }
+
+  #[test]
+  fn cancelling_input_keeps_previous_selection() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = branch_list_with_zulu_selected(&test_repo);
+
+    dispatch(&mut branch_list, Action::InitNewBranch);
+    type_text(&mut branch_list, "beta");
+    press(&mut branch_list, KeyCode::Esc);
+
+    assert_eq!(branch_list.mode, Mode::Default);
+    assert_eq!(selected_name(&branch_list), "zulu");
+    // Opening input again starts from scratch rather than showing the abandoned name.
+    dispatch(&mut branch_list, Action::InitNewBranch);
+    assert_eq!(branch_list.branch_input.input_state.value, None);
+    assert_eq!(branch_list.pending_branch(&branch_list.visible_indexes()), None);
+  }
+
+  #[test]
+  fn creating_selects_created_branch() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = branch_list_with_zulu_selected(&test_repo);
+
+    dispatch(&mut branch_list, Action::InitNewBranch);
+    type_text(&mut branch_list, "beta");
+    press(&mut branch_list, KeyCode::Enter);
+
+    assert_eq!(branch_list.mode, Mode::Default);
+    assert_eq!(selected_name(&branch_list), "beta");
+    assert!(branch_list.get_selected_branch().unwrap().branch.is_head);
+    assert_eq!(test_repo.current_branch(), "beta");
+  }
+
+  #[test]
+  fn creating_without_checkout_keeps_head() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = branch_list_with_zulu_selected(&test_repo);
+
+    dispatch(&mut branch_list, Action::InitNewBranch);
+    type_text(&mut branch_list, "beta");
+    if let Some(action) = branch_list.handle_key_events(KeyEvent::new(KeyCode::Enter, KeyModifiers::ALT)).unwrap() {
+      dispatch(&mut branch_list, action);
+    }
+
+    assert_eq!(selected_name(&branch_list), "beta");
+    assert!(!branch_list.get_selected_branch().unwrap().branch.is_head);
+    assert_eq!(test_repo.current_branch(), "main");
+  }
+
+  #[test]
+  fn creating_from_selected_starts_at_its_tip() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = branch_list_with_zulu_selected(&test_repo);
+    let zulu_tip = test_repo.git(&["commit-tree", "HEAD^{tree}", "-p", "HEAD", "-m", "Zulu work"]);
+    test_repo.git(&["update-ref", "refs/heads/zulu", zulu_tip.trim()]);
+
+    press(&mut branch_list, KeyCode::Char('n'));
+    type_text(&mut branch_list, "beta");
+    press(&mut branch_list, KeyCode::Enter);
+
+    assert_eq!(selected_name(&branch_list), "beta");
+    assert_eq!(test_repo.git(&["rev-parse", "beta"]), test_repo.git(&["rev-parse", "zulu"]));
+    assert_eq!(test_repo.current_branch(), "main");
+  }
+
+  #[test]
+  fn sort_branches_cases() {
+    let branch =
+      |name: &str, time: Option<i64>| GitBranch { last_commit_time: time, ..GitBranch::new(String::from(name)) };
+    let branches = vec![branch("b", Some(2)), branch("a", Some(2)), branch("c", Some(1)), branch("d", None)];
+    let cases = vec![
+      ("name", BranchOrder::default(), vec!["a", "b", "c", "d"]),
+      ("name reversed", BranchOrder { direction: SortDirection::Descending, ..BranchOrder::default() }, vec![
+        "d", "c", "b", "a",
+      ]),
+      (
+        "newest first then name",
+        BranchOrder {
+          key: BranchSort::Age,
+          direction: SortDirection::Descending,
+          tiebreaker: Some((BranchSort::Name, SortDirection::Ascending)),
+        },
+        vec!["a", "b", "c", "d"],
+      ),
+      (
+        "oldest first then name reversed",
+        BranchOrder {
+          key: BranchSort::Age,
+          direction: SortDirection::Ascending,
+          tiebreaker: Some((BranchSort::Name, SortDirection::Descending)),
+        },
+        vec!["c", "b", "a", "d"],
+      ),
+    ];
+
+    for (description, order, expected) in cases {
+      let mut items: Vec<BranchItem> = branches.iter().map(|branch| BranchItem::new(branch.clone(), true)).collect();
+      sort_branches(&mut items, &order);
+      let names: Vec<&str> = items.iter().map(|item| item.branch.name.as_str()).collect();
+      assert_eq!(names, expected, "{}", description);
+    }
+  }
+
+  #[test]
+  fn compare_names_cases() {
+    let cases = vec![
+      ("ignores case", "apple", "Zebra", Ordering::Less),
+      ("numbers by value", "Feature-2", "feature-10", Ordering::Less),
+      ("same number", "release-1.9", "release-1.10", Ordering::Less),
+      ("prefix first", "fix", "fix-1", Ordering::Less),
+      ("digits before letters", "v2", "va", Ordering::Less),
+      ("leading zeros", "build-007", "build-7", Ordering::Less),
+      ("case only breaks ties", "Main", "main", Ordering::Less),
+      ("identical", "main", "main", Ordering::Equal),
+    ];
+
+    for (description, a, b, expected) in cases {
+      assert_eq!(compare_names(a, b), expected, "{}", description);
+      assert_eq!(compare_names(b, a), expected.reverse(), "{} reversed", description);
+    }
+  }
+
+  #[test]
+  fn error_panel_height_cases() {
+    let long_error = vec!["CONFLICT (content): Merge conflict in src/main.rs"; 200].join("\n");
+    let cases = vec![
+      ("one line", "error: pathspec 'x' did not match", 80, 40, 3),
+      ("wraps", "error: a message much longer than the panel is wide", 22, 40, 5),
+      ("capped to a third", long_error.as_str(), 80, 42, 14),
+      ("never below one line", long_error.as_str(), 80, 6, 3),
+    ];
+
+    for (description, error, width, screen_height, expected) in cases {
+      assert_eq!(error_panel_height(error, width, screen_height), expected, "{}", description);
+    }
+  }
+
+  #[test]
+  fn creating_from_reflog_recovers_reset_commit() {
+    let test_repo = TestRepo::new();
+    test_repo.git(&["commit", "--allow-empty", "-m", "Lost work"]);
+    let lost_sha = test_repo.git(&["rev-parse", "HEAD"]);
+    test_repo.git(&["reset", "--hard", "HEAD~1"]);
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+
+    dispatch(&mut branch_list, Action::ShowReflog);
+    // The newest entry is the reset, the one before it is the lost commit.
+    press(&mut branch_list, KeyCode::Down);
+    press(&mut branch_list, KeyCode::Char('n'));
+    type_text(&mut branch_list, "rescued");
+    press(&mut branch_list, KeyCode::Enter);
+
+    assert_eq!(test_repo.git(&["rev-parse", "rescued"]), lost_sha);
+    assert_eq!(test_repo.current_branch(), "main");
+  }
+
+  #[test]
+  fn scroll_list_to_cases() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    // Offset before, selected row, total rows, height, offset after.
+    let cases = vec![
+      ("selection already visible", 0, Some(3), 20, 10, 0),
+      ("selection below the window", 0, Some(15), 20, 10, 6),
+      ("selection above the window", 8, Some(2), 20, 10, 2),
+      ("shrunk past the selection", 0, Some(9), 20, 5, 5),
+      ("grown past the end", 15, Some(18), 20, 10, 10),
+      ("grown taller than the list", 6, Some(18), 20, 30, 0),
+      ("nothing selected", 12, None, 20, 10, 10),
+    ];
+
+    for (description, offset, selected, total, height, expected) in cases {
+      branch_list.list_offset = offset;
+      branch_list.scroll_list_to(selected, total, height);
+      assert_eq!(branch_list.list_offset, expected, "{}", description);
+    }
+  }
+
+  #[test]
+  fn staging_survives_sorting_filtering_and_reloading() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = branch_list_with_zulu_selected(&test_repo);
+    press(&mut branch_list, KeyCode::Char('d'));
+    let is_staged = |branch_list: &BranchList, name: &str| {
+      branch_list.branches.iter().any(|item| item.branch.name == name && item.staged_for_deletion)
+    };
+    assert!(is_staged(&branch_list, "zulu"));
+
+    dispatch(&mut branch_list, Action::ToggleBranchSort);
+    dispatch(&mut branch_list, Action::ReverseBranchSort);
+    assert!(is_staged(&branch_list, "zulu"), "sorted");
+    dispatch(&mut branch_list, Action::CycleUpstreamFilter);
+    dispatch(&mut branch_list, Action::ToggleShowRemoteBranches);
+    assert!(is_staged(&branch_list, "zulu"), "filtered");
+    dispatch(&mut branch_list, Action::Refresh);
+    assert!(is_staged(&branch_list, "zulu"), "reloaded");
+    assert!(!is_staged(&branch_list, "alpha"));
+  }
+
+  #[test]
+  fn selection_moves_to_a_shown_branch_when_filtered_out() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = branch_list_with_zulu_selected(&test_repo);
+
+    // Only tracking branches, none do so only the checked out branch is left.
+    press(&mut branch_list, KeyCode::Char('u'));
+    assert_eq!(selected_name(&branch_list), "main");
+    assert!(branch_list.selected_index().is_some_and(|index| !branch_list.is_hidden(&branch_list.branches[index])));
+
+    for _ in 0..3 {
+      press(&mut branch_list, KeyCode::Char('u'));
+    }
+    assert_eq!(branch_list.upstream_filter, UpstreamFilter::All);
+    assert_eq!(selected_name(&branch_list), "main");
+  }
+
+  #[test]
+  fn columns_are_moved_between_and_scrolled_whole() {
+    let test_repo = TestRepo::new();
+    for name in ["alpha", "bravo", "charlie", "delta", "echo"] {
+      test_repo.git(&["branch", name]);
+    }
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    branch_list.list_rows = 4;
+    branch_list.list_columns = 2;
+    assert_eq!(selected_name(&branch_list), "alpha");
+
+    let cases = vec![
+      ("across a full column", KeyCode::Right, "echo"),
+      ("already in the last column", KeyCode::Right, "echo"),
+      ("back across", KeyCode::Left, "alpha"),
+      ("already in the first column", KeyCode::Left, "alpha"),
+    ];
+    for (description, code, expected) in cases {
+      press(&mut branch_list, code);
+      assert_eq!(selected_name(&branch_list), expected, "{}", description);
+    }
+
+    press(&mut branch_list, KeyCode::Down);
+    press(&mut branch_list, KeyCode::Down);
+    press(&mut branch_list, KeyCode::Right);
+    assert_eq!(selected_name(&branch_list), "main", "onto the last branch of a short column");
+
+    branch_list.list_offset = 0;
+    branch_list.scroll_columns_to(Some(13), 20, 4, 2);
+    assert_eq!(branch_list.list_offset, 8);
+  }
+
+  #[test]
+  fn conflicting_checkout_is_only_forced_once_confirmed() {
+    let test_repo = TestRepo::new();
+    let file = test_repo.path().join("notes.txt");
+    std::fs::write(&file, "main\n").unwrap();
+    test_repo.git(&["add", "notes.txt"]);
+    test_repo.git(&["commit", "-m", "Add notes"]);
+    test_repo.git(&["checkout", "-b", "zulu"]);
+    std::fs::write(&file, "zulu\n").unwrap();
+    test_repo.git(&["commit", "-am", "Change notes"]);
+    test_repo.git(&["checkout", "main"]);
+    std::fs::write(&file, "uncommitted\n").unwrap();
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    dispatch(&mut branch_list, Action::SelectNextBranch);
+    assert_eq!(selected_name(&branch_list), "zulu");
+
+    press(&mut branch_list, KeyCode::Char('c'));
+    assert_eq!(branch_list.pending_force_checkout.as_deref(), Some("zulu"));
+    press(&mut branch_list, KeyCode::Esc);
+    assert_eq!(branch_list.pending_force_checkout, None);
+    assert_eq!(test_repo.current_branch(), "main");
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "uncommitted\n");
+
+    press(&mut branch_list, KeyCode::Char('c'));
+    press(&mut branch_list, KeyCode::Char('y'));
+    assert_eq!(test_repo.current_branch(), "zulu");
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "zulu\n");
+    assert!(branch_list.get_selected_branch().unwrap().branch.is_head);
+  }
+
+  #[test]
+  fn remote_branches_are_shown_inline_and_checked_out_as_tracking_branches() {
+    let upstream = TestRepo::new();
+    upstream.git(&["branch", "feature"]);
+    let test_repo = TestRepo::new();
+    test_repo.git(&["remote", "add", "origin", &upstream.path().display().to_string()]);
+    test_repo.git(&["fetch", "origin"]);
+    test_repo.git(&["branch", "--set-upstream-to=origin/main", "main"]);
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    let names =
+      |branch_list: &BranchList| branch_list.branches.iter().map(|item| item.branch.name.clone()).collect::<Vec<_>>();
+
+    press(&mut branch_list, KeyCode::Char('r'));
+    assert_eq!(names(&branch_list), vec!["main", "origin/feature"]);
+    dispatch(&mut branch_list, Action::SelectNextBranch);
+    assert_eq!(selected_name(&branch_list), "origin/feature");
+    press(&mut branch_list, KeyCode::Char('d'));
+    assert!(!branch_list.get_selected_branch().unwrap().staged_for_deletion);
+
+    press(&mut branch_list, KeyCode::Char('c'));
+    assert_eq!(test_repo.current_branch(), "feature");
+    assert_eq!(names(&branch_list), vec!["feature", "main"]);
+    assert_eq!(selected_name(&branch_list), "feature");
+
+    press(&mut branch_list, KeyCode::Char('r'));
+    assert_eq!(names(&branch_list), vec!["feature", "main"]);
+  }
+
+  #[test]
+  fn renaming_a_tracked_branch_offers_to_rename_its_upstream() {
+    let test_repo = TestRepo::new();
+    test_repo.git(&["remote", "add", "origin", "https://example.com/repo.git"]);
+    test_repo.git(&["update-ref", "refs/remotes/origin/tracked", "HEAD"]);
+    test_repo.git(&["branch", "--track", "tracked", "origin/tracked"]);
+    test_repo.git(&["branch", "untracked"]);
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    let cases =
+      vec![("tracked", "tracked-2", Some(("tracked-2", "origin/tracked"))), ("untracked", "untracked-2", None)];
+
+    for (from, to, expected) in cases {
+      dispatch(&mut branch_list, Action::RenameBranch(String::from(from), String::from(to)));
+      assert_eq!(selected_name(&branch_list), to, "{}", from);
+      let pending =
+        branch_list.pending_rename_push.as_ref().map(|(branch, upstream)| (branch.as_str(), upstream.name.as_str()));
+      assert_eq!(pending, expected, "{}", from);
+      dispatch(&mut branch_list, Action::CancelRenamePush);
+    }
+    // Declining leaves the remote alone, the renamed branch still tracks the old name.
+    assert_eq!(test_repo.git(&["rev-parse", "--abbrev-ref", "tracked-2@{upstream}"]), "origin/tracked\n");
+  }
+
+  #[test]
+  fn read_only_refuses_pruning() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    branch_list.read_only = true;
+
+    press(&mut branch_list, KeyCode::Char('p'));
+    assert_eq!(branch_list.status.as_deref(), Some("read-only mode"));
+    dispatch(&mut branch_list, Action::PruneRemote(String::from("origin")));
+    assert!(branch_list.operation.is_none());
+  }
+
+  #[test]
+  fn read_only_refuses_fetching() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    branch_list.read_only = true;
+
+    press(&mut branch_list, KeyCode::Char('f'));
+    assert_eq!(branch_list.status.as_deref(), Some("read-only mode"));
+    assert!(branch_list.operation.is_none());
+  }
+
+  #[test]
+  fn remote_branches_can_be_limited_to_one_remote() {
+    let test_repo = TestRepo::new();
+    for remote in ["origin", "upstream"] {
+      test_repo.git(&["remote", "add", remote, &format!("https://example.com/{}.git", remote)]);
+      test_repo.git(&["update-ref", &format!("refs/remotes/{}/feature", remote), "HEAD"]);
+    }
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    branch_list.shown_remote = Some(String::from("upstream"));
+
+    press(&mut branch_list, KeyCode::Char('r'));
+    let names: Vec<&str> = branch_list.branches.iter().map(|item| item.branch.name.as_str()).collect();
+    assert_eq!(names, vec!["main", "upstream/feature"]);
+  }
+
+  #[test]
+  fn upstream_filter_cycles_through_tracking_untracked_and_gone() {
+    let test_repo = TestRepo::new();
+    test_repo.git(&["remote", "add", "origin", "https://example.com/repo.git"]);
+    test_repo.git(&["update-ref", "refs/remotes/origin/tracking", "HEAD"]);
+    test_repo.git(&["update-ref", "refs/remotes/origin/gone", "HEAD"]);
+    test_repo.git(&["branch", "--track", "tracking", "origin/tracking"]);
+    test_repo.git(&["branch", "--track", "gone", "origin/gone"]);
+    test_repo.git(&["update-ref", "-d", "refs/remotes/origin/gone"]);
+    test_repo.git(&["branch", "untracked"]);
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    let visible_names = |branch_list: &BranchList| -> Vec<String> {
+      branch_list.visible_indexes().into_iter().map(|index| branch_list.branches[index].branch.name.clone()).collect()
+    };
+    // main is checked out, so it stays in the list whatever the filter.
+    let cases: Vec<(&str, Vec<&str>)> = vec![
+      ("tracking", vec!["main", "tracking"]),
+      ("no upstream", vec!["main", "untracked"]),
+      ("gone", vec!["gone", "main"]),
+      ("all", vec!["gone", "main", "tracking", "untracked"]),
+    ];
+
+    for (description, expected) in cases {
+      press(&mut branch_list, KeyCode::Char('u'));
+      assert_eq!(branch_list.upstream_filter.label(), description);
+      assert_eq!(visible_names(&branch_list), expected, "{}", description);
+    }
+  }
+
+  #[test]
+  fn detached_checkout_from_the_log_offers_a_branch_to_reattach() {
+    let test_repo = TestRepo::new();
+    let first = test_repo.git(&["rev-parse", "HEAD"]);
+    test_repo.git(&["commit", "--allow-empty", "-m", "Second"]);
+    let mut branch_list = BranchList::new(Box::new(GitCliRepo::from_path(test_repo.path())));
+    press(&mut branch_list, KeyCode::Char('l'));
+    press(&mut branch_list, KeyCode::Down);
+
+    press(&mut branch_list, KeyCode::Char('c'));
+    press(&mut branch_list, KeyCode::Esc);
+    assert_eq!(test_repo.current_branch(), "main");
+
+    press(&mut branch_list, KeyCode::Char('c'));
+    press(&mut branch_list, KeyCode::Char('y'));
+    assert_eq!(test_repo.current_branch(), "");
+    assert_eq!(test_repo.git(&["rev-parse", "HEAD"]), first);
+    assert_eq!(branch_list.mode, Mode::BranchInput);
+
+    type_text(&mut branch_list, "rescue");
+    press(&mut branch_list, KeyCode::Enter);
+    assert_eq!(test_repo.current_branch(), "rescue");
+    assert_eq!(test_repo.git(&["rev-parse", "HEAD"]), first);
+  }
+
+  #[test]
+  fn checkout_previous_bounces_between_the_last_two_branches() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = branch_list_with_zulu_selected(&test_repo);
+
+    press(&mut branch_list, KeyCode::Char('-'));
+    assert_eq!(branch_list.status.as_deref(), Some("No previous branch to switch back to"));
+    assert_eq!(test_repo.current_branch(), "main");
+
+    press(&mut branch_list, KeyCode::Char('c'));
+    press(&mut branch_list, KeyCode::Char('-'));
+    assert_eq!(test_repo.current_branch(), "main");
+    assert_eq!(selected_name(&branch_list), "main");
+    assert!(branch_list.get_selected_branch().unwrap().branch.is_head);
+
+    press(&mut branch_list, KeyCode::Char('-'));
+    assert_eq!(test_repo.current_branch(), "zulu");
+    assert_eq!(selected_name(&branch_list), "zulu");
+  }
+
+  #[test]
+  fn recent_branches_are_offered_for_checkout() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = branch_list_with_zulu_selected(&test_repo);
+
+    press(&mut branch_list, KeyCode::Char('o'));
+    assert_eq!(branch_list.status.as_deref(), Some("No other branches checked out recently"));
+
+    press(&mut branch_list, KeyCode::Char('c'));
+    press(&mut branch_list, KeyCode::Char('-'));
+    press(&mut branch_list, KeyCode::Char('o'));
+    let labels: Vec<&str> =
+      branch_list.context_menu.as_ref().unwrap().items.iter().map(|item| item.label.as_str()).collect();
+    assert_eq!(labels, vec!["zulu"]);
+
+    press(&mut branch_list, KeyCode::Enter);
+    assert_eq!(test_repo.current_branch(), "zulu");
+  }
+
+  #[test]
+  fn context_menu_runs_the_chosen_operation() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = branch_list_with_zulu_selected(&test_repo);
+
+    press(&mut branch_list, KeyCode::Char('m'));
+    assert_eq!(branch_list.mode, Mode::Overlay);
+    assert_eq!(branch_list.context_menu.as_ref().unwrap().selected().unwrap().action, Action::CheckoutSelectedBranch);
+    press(&mut branch_list, KeyCode::Enter);
+
+    assert!(branch_list.context_menu.is_none());
+    assert_eq!(branch_list.mode, Mode::Default);
+    assert_eq!(test_repo.current_branch(), "zulu");
+
+    // Already checked out, so checkout isn't offered any more.
+    press(&mut branch_list, KeyCode::Char('m'));
+    let menu = branch_list.context_menu.as_ref().unwrap();
+    assert!(menu.items.iter().all(|item| item.action != Action::CheckoutSelectedBranch));
+  }
+
+  #[test]
+  fn failed_create_keeps_previous_selection() {
+    let test_repo = TestRepo::new();
+    let mut branch_list = branch_list_with_zulu_selected(&test_repo);
+
+    dispatch(&mut branch_list, Action::InitNewBranch);
+    dispatch(&mut branch_list, Action::CreateBranch(String::from("bad..name")));
+
+    assert!(branch_list.error.is_some());
+    assert_eq!(selected_name(&branch_list), "zulu");
+  }
+}
@@ -0,0 +1,64 @@
+use ratatui::{
+  layout::Rect,
+  style::{Color, Modifier, Style},
+  text::{Line, Span},
+  widgets::{Block, Clear, Paragraph},
+};
+
+use crate::{components::popup::centered_area, git::git_repo::GitCommit, tui::Frame};
+
+/// The commits and changes on one branch that aren't on a base branch.
+#[derive(Debug, Default, Clone)]
+pub struct BranchComparison {
+  pub base: String,
+  pub compare: String,
+  pub commits: Vec<GitCommit>,
+  pub diff_stat: String,
+  scroll: u16,
+}
+
+impl BranchComparison {
+  pub fn new(base: String, compare: String, commits: Vec<GitCommit>, diff_stat: String) -> Self {
+    BranchComparison { base, compare, commits, diff_stat, scroll: 0 }
+  }
+
+  pub fn scroll_up(&mut self) {
+    self.scroll = self.scroll.saturating_sub(1);
+  }
+
+  pub fn scroll_down(&mut self) {
+    self.scroll = self.scroll.saturating_add(1);
+  }
+
+  fn lines(&self) -> Vec<Line<'static>> {
+    let mut lines =
+      vec![Line::from(format!("{} commits on {} that are not on {}", self.commits.len(), self.compare, self.base))];
+    for commit in &self.commits {
+      lines.push(Line::from(vec![
+        Span::styled(format!("  {} ", commit.short_sha()), Style::default().fg(Color::Yellow)),
+        Span::raw(commit.subject.clone()),
+        Span::styled(format!(" ({})", commit.author), Style::default().add_modifier(Modifier::DIM)),
+      ]));
+    }
+    lines.push(Line::default());
+    lines.extend(self.diff_stat.lines().map(|line| Line::from(String::from(line))));
+    lines
+  }
+
+  pub fn render(&mut self, f: &mut Frame<'_>, area: Rect) {
+    let lines = self.lines();
+    let popup_area = centered_area(area, 80, 80);
+    let max_scroll = u16::try_from(lines.len()).unwrap_or(u16::MAX).saturating_sub(popup_area.height.saturating_sub(2));
+    self.scroll = self.scroll.min(max_scroll);
+    let popup = Paragraph::new(lines)
+      .block(
+        Block::bordered()
+          .title(format!("Compare {}..{}", self.base, self.compare))
+          .title_bottom("↑/↓: Scroll | esc/enter: Close"),
+      )
+      .style(Style::default().fg(Color::White))
+      .scroll((self.scroll, 0));
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup, popup_area);
+  }
+}
@@ -0,0 +1,76 @@
+use ratatui::{
+  layout::Rect,
+  style::{Color, Modifier, Style},
+  text::{Line, Span},
+  widgets::{Block, Clear, Paragraph, Wrap},
+};
+
+use crate::{
+  components::popup::centered_area,
+  git::{git_repo::GitBranchDetails, pull_request::PullRequest},
+  tui::Frame,
+  utils::format_timestamp,
+};
+
+/// What's known about the open pull request of the branch being shown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PullRequestStatus {
+  Loading,
+  Open(PullRequest),
+  NoneOpen,
+}
+
+#[derive(Debug, Default)]
+pub struct BranchDetailsPopup {}
+
+impl BranchDetailsPopup {
+  /// In a `shallow` clone the ahead/behind counts are marked as possibly wrong, the history they're counted over can be
+  /// cut off. The pull request line is only there when pull requests are being looked up.
+  pub fn render(
+    &self,
+    f: &mut Frame<'_>,
+    area: Rect,
+    details: &GitBranchDetails,
+    shallow: bool,
+    pull_request: Option<&PullRequestStatus>,
+  ) {
+    let upstream = match (&details.upstream, details.ahead_behind) {
+      (Some(upstream), Some((ahead, behind))) if shallow => {
+        format!("{} (↑{} ↓{}, may be inaccurate in a shallow clone)", upstream, ahead, behind)
+      },
+      (Some(upstream), Some((ahead, behind))) => format!("{} (↑{} ↓{})", upstream, ahead, behind),
+      (Some(upstream), None) => upstream.clone(),
+      (None, _) => String::from("none"),
+    };
+    let mut lines = vec![
+      detail_line("Ref", details.full_ref.clone()),
+      detail_line("SHA", details.sha.clone()),
+      detail_line("Upstream", upstream),
+      detail_line("Author", details.last_commit.author.clone()),
+      detail_line("Date", format_timestamp(details.last_commit.time)),
+      detail_line("Subject", details.last_commit.subject.clone()),
+    ];
+    match pull_request {
+      Some(PullRequestStatus::Loading) => lines.push(detail_line("PR", String::from("looking up…"))),
+      Some(PullRequestStatus::Open(pull_request)) => {
+        lines.push(detail_line("PR", format!("#{} {} ({})", pull_request.number, pull_request.title, pull_request.url)))
+      },
+      Some(PullRequestStatus::NoneOpen) => lines.push(detail_line("PR", String::from("none open"))),
+      None => {},
+    }
+    let popup_area = centered_area(area, 70, 50);
+    let popup = Paragraph::new(lines)
+      .block(Block::bordered().title("Branch Details").title_bottom("esc/enter: Close"))
+      .style(Style::default().fg(Color::White))
+      .wrap(Wrap { trim: false });
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup, popup_area);
+  }
+}
+
+fn detail_line(label: &str, value: String) -> Line<'static> {
+  Line::from(vec![
+    Span::styled(format!("{:<10}", format!("{}:", label)), Style::default().add_modifier(Modifier::DIM)),
+    Span::raw(value),
+  ])
+}
@@ -9,7 +9,8 @@ use tui_textarea::{CursorMove, Input, TextArea};
 
 use crate::{
   action::Action,
-  git::git_repo::{GitBranch, GitRepo},
+  git::git_repo::{BranchNameValidity, GitRepo},
+  mode::Mode,
   tui::Frame,
 };
 
@@ -22,16 +23,54 @@ pub struct InputState {
 pub struct BranchInput {
   pub text_input: TextArea<'static>,
   pub input_state: InputState,
+  // Only offered once an empty commit message is configured.
+  pub empty_commit_enabled: bool,
+  // The branch the new one starts from, HEAD when unset.
+  pub start_point: Option<String>,
+  // The branch being renamed, the input names a new branch when unset.
+  pub renaming: Option<String>,
+  // A valid name close to an invalid one, taken with tab.
+  pub suggestion: Option<String>,
 }
 
 impl BranchInput {
   pub fn new() -> Self {
-    BranchInput { text_input: TextArea::default(), input_state: InputState::default() }
+    BranchInput {
+      text_input: TextArea::default(),
+      input_state: InputState::default(),
+      empty_commit_enabled: false,
+      start_point: None,
+      renaming: None,
+      suggestion: None,
+    }
+  }
+
+  /// Start renaming `branch`, with its current name to edit.
+  pub fn start_rename(&mut self, branch: String) {
+    self.reset();
+    self.text_input.insert_str(&branch);
+    self.renaming = Some(branch);
+    self.init_style();
   }
 
   pub fn init_style(&mut self) {
     self.text_input.set_style(Style::default().fg(Color::White));
-    self.text_input.set_block(Block::default().borders(Borders::ALL));
+    self.text_input.set_block(self.block(None));
+  }
+
+  /// The input's border, titled with why the name is invalid or else with the branch being renamed or where the new
+  /// one will start from, and with any suggested name underneath.
+  fn block(&self, reason: Option<&str>) -> Block<'static> {
+    let mut block = Block::default().borders(Borders::ALL);
+    if let Some(suggestion) = &self.suggestion {
+      block = block.title_bottom(format!("tab: Use {}", suggestion));
+    }
+    match (reason, &self.renaming, &self.start_point) {
+      (Some(reason), _, _) => block.title(String::from(reason)),
+      (None, Some(renaming), _) => block.title(format!("Rename {}", renaming)),
+      (None, None, Some(start_point)) => block.title(format!("New branch from {}", start_point)),
+      (None, None, None) => block,
+    }
   }
 
   fn get_text(&self) -> Option<String> {
@@ -42,54 +81,109 @@ impl BranchInput {
     Some(input)
   }
 
-  fn validate_branch_name(&mut self, repo: &dyn GitRepo, current_branches: Vec<&GitBranch>) {
+  fn validate_branch_name(&mut self, repo: &dyn GitRepo) {
     if self.text_input.lines().first().is_none() {
       return;
     }
     let proposed_name = self.text_input.lines().first().unwrap();
-    let is_valid = repo.validate_branch_name(proposed_name);
-    let is_unique_name = !current_branches.iter().any(|b| b.name.eq(proposed_name));
-    if is_valid.is_err() || !is_valid.unwrap() || !is_unique_name {
-      self.text_input.set_style(Style::default().fg(Color::LightRed));
-      self.input_state.is_valid = Some(false);
+    let validity = repo.validate_branch_name(proposed_name).unwrap_or(BranchNameValidity::InvalidFormat);
+    self.suggestion = None;
+    if validity == BranchNameValidity::InvalidFormat {
+      let sanitized = sanitize_branch_name(proposed_name);
+      // Only worth offering if git takes it, it could still clash with an existing branch.
+      if !sanitized.is_empty()
+        && repo.validate_branch_name(&sanitized).is_ok_and(|validity| validity == BranchNameValidity::Valid)
+      {
+        self.suggestion = Some(sanitized);
+      }
+    }
+    let reason = match validity {
+      BranchNameValidity::Valid => {
+        self.text_input.set_style(Style::default().fg(Color::LightGreen));
+        self.text_input.set_block(self.block(None));
+        self.input_state.is_valid = Some(true);
+        return;
+      },
+      BranchNameValidity::InvalidFormat => "Invalid branch name",
+      BranchNameValidity::AlreadyExists => "Branch already exists",
+    };
+    self.text_input.set_style(Style::default().fg(Color::LightRed));
+    self.text_input.set_block(self.block(Some(reason)));
+    self.input_state.is_valid = Some(false);
+  }
+
+  /// Empty the input and forget the previous name, so nothing lingers in the list or the next time input is opened.
+  fn reset(&mut self) {
+    self.text_input.move_cursor(CursorMove::Head);
+    self.text_input.delete_line_by_end();
+    self.input_state = InputState::default();
+    self.start_point = None;
+    self.renaming = None;
+    self.suggestion = None;
+    self.init_style();
+  }
+
+  /// Swap the typed name for the suggested one.
+  fn accept_suggestion(&mut self, repo: &dyn GitRepo) {
+    let Some(suggestion) = self.suggestion.take() else {
       return;
+    };
+    self.text_input.move_cursor(CursorMove::Head);
+    self.text_input.delete_line_by_end();
+    self.text_input.insert_str(&suggestion);
+    self.validate_branch_name(repo);
+    self.input_state.value = Some(suggestion);
+  }
+
+  fn submit(&mut self, create: impl FnOnce(String) -> Action) -> Option<Action> {
+    if self.input_state.is_valid.is_some() && !self.input_state.is_valid? {
+      // TODO report error
+      return None;
+    }
+    let new_branch_name = self.get_text();
+    // purposely don't send the key, we want to delete the line
+    self.reset();
+    if let Some(name) = new_branch_name {
+      return Some(create(name));
     }
-    self.text_input.set_style(Style::default().fg(Color::LightGreen));
-    self.input_state.is_valid = Some(true);
+
+    Some(Action::SetMode(Mode::Default))
   }
 
-  pub fn handle_key_event(
-    &mut self,
-    key_event: KeyEvent,
-    repo: &dyn GitRepo,
-    current_branches: Vec<&GitBranch>,
-  ) -> Option<Action> {
+  pub fn handle_key_event(&mut self, key_event: KeyEvent, repo: &dyn GitRepo) -> Option<Action> {
     match key_event {
-      KeyEvent { code: KeyCode::Esc, modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
-        self.input_state.value = None;
+      KeyEvent { code: KeyCode::Esc, modifiers: _, kind: _, state: _ } => {
         // purposely don't send the key, we want to delete the line
-        self.text_input.move_cursor(CursorMove::Head);
-        self.text_input.delete_line_by_end();
-        Some(Action::EndInputMod)
+        self.reset();
+        Some(Action::SetMode(Mode::Default))
       },
-      KeyEvent { code: KeyCode::Enter, modifiers: _, kind: _, state: _ } => {
-        if self.input_state.is_valid.is_some() && !self.input_state.is_valid? {
-          // TODO report error
-          return None;
-        }
-        let new_branch_name = self.get_text();
-        // purposely don't send the key, we want to delete the line
-        self.text_input.move_cursor(CursorMove::Head);
-        self.text_input.delete_line_by_end();
-        if let Some(name) = new_branch_name {
-          return Some(Action::CreateBranch(name));
-        }
-
-        Some(Action::EndInputMod)
+      KeyEvent { code: KeyCode::Enter, modifiers: _, kind: _, state: _ } if self.renaming.is_some() => {
+        let from = self.renaming.clone().unwrap_or_default();
+        self.submit(move |name| Action::RenameBranch(from, name))
+      },
+      // Starting from another branch never checks out, so there's only the one way to submit.
+      KeyEvent { code: KeyCode::Enter, modifiers: _, kind: _, state: _ } if self.start_point.is_some() => {
+        let start_point = self.start_point.clone().unwrap_or_default();
+        self.submit(move |name| Action::CreateBranchFrom(name, start_point))
+      },
+      KeyEvent { code: KeyCode::Enter, modifiers: KeyModifiers::ALT, kind: _, state: _ } => {
+        self.submit(Action::CreateBranchWithoutCheckout)
+      },
+      KeyEvent { code: KeyCode::Enter, modifiers: _, kind: _, state: _ } => self.submit(Action::CreateBranch),
+      KeyEvent { code: KeyCode::Tab, modifiers: KeyModifiers::NONE, kind: _, state: _ }
+        if self.suggestion.is_some() =>
+      {
+        self.accept_suggestion(repo);
+        None
+      },
+      KeyEvent { code: KeyCode::Char('e'), modifiers: KeyModifiers::CONTROL, kind: _, state: _ }
+        if self.empty_commit_enabled && self.start_point.is_none() && self.renaming.is_none() =>
+      {
+        self.submit(Action::CreateBranchWithEmptyCommit)
       },
       _ => {
         if self.text_input.input(Input::from(key_event)) {
-          self.validate_branch_name(repo, current_branches);
+          self.validate_branch_name(repo);
           let new_branch_name = self.get_text();
           if new_branch_name.is_some() {
             self.input_state.value = new_branch_name;
@@ -104,3 +198,76 @@ impl BranchInput {
     f.render_widget(&self.text_input, area);
   }
 }
+
+/// The closest name to `name` that git accepts, following `git check-ref-format`: whitespace becomes `-`, characters
+/// git forbids are dropped and `..`, `@{`, leading dots, `.lock` endings and stray slashes are cleaned up. Empty when
+/// nothing usable is left.
+pub fn sanitize_branch_name(name: &str) -> String {
+  let cleaned: String = name
+    .trim()
+    .chars()
+    .filter_map(|c| {
+      match c {
+        c if c.is_whitespace() => Some('-'),
+        c if c.is_control() => None,
+        '~' | '^' | ':' | '?' | '*' | '[' | '\\' => None,
+        c => Some(c),
+      }
+    })
+    .collect();
+  let cleaned = cleaned.replace("@{", "@");
+  let components: Vec<String> = cleaned
+    .split('/')
+    .map(|component| {
+      let mut component = String::from(component);
+      while component.contains("..") {
+        component = component.replace("..", ".");
+      }
+      while component.contains("--") {
+        component = component.replace("--", "-");
+      }
+      let mut component = component.trim_start_matches('.');
+      while let Some(stripped) = component.strip_suffix(".lock") {
+        component = stripped;
+      }
+      String::from(component.trim_end_matches('.'))
+    })
+    .filter(|component| !component.is_empty())
+    .collect();
+  let sanitized = components.join("/");
+  let sanitized = sanitized.trim_start_matches('-');
+  if sanitized == "@" {
+    return String::new();
+  }
+  String::from(sanitized)
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn sanitize_branch_name_cases() {
+    let cases = vec![
+      ("already valid", "feature/login", "feature/login"),
+      ("spaces", "fix the  login bug", "fix-the-login-bug"),
+      ("double dots", "release..1.2", "release.1.2"),
+      ("trailing slash", "feature/", "feature"),
+      ("repeated slashes", "feature//login", "feature/login"),
+      ("forbidden characters", "what?is:this*[x]~^\\", "whatisthisx]"),
+      ("reflog syntax", "main@{1}", "main@1}"),
+      ("leading dot component", "feature/.hidden", "feature/hidden"),
+      ("lock suffix", "topic.lock", "topic"),
+      ("trailing dot", "topic.", "topic"),
+      ("leading dash", "-topic", "topic"),
+      ("lone at", "@", ""),
+      ("nothing usable", " ~^ ", ""),
+    ];
+
+    for (description, name, expected) in cases {
+      assert_eq!(sanitize_branch_name(name), expected, "{}", description);
+    }
+  }
+}
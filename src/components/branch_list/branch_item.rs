@@ -1,10 +1,19 @@
+use std::borrow::Cow;
+
 use ratatui::{
   style::{Color, Modifier, Style},
   text::{Line, Span},
   widgets::ListItem,
 };
 
-use crate::git::git_repo::GitBranch;
+use crate::{
+  git::git_repo::{GitBranch, GitRemoteBranch},
+  theme,
+  utils::{format_relative_time, now_timestamp, truncate_with_ellipsis},
+};
+
+// Names are never cut shorter than this to make room for the details after them, those can be clipped instead.
+const MIN_NAME_WIDTH: usize = 12;
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct BranchItem {
@@ -12,30 +21,107 @@ pub struct BranchItem {
   pub staged_for_deletion: bool,
   pub staged_for_creation: bool,
   pub is_valid_name: bool,
+  /// Set for a remote branch that no local branch tracks, listed alongside the local ones when remotes are shown.
+  pub remote: Option<GitRemoteBranch>,
 }
 
 impl BranchItem {
   pub fn new(branch: GitBranch, is_valid_name: bool) -> Self {
-    BranchItem { branch, staged_for_deletion: false, staged_for_creation: false, is_valid_name }
+    BranchItem { branch, staged_for_deletion: false, staged_for_creation: false, is_valid_name, remote: None }
+  }
+
+  /// An entry for `remote_branch`, whose `branch` is named like `origin/feature`.
+  pub fn remote(branch: GitBranch, remote_branch: GitRemoteBranch) -> Self {
+    BranchItem { remote: Some(remote_branch), ..BranchItem::new(branch, true) }
   }
 
-  pub fn render(&self) -> ListItem {
+  /// Render into a row `width` cells wide. The name is shortened so the details after it stay visible, and to
+  /// `max_name_width` if one is configured. A leading namespace in `abbreviated_prefixes` is shown as `…`, and stale
+  /// branches have their name drawn in `stale_color`. The first occurrence of `highlight`, ignoring case, is picked out
+  /// in the name so it's clear why a jump landed there. `commit_count` is shown next to the name once it's known.
+  pub fn render(
+    &self,
+    width: usize,
+    max_name_width: Option<usize>,
+    abbreviated_prefixes: &[String],
+    stale_color: Option<Color>,
+    highlight: Option<&str>,
+    commit_count: Option<usize>,
+  ) -> ListItem {
+    let mut details = Vec::new();
+    if let Some(count) = commit_count {
+      let label = if count == 1 { "commit" } else { "commits" };
+      details.push(Span::styled(format!(" {} {}", count, label), Style::default().fg(Color::Blue)));
+    }
+    if self.branch.is_head {
+      details.push(Span::styled(" (HEAD)", Style::default().add_modifier(Modifier::DIM)));
+    }
+    if let Some(upstream) = &self.branch.upstream {
+      let dim = Style::default().add_modifier(Modifier::DIM);
+      match &upstream.remote {
+        // The remote is picked out so branches tracking a fork stand apart from ones tracking origin.
+        Some(remote) => {
+          details.push(Span::styled(" [", dim));
+          details.push(Span::styled(remote.clone(), Style::default().fg(Color::Cyan)));
+          details.push(Span::styled(format!("/{}]", upstream.branch_name()), dim));
+        },
+        None => details.push(Span::styled(format!(" [{}]", upstream.name), dim)),
+      }
+    }
+    // Without colors these would be indistinguishable from any other branch.
+    if !theme::styling_enabled() && self.staged_for_deletion {
+      details.push(Span::raw(" (delete)"));
+    }
+    if !theme::styling_enabled() && stale_color.is_some() {
+      details.push(Span::raw(" (stale)"));
+    }
+    if self.branch.parse_failed {
+      details.push(Span::styled(" (details unavailable)", Style::default().fg(Color::Magenta)));
+    }
+    if self.branch.upstream_gone {
+      details.push(Span::styled(" gone", Style::default().fg(Color::Red)));
+    }
+    if let Some(last_commit_time) = self.branch.last_commit_time {
+      let relative_time = format_relative_time(last_commit_time, now_timestamp());
+      details.push(Span::styled(format!(" {}", relative_time), Style::default().add_modifier(Modifier::DIM)));
+    }
+    let details_width: usize = details.iter().map(Span::width).sum();
+    let name_width = width.saturating_sub(details_width).max(MIN_NAME_WIDTH).min(max_name_width.unwrap_or(usize::MAX));
+
     let mut text = Line::default();
     let mut parts = Vec::new();
-    let mut name = Span::styled(self.branch.name.clone(), Style::default());
+    let display_name = match &self.remote {
+      Some(_) => Cow::Owned(format!("remotes/{}", self.branch.name)),
+      None => abbreviate_name(&self.branch.name, abbreviated_prefixes),
+    };
+    let name = truncate_with_ellipsis(&display_name, name_width);
+    let mut name_style = Style::default();
+    if let Some(stale_color) = stale_color {
+      name_style = Style::default().fg(stale_color);
+    }
+    if self.remote.is_some() {
+      name_style = Style::default().fg(Color::Cyan).add_modifier(Modifier::ITALIC);
+    }
     if self.staged_for_deletion {
-      name = name.style(Style::default().fg(Color::Red));
+      name_style = Style::default().fg(Color::Red);
     }
     if self.staged_for_creation {
-      name = name.style(Style::default().fg(if self.is_valid_name { Color::LightGreen } else { Color::LightRed }));
+      name_style = Style::default().fg(if self.is_valid_name { Color::LightGreen } else { Color::LightRed });
     }
-    parts.push(name);
-    if self.branch.is_head {
-      parts.push(Span::styled(" (HEAD)", Style::default().add_modifier(Modifier::DIM)));
+    match highlight.and_then(|query| find_ignoring_case(&name, query)) {
+      Some((start, end)) => {
+        // Bold and underlined rather than recoloured, so the match doesn't hide that a branch is staged or stale.
+        let match_style = name_style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+        parts.push(Span::styled(String::from(&name[..start]), name_style));
+        parts.push(Span::styled(String::from(&name[start..end]), match_style));
+        parts.push(Span::styled(String::from(&name[end..]), name_style));
+      },
+      None => parts.push(Span::styled(name, name_style)),
     }
-    if self.branch.upstream.is_some() {
-      let upstream = self.branch.upstream.clone();
-      parts.push(Span::styled(format!(" [{}]", upstream.unwrap().name), Style::default().add_modifier(Modifier::DIM)));
+    parts.extend(details);
+    // Last and left out of the width above, so a long description is clipped rather than squeezing the name.
+    if let Some(description) = &self.branch.description {
+      parts.push(Span::styled(format!(" · {}", description), Style::default().add_modifier(Modifier::DIM)));
     }
     text = text.spans(parts);
     ListItem::from(text)
@@ -45,3 +131,73 @@ impl BranchItem {
     self.staged_for_deletion = stage;
   }
 }
+
+/// Replace the first matching namespace at the start of `name` with an ellipsis, e.g. `john/feature/x` with the prefix
+/// `john` becomes `…/feature/x`. Prefixes only match whole path segments and a name is never shortened to nothing.
+fn abbreviate_name<'a>(name: &'a str, prefixes: &[String]) -> Cow<'a, str> {
+  for prefix in prefixes {
+    let prefix = prefix.trim_end_matches('/');
+    if prefix.is_empty() {
+      continue;
+    }
+    if let Some(rest) = name.strip_prefix(prefix).filter(|rest| rest.starts_with('/') && rest.len() > 1) {
+      return Cow::Owned(format!("…{}", rest));
+    }
+  }
+  Cow::Borrowed(name)
+}
+
+/// The byte range of the first occurrence of `query` in `text`, comparing characters case insensitively.
+fn find_ignoring_case(text: &str, query: &str) -> Option<(usize, usize)> {
+  let query: Vec<char> = query.chars().collect();
+  if query.is_empty() {
+    return None;
+  }
+  let chars: Vec<(usize, char)> = text.char_indices().collect();
+  chars.windows(query.len()).find_map(|window| {
+    let matches = window.iter().zip(&query).all(|((_, c), q)| c.to_lowercase().eq(q.to_lowercase()));
+    let (start, _) = window[0];
+    let (last, last_char) = window[window.len() - 1];
+    matches.then_some((start, last + last_char.len_utf8()))
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn abbreviate_name_only_shortens_whole_leading_segments() {
+    let prefixes = vec![String::from("john/"), String::from("team"), String::new()];
+    assert_eq!(abbreviate_name("john/feature/x", &prefixes), "…/feature/x");
+    // The trailing slash is optional in the config.
+    assert_eq!(abbreviate_name("team/fix", &prefixes), "…/fix");
+    assert_eq!(abbreviate_name("john/team/fix", &prefixes), "…/team/fix");
+    assert_eq!(abbreviate_name("johnny/feature", &prefixes), "johnny/feature");
+    assert_eq!(abbreviate_name("feature/john/x", &prefixes), "feature/john/x");
+    assert_eq!(abbreviate_name("john/", &prefixes), "john/");
+  }
+
+  #[test]
+  fn wide_names_are_cut_to_the_cells_they_take() {
+    // 25 cells, every character but the slash is two wide.
+    let item = BranchItem::new(GitBranch::new(String::from("機能/ログイン画面の修正🚀")), true);
+    assert_eq!(item.render(40, None, &[], None, None, None).width(), 25);
+    assert_eq!(item.render(14, None, &[], None, None, None).width(), 14);
+    // A wide character that would straddle the edge is dropped whole.
+    assert_eq!(item.render(15, None, &[], None, None, None).width(), 14);
+  }
+
+  #[test]
+  fn find_ignoring_case_returns_the_first_byte_range() {
+    assert_eq!(find_ignoring_case("feature/1234", "1234"), Some((8, 12)));
+    assert_eq!(find_ignoring_case("Feature/X", "feature/x"), Some((0, 9)));
+    assert_eq!(find_ignoring_case("ab-ab", "ab"), Some((0, 2)));
+    // Offsets are in bytes, so they can slice the name directly.
+    assert_eq!(find_ignoring_case("fix/çava", "çav"), Some((4, 8)));
+    assert_eq!(find_ignoring_case("main", "dev"), None);
+    assert_eq!(find_ignoring_case("main", ""), None);
+  }
+}
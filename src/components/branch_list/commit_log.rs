@@ -0,0 +1,160 @@
+use ratatui::{
+  layout::Rect,
+  style::{Color, Modifier, Style},
+  text::{Line, Span, Text},
+  widgets::{Block, Clear, List, ListItem, ListState},
+};
+
+use crate::{
+  components::popup::centered_area,
+  git::git_repo::GitCommit,
+  tui::Frame,
+  utils::{format_relative_time, now_timestamp},
+};
+
+/// The most recent commits on a branch with one of them selected, loaded a page at a time as the selection moves down.
+#[derive(Debug, Default, Clone)]
+pub struct CommitLog {
+  pub branch: String,
+  pub commits: Vec<GitCommit>,
+  // Set once a page comes back short, there is no more history to load.
+  reached_end: bool,
+  list_state: ListState,
+}
+
+impl CommitLog {
+  pub fn new(branch: String, commits: Vec<GitCommit>, page_size: usize) -> Self {
+    let selected = if commits.is_empty() { None } else { Some(0) };
+    let reached_end = commits.len() < page_size;
+    CommitLog { branch, commits, reached_end, list_state: ListState::default().with_selected(selected) }
+  }
+
+  /// Whether the selection is on the last loaded commit and there may be older ones.
+  pub fn needs_more(&self) -> bool {
+    !self.reached_end && self.list_state.selected().is_some_and(|selected| selected + 1 >= self.commits.len())
+  }
+
+  pub fn append(&mut self, commits: Vec<GitCommit>, page_size: usize) {
+    self.reached_end = commits.len() < page_size;
+    self.commits.extend(commits);
+  }
+
+  pub fn selected(&self) -> Option<&GitCommit> {
+    self.commits.get(self.list_state.selected()?)
+  }
+
+  pub fn select_previous(&mut self) {
+    if let Some(selected) = self.list_state.selected() {
+      self.list_state.select(Some(selected.saturating_sub(1)));
+    }
+  }
+
+  pub fn select_next(&mut self) {
+    if let Some(selected) = self.list_state.selected() {
+      self.list_state.select(Some((selected + 1).min(self.commits.len().saturating_sub(1))));
+    }
+  }
+
+  /// In a `shallow` clone the end of the log is marked as the end of the fetched history, not the branch's first commit.
+  /// Keys for actions that would be refused, like in read-only mode, aren't shown.
+  pub fn render(&mut self, f: &mut Frame<'_>, area: Rect, shallow: bool, can_cherry_pick: bool, can_checkout: bool) {
+    let now = now_timestamp();
+    let items: Vec<ListItem> = self
+      .commits
+      .iter()
+      .map(|commit| {
+        let mut text = Text::from(Line::from(vec![
+          Span::styled(format!("{} ", commit.short_sha()), Style::default().fg(Color::Yellow)),
+          Span::raw(commit.subject.clone()),
+          Span::styled(
+            format!(" ({}, {})", commit.author, format_relative_time(commit.time, now)),
+            Style::default().add_modifier(Modifier::DIM),
+          ),
+        ]));
+        // Indented under the sha so it reads as belonging to the commit above.
+        for note_line in commit.note.iter().flat_map(|note| note.lines()) {
+          text.push_line(Line::styled(format!("        {}", note_line), Style::default().add_modifier(Modifier::DIM)));
+        }
+        ListItem::new(text)
+      })
+      .collect();
+    let mut instructions = vec!["↑/↓: Select"];
+    if can_cherry_pick {
+      instructions.push("p: Cherry-pick onto HEAD");
+    }
+    if can_checkout {
+      instructions.push("c: Checkout detached");
+    }
+    instructions.push("esc/enter: Close");
+    let popup_area = centered_area(area, 80, 80);
+    let list = List::new(items)
+      .block(
+        Block::bordered()
+          .title(if self.reached_end && shallow {
+            format!("Log {} (older history missing from the shallow clone)", self.branch)
+          } else if self.reached_end {
+            format!("Log {}", self.branch)
+          } else {
+            format!("Log {} ({} loaded, more below)", self.branch, self.commits.len())
+          })
+          .title_bottom(instructions.join(" | ")),
+      )
+      .style(Style::default().fg(Color::White))
+      .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+      .highlight_symbol("→");
+    f.render_widget(Clear, popup_area);
+    f.render_stateful_widget(list, popup_area, &mut self.list_state);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn commits(subjects: &[&str]) -> Vec<GitCommit> {
+    subjects.iter().map(|subject| GitCommit { subject: String::from(*subject), ..GitCommit::default() }).collect()
+  }
+
+  #[test]
+  fn loads_more_at_the_end_until_a_short_page() {
+    let mut log = CommitLog::new(String::from("main"), commits(&["c4", "c3"]), 2);
+    assert!(!log.needs_more(), "first commit selected");
+
+    log.select_next();
+    assert!(log.needs_more(), "last loaded commit selected");
+
+    log.append(commits(&["c2"]), 2);
+    assert!(!log.needs_more(), "more loaded below the selection");
+    log.select_next();
+    assert!(!log.needs_more(), "a short page is the end of the history");
+
+    log.select_next();
+    assert_eq!(log.selected().map(|commit| commit.subject.as_str()), Some("c2"), "stays on the last commit");
+    log.select_previous();
+    log.select_previous();
+    log.select_previous();
+    assert_eq!(log.selected().map(|commit| commit.subject.as_str()), Some("c4"), "stays on the first commit");
+  }
+
+  #[test]
+  fn footer_only_shows_allowed_actions() {
+    let footer = |can_cherry_pick, can_checkout| {
+      let mut log = CommitLog::new(String::from("main"), commits(&["c1"]), 2);
+      let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(120, 10)).unwrap();
+      terminal.draw(|f| log.render(f, f.area(), false, can_cherry_pick, can_checkout)).unwrap();
+      let buffer = terminal.backend().buffer();
+      (0..10).map(|y| (0..120).map(|x| buffer[(x, y)].symbol()).collect::<String>()).collect::<String>()
+    };
+
+    let all = footer(true, true);
+    assert!(
+      all.contains("↑/↓: Select | p: Cherry-pick onto HEAD | c: Checkout detached | esc/enter: Close"),
+      "{}",
+      all
+    );
+    let read_only = footer(false, false);
+    assert!(read_only.contains("↑/↓: Select | esc/enter: Close"), "{}", read_only);
+  }
+}
@@ -0,0 +1,79 @@
+use ratatui::{
+  layout::Rect,
+  style::{Color, Modifier, Style},
+  text::{Line, Span},
+  widgets::{Block, Clear, List, ListItem, ListState},
+};
+
+use crate::{action::Action, components::popup::centered_area, tui::Frame};
+
+/// An operation offered in the context menu, along with the key that runs it directly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuItem {
+  pub key: &'static str,
+  pub label: String,
+  pub action: Action,
+}
+
+impl MenuItem {
+  pub fn new(key: &'static str, label: impl Into<String>, action: Action) -> Self {
+    MenuItem { key, label: label.into(), action }
+  }
+}
+
+/// The operations available on one branch, for browsing rather than remembering keys. Also used to pick between a
+/// few choices, like which remote to prune.
+#[derive(Debug, Default, Clone)]
+pub struct ContextMenu {
+  pub title: String,
+  pub items: Vec<MenuItem>,
+  list_state: ListState,
+}
+
+impl ContextMenu {
+  pub fn new(title: String, items: Vec<MenuItem>) -> Self {
+    let selected = if items.is_empty() { None } else { Some(0) };
+    ContextMenu { title, items, list_state: ListState::default().with_selected(selected) }
+  }
+
+  pub fn selected(&self) -> Option<&MenuItem> {
+    self.items.get(self.list_state.selected()?)
+  }
+
+  pub fn select_previous(&mut self) {
+    if let Some(selected) = self.list_state.selected() {
+      self.list_state.select(Some(selected.saturating_sub(1)));
+    }
+  }
+
+  pub fn select_next(&mut self) {
+    if let Some(selected) = self.list_state.selected() {
+      self.list_state.select(Some((selected + 1).min(self.items.len().saturating_sub(1))));
+    }
+  }
+
+  pub fn render(&mut self, f: &mut Frame<'_>, area: Rect) {
+    let key_width = self.items.iter().map(|item| item.key.chars().count()).max().unwrap_or(0);
+    let items: Vec<ListItem> = self
+      .items
+      .iter()
+      .map(|item| {
+        ListItem::from(Line::from(vec![
+          Span::styled(
+            format!(" {:<width$}  ", item.key, width = key_width),
+            Style::default().add_modifier(Modifier::DIM),
+          ),
+          Span::raw(item.label.clone()),
+        ]))
+      })
+      .collect();
+    let list = List::new(items)
+      .block(Block::bordered().title(self.title.clone()).title_bottom("↑/↓: Select | enter: Run | esc: Close"))
+      .style(Style::default().fg(Color::White))
+      .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+      .highlight_symbol("→");
+    let popup_area = centered_area(area, 50, 60);
+    f.render_widget(Clear, popup_area);
+    f.render_stateful_widget(list, popup_area, &mut self.list_state);
+  }
+}
@@ -0,0 +1,64 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  layout::Rect,
+  prelude::Color,
+  style::Style,
+  widgets::{Block, Borders},
+};
+use tui_textarea::{CursorMove, Input, TextArea};
+
+use crate::{action::Action, mode::Mode, tui::Frame};
+
+/// A prompt for what a branch is for, starting from its current description.
+pub struct DescriptionInput {
+  pub text_input: TextArea<'static>,
+  // The branch being described.
+  pub branch: Option<String>,
+}
+
+impl DescriptionInput {
+  pub fn new() -> Self {
+    DescriptionInput { text_input: TextArea::default(), branch: None }
+  }
+
+  /// Start describing `branch`, with the cursor after its `current` description so it can be amended.
+  pub fn start(&mut self, branch: String, current: Option<&str>) {
+    self.clear();
+    self.text_input.insert_str(current.unwrap_or_default());
+    self.text_input.set_style(Style::default().fg(Color::White));
+    self.text_input.set_block(Block::default().borders(Borders::ALL).title(format!("Description of {}", branch)));
+    self.branch = Some(branch);
+  }
+
+  fn clear(&mut self) {
+    self.text_input.move_cursor(CursorMove::Head);
+    self.text_input.delete_line_by_end();
+    self.branch = None;
+  }
+
+  pub fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<Action> {
+    match key_event {
+      KeyEvent { code: KeyCode::Esc, modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        self.clear();
+        Some(Action::SetMode(Mode::Default))
+      },
+      KeyEvent { code: KeyCode::Enter, modifiers: _, kind: _, state: _ } => {
+        let description = String::from(self.text_input.lines().first().map(|line| line.trim()).unwrap_or_default());
+        let branch = self.branch.clone();
+        self.clear();
+        match branch {
+          Some(branch) => Some(Action::SetBranchDescription(branch, description)),
+          None => Some(Action::SetMode(Mode::Default)),
+        }
+      },
+      _ => {
+        self.text_input.input(Input::from(key_event));
+        None
+      },
+    }
+  }
+
+  pub fn render(&mut self, f: &mut Frame<'_>, area: Rect) {
+    f.render_widget(&self.text_input, area);
+  }
+}
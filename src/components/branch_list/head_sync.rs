@@ -0,0 +1,57 @@
+use ratatui::{
+  style::{Color, Style},
+  text::Line,
+};
+
+/// How the checked out branch compares with its upstream, what matters most before committing or pushing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadSync {
+  pub branch: String,
+  /// Commits (ahead, behind) the upstream, unknown once the upstream is gone.
+  pub ahead_behind: Option<(usize, usize)>,
+}
+
+impl HeadSync {
+  /// What to do to get back in sync.
+  pub fn suggestion(&self) -> &'static str {
+    match self.ahead_behind {
+      None => "upstream gone",
+      Some((0, 0)) => "up to date",
+      Some((_, 0)) => "push needed",
+      Some((0, _)) => "pull needed",
+      Some(_) => "diverged, pull then push",
+    }
+  }
+
+  pub fn to_line(&self) -> Line<'static> {
+    let text = match self.ahead_behind {
+      Some((ahead, behind)) => format!(" {} ↑{} ↓{} ({}) ", self.branch, ahead, behind, self.suggestion()),
+      None => format!(" {} ({}) ", self.branch, self.suggestion()),
+    };
+    let color = if self.ahead_behind == Some((0, 0)) { Color::Green } else { Color::Yellow };
+    Line::styled(text, Style::default().fg(color)).right_aligned()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn suggestion_cases() {
+    let cases = vec![
+      ("in sync", Some((0, 0)), "up to date"),
+      ("ahead", Some((2, 0)), "push needed"),
+      ("behind", Some((0, 3)), "pull needed"),
+      ("diverged", Some((1, 1)), "diverged, pull then push"),
+      ("upstream deleted", None, "upstream gone"),
+    ];
+
+    for (description, ahead_behind, expected) in cases {
+      let head_sync = HeadSync { branch: String::from("main"), ahead_behind };
+      assert_eq!(head_sync.suggestion(), expected, "{}", description);
+    }
+  }
+}
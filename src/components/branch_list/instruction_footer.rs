@@ -1,35 +1,183 @@
 use ratatui::{
   layout::Rect,
   prelude::{Line, Span},
+  style::{Color, Style},
+  widgets::Paragraph,
 };
 
-use crate::{components::branch_list::branch_item::BranchItem, tui::Frame};
+use crate::{
+  components::branch_list::BranchList, config::BranchSort, git::git_repo::short_sha, mode::Mode, tui::Frame,
+};
+
+const SEPARATOR: &str = " | ";
 
 #[derive(Debug, Default)]
 pub struct InstructionFooter {}
 
 impl InstructionFooter {
-  pub fn render(&self, f: &mut Frame<'_>, area: Rect, branches: &[BranchItem], selected: Option<&BranchItem>) {
-    let mut commands = vec![Span::raw("esc: Quit")];
-    commands.push(Span::raw(" | ⇧ + c: Checkout new"));
-    if selected.is_some() && selected.unwrap().staged_for_deletion {
-      commands.push(Span::raw(" | d: Delete"));
-      commands.push(Span::raw(" | ⇧ + d: Unstage for deletion"));
+  /// Rows needed to show every command in `width`, commands wrap onto more rows in narrow terminals instead of being
+  /// cut off.
+  pub fn height(&self, width: u16, branch_list: &BranchList) -> u16 {
+    u16::try_from(wrap_commands(self.commands(branch_list), width).len()).unwrap_or(u16::MAX)
+  }
+
+  pub fn render(&self, f: &mut Frame<'_>, area: Rect, branch_list: &BranchList) {
+    let rows = wrap_commands(self.commands(branch_list), area.width);
+    f.render_widget(Paragraph::new(rows), area);
+  }
+
+  fn commands(&self, branch_list: &BranchList) -> Vec<Span<'static>> {
+    if let Some(status) = &branch_list.status {
+      return vec![Span::styled(status.clone(), Style::default().fg(Color::Yellow))];
+    }
+    if branch_list.mode == Mode::DescriptionInput {
+      return vec![Span::raw("esc: Cancel"), Span::raw("enter: Save, empty to remove")];
+    }
+    if branch_list.mode == Mode::BranchInput {
+      if branch_list.branch_input.renaming.is_some() {
+        return vec![Span::raw("esc: Cancel"), Span::raw("enter: Rename")];
+      }
+      if let Some(start_point) = &branch_list.branch_input.start_point {
+        return vec![
+          Span::raw("esc: Cancel"),
+          Span::raw(format!("enter: Create from {} without checkout", start_point)),
+        ];
+      }
+      let mut commands =
+        vec![Span::raw("esc: Cancel"), Span::raw("enter: Create and checkout"), Span::raw("alt + enter: Create")];
+      if branch_list.branch_input.empty_commit_enabled {
+        commands.push(Span::raw("^ + e: Create with empty commit"));
+      }
+      return commands;
     }
 
-    if selected.is_some() && !selected.unwrap().branch.is_head {
-      commands.push(Span::raw(" | d: Stage for deletion"));
+    // Hidden keys still leave a way back to them.
+    if !branch_list.keymap.pinned {
+      return vec![Span::raw("?: Show keys")];
+    }
+
+    let selected = branch_list.get_selected_branch();
+    let is_local = selected.is_some_and(|item| item.remote.is_none());
+    let can_checkout = !branch_list.is_bare && !branch_list.read_only;
+    let can_delete = !branch_list.read_only;
+    let mut commands = Vec::new();
+    // Where the selection points, so a branch that moved is noticed without opening its details.
+    if let Some(tip_sha) = selected.and_then(|item| item.branch.tip_sha.as_deref()) {
+      commands.push(Span::styled(format!("@ {}", short_sha(tip_sha)), Style::default().fg(Color::Yellow)));
+    }
+    commands.push(Span::raw("q/esc: Quit"));
+    if branch_list.list_columns > 1 {
+      commands.push(Span::raw("←/→: Change column"));
+    }
+    if can_checkout {
+      commands.push(Span::raw("⇧ + c: Checkout new"));
+      commands.push(Span::raw("g: Checkout by prefix"));
+      commands.push(Span::raw("-: Checkout previous"));
+      commands.push(Span::raw("o: Recent branches"));
+    }
+    commands.push(Span::raw("/: Jump to branch"));
+    if !branch_list.read_only {
+      commands.push(Span::raw("f: Fetch"));
+      commands.push(Span::raw("p: Prune remote"));
+    }
+    if can_delete && selected.is_some() && selected.unwrap().staged_for_deletion {
+      commands.push(Span::raw("d: Delete"));
+      commands.push(Span::raw("⇧ + d: Unstage for deletion"));
+    }
+
+    if can_delete
+      && is_local
+      && !selected.unwrap().branch.is_head
+      && !branch_list.is_protected(&selected.unwrap().branch.name)
+    {
+      commands.push(Span::raw("d: Stage for deletion"));
     }
 
     if selected.is_some() {
-      commands.push(Span::raw(" | c: Checkout"));
+      if can_checkout {
+        commands.push(Span::raw("c: Checkout"));
+      }
+      if can_checkout && !selected.unwrap().branch.is_head {
+        commands.push(Span::raw("⇧ + r: Rebase onto"));
+      }
+      if !branch_list.read_only {
+        commands.push(Span::raw(format!("n: New branch from {}", selected.unwrap().branch.name)));
+      }
+      if !branch_list.read_only && is_local {
+        commands.push(Span::raw("e: Edit description"));
+        commands.push(Span::raw("⇧ + e: Rename"));
+      }
+      if is_local {
+        commands.push(Span::raw("enter: Details"));
+      }
+      commands.push(Span::raw("m: Menu"));
+      commands.push(Span::raw("l: Log"));
+      commands.push(Span::raw("v: View in pager"));
+      if selected.unwrap().branch.upstream.is_some() {
+        commands.push(Span::raw("w: Open in browser"));
+        commands.push(Span::raw("⇧ + w: Open pull request"));
+      }
+      commands.push(Span::raw("y: Copy name"));
+      commands.push(Span::raw("⇧ + y: Copy sha"));
+      match &branch_list.compare_base {
+        Some(base) if *base != selected.unwrap().branch.name => {
+          commands.push(Span::raw(format!("b: Compare with {}", base)));
+        },
+        Some(_) => commands.push(Span::raw("b: Unmark compare base")),
+        None => commands.push(Span::raw("b: Mark compare base")),
+      }
+    }
+
+    if can_delete && branch_list.branches.iter().any(|b| b.branch.upstream_gone && !b.staged_for_deletion) {
+      commands.push(Span::raw("⇧ + g: Stage gone branches"));
+    }
+    if can_delete && branch_list.branches.iter().any(|b| b.staged_for_deletion) {
+      commands.push(Span::raw("^ + d: Delete all staged branches"));
     }
 
-    if branches.iter().any(|b| b.staged_for_deletion) {
-      commands.push(Span::raw(" | ^ + d: Delete all staged branches"));
+    match branch_list.hide_toggle() {
+      Some(true) => commands.push(Span::raw("a: Hide noise")),
+      Some(false) => commands.push(Span::raw("a: Show all")),
+      None => {},
     }
+    if branch_list.show_remotes {
+      commands.push(Span::raw("r: Hide remote branches"));
+    } else {
+      commands.push(Span::raw("r: Show remote branches"));
+    }
+    commands.push(Span::raw(format!("u: Upstreams ({})", branch_list.upstream_filter.label())));
+    match branch_list.sort.key {
+      BranchSort::Name => commands.push(Span::raw("s: Sort by age")),
+      BranchSort::Age => commands.push(Span::raw("s: Sort by name")),
+    }
+    commands.push(Span::raw("⇧ + s: Reverse order"));
+    commands.push(Span::raw("⇧ + l: Reflog"));
+    commands.push(Span::raw("⇧ + h: History"));
+    commands.push(Span::raw(",: Edit config"));
+    commands.push(Span::raw("?: Hide keys"));
+
+    commands
+  }
+}
 
-    let footer = Line::from(commands);
-    f.render_widget(footer, area);
+/// Lay commands out left to right, starting a new row whenever the next one doesn't fit.
+fn wrap_commands(commands: Vec<Span<'static>>, width: u16) -> Vec<Line<'static>> {
+  let width = usize::from(width);
+  let mut rows: Vec<Vec<Span>> = Vec::new();
+  let mut row_width = 0;
+  for command in commands {
+    let command_width = command.width();
+    match rows.last_mut() {
+      Some(row) if row_width + SEPARATOR.len() + command_width <= width => {
+        row.push(Span::raw(SEPARATOR));
+        row.push(command);
+        row_width += SEPARATOR.len() + command_width;
+      },
+      _ => {
+        rows.push(vec![command]);
+        row_width = command_width;
+      },
+    }
   }
+  rows.into_iter().map(Line::from).collect()
 }
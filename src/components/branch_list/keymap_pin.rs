@@ -0,0 +1,68 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+// Kept next to the log, its presence means the key legend was hidden.
+const HIDDEN_MARKER_FILE: &str = "keymap_hidden";
+
+/// Whether the key legend stays pinned under the list. It's there until hidden, for learning the keys, and the choice
+/// is remembered between sessions so those who know them keep the extra rows.
+#[derive(Debug)]
+pub struct KeymapPin {
+  pub pinned: bool,
+  // Nothing is remembered until the data directory is known.
+  marker: Option<PathBuf>,
+}
+
+impl Default for KeymapPin {
+  fn default() -> Self {
+    KeymapPin { pinned: true, marker: None }
+  }
+}
+
+impl KeymapPin {
+  /// The choice last made in `data_dir`.
+  pub fn load(data_dir: &Path) -> Self {
+    let marker = data_dir.join(HIDDEN_MARKER_FILE);
+    KeymapPin { pinned: !marker.exists(), marker: Some(marker) }
+  }
+
+  /// Flip the legend, it still flips for this session if it can't be remembered.
+  pub fn toggle(&mut self) -> std::io::Result<()> {
+    self.pinned = !self.pinned;
+    let Some(marker) = &self.marker else {
+      return Ok(());
+    };
+    if self.pinned {
+      match fs::remove_file(marker) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+      }
+    } else {
+      if let Some(parent) = marker.parent() {
+        fs::create_dir_all(parent)?;
+      }
+      fs::write(marker, "")
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn toggle_is_remembered_in_the_data_dir() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let mut pin = KeymapPin::load(data_dir.path());
+    assert!(pin.pinned);
+
+    pin.toggle().unwrap();
+    assert!(!pin.pinned);
+    assert!(!KeymapPin::load(data_dir.path()).pinned);
+
+    pin.toggle().unwrap();
+    assert!(KeymapPin::load(data_dir.path()).pinned);
+  }
+}
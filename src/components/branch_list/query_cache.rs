@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use crate::{
+  error::Error,
+  git::git_repo::{GitBranch, GitBranchDetails},
+};
+
+/// Results of slow per-branch git queries, kept until the branch's tip moves so checkouts and reloads don't work them
+/// out again. Ahead/behind also depends on the upstream, which only moves on a fetch, so the whole cache is cleared
+/// when the branches are explicitly reloaded after one.
+#[derive(Debug, Default)]
+pub struct QueryCache {
+  branches: HashMap<String, BranchQueries>,
+  // Commit counts by the range they were counted over. Ranges are between shas, so a count never goes stale.
+  commit_counts: HashMap<String, usize>,
+}
+
+#[derive(Debug, Default)]
+struct BranchQueries {
+  tip_sha: String,
+  ahead_behind: Option<(usize, usize)>,
+  details: Option<GitBranchDetails>,
+}
+
+impl QueryCache {
+  pub fn clear(&mut self) {
+    self.branches.clear();
+    self.commit_counts.clear();
+  }
+
+  /// Forget the branches in a fresh load of `branches` whose tip moved, and any that are gone.
+  pub fn retain_current<'a>(&mut self, branches: impl IntoIterator<Item = &'a GitBranch>) {
+    let tips: HashMap<&str, &str> =
+      branches.into_iter().filter_map(|branch| Some((branch.name.as_str(), branch.tip_sha.as_deref()?))).collect();
+    self.branches.retain(|name, queries| tips.get(name.as_str()) == Some(&queries.tip_sha.as_str()));
+  }
+
+  /// The cached queries for `branch` at its current tip, nothing is cached for a branch whose tip isn't known.
+  fn entry(&mut self, branch: &GitBranch) -> Option<&mut BranchQueries> {
+    let tip_sha = branch.tip_sha.as_deref()?;
+    let queries = self.branches.entry(branch.name.clone()).or_default();
+    if queries.tip_sha != tip_sha {
+      *queries = BranchQueries { tip_sha: String::from(tip_sha), ..BranchQueries::default() };
+    }
+    Some(queries)
+  }
+
+  /// `branch`'s commits ahead of and behind its upstream, if they were worked out at its current tip.
+  pub fn ahead_behind(&self, branch: &GitBranch) -> Option<(usize, usize)> {
+    let queries = self.branches.get(&branch.name)?;
+    if branch.tip_sha.as_deref() != Some(queries.tip_sha.as_str()) {
+      return None;
+    }
+    queries.ahead_behind
+  }
+
+  /// Keep `branch`'s commits ahead of and behind its upstream, they're counted off the UI thread.
+  pub fn insert_ahead_behind(&mut self, branch: &GitBranch, ahead_behind: (usize, usize)) {
+    if let Some(queries) = self.entry(branch) {
+      queries.ahead_behind = Some(ahead_behind);
+    }
+  }
+
+  /// `branch`'s details, from `load` only if they aren't cached.
+  pub fn details(
+    &mut self,
+    branch: &GitBranch,
+    load: impl FnOnce() -> Result<GitBranchDetails, Error>,
+  ) -> Result<GitBranchDetails, Error> {
+    let Some(queries) = self.entry(branch) else {
+      return load();
+    };
+    if let Some(details) = &queries.details {
+      return Ok(details.clone());
+    }
+    let details = load()?;
+    queries.details = Some(details.clone());
+    Ok(details)
+  }
+
+  pub fn commit_count(&self, range: &str) -> Option<usize> {
+    self.commit_counts.get(range).copied()
+  }
+
+  pub fn insert_commit_counts(&mut self, counts: Vec<(String, usize)>) {
+    self.commit_counts.extend(counts);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn branch(name: &str, tip_sha: &str) -> GitBranch {
+    GitBranch { tip_sha: Some(String::from(tip_sha)), ..GitBranch::new(String::from(name)) }
+  }
+
+  #[test]
+  fn ahead_behind_is_kept_until_the_tip_moves() {
+    let mut cache = QueryCache::default();
+    cache.insert_ahead_behind(&branch("main", "a1"), (1, 0));
+    cache.insert_ahead_behind(&GitBranch::new(String::from("new")), (0, 0));
+
+    assert_eq!(cache.ahead_behind(&branch("main", "a1")), Some((1, 0)));
+    assert_eq!(cache.ahead_behind(&branch("main", "b2")), None);
+    assert_eq!(cache.ahead_behind(&GitBranch::new(String::from("new"))), None);
+  }
+
+  #[test]
+  fn retain_current_forgets_moved_and_deleted_branches() {
+    let mut cache = QueryCache::default();
+    for (name, tip_sha) in [("main", "a1"), ("moved", "b1"), ("deleted", "c1")] {
+      cache.insert_ahead_behind(&branch(name, tip_sha), (0, 0));
+    }
+
+    cache.retain_current(&[branch("main", "a1"), branch("moved", "b2")]);
+
+    let mut names: Vec<&str> = cache.branches.keys().map(String::as_str).collect();
+    names.sort();
+    assert_eq!(names, vec!["main"]);
+  }
+}
@@ -0,0 +1,84 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  layout::Rect,
+  prelude::Color,
+  style::Style,
+  widgets::{Block, Borders},
+};
+use tui_textarea::{CursorMove, Input, TextArea};
+
+use crate::{action::Action, git::git_repo::resolve_branch_prefix, mode::Mode, tui::Frame};
+
+/// A prompt that checks out the branch uniquely matching the typed prefix.
+pub struct QuickCheckoutInput {
+  pub text_input: TextArea<'static>,
+}
+
+impl QuickCheckoutInput {
+  pub fn new() -> Self {
+    QuickCheckoutInput { text_input: TextArea::default() }
+  }
+
+  pub fn init_style(&mut self) {
+    self.text_input.set_style(Style::default().fg(Color::White));
+    self.text_input.set_block(Block::default().borders(Borders::ALL).title("Checkout branch by prefix"));
+  }
+
+  fn get_text(&self) -> Option<String> {
+    let input = String::from(self.text_input.lines().first()?.trim());
+    if input.is_empty() {
+      return None;
+    }
+    Some(input)
+  }
+
+  fn clear(&mut self) {
+    self.text_input.move_cursor(CursorMove::Head);
+    self.text_input.delete_line_by_end();
+  }
+
+  /// Show what the current text resolves to so the user knows what enter will do.
+  fn update_hint<'a>(&mut self, branch_names: impl IntoIterator<Item = &'a str>) {
+    let Some(partial) = self.get_text() else {
+      self.init_style();
+      return;
+    };
+    let (title, color) = match resolve_branch_prefix(branch_names, &partial) {
+      Ok(name) => (format!("Checkout {}", name), Color::LightGreen),
+      Err(err) => (err.to_string(), Color::LightRed),
+    };
+    self.text_input.set_style(Style::default().fg(color));
+    self.text_input.set_block(Block::default().borders(Borders::ALL).title(title));
+  }
+
+  pub fn handle_key_event<'a>(
+    &mut self,
+    key_event: KeyEvent,
+    branch_names: impl IntoIterator<Item = &'a str>,
+  ) -> Option<Action> {
+    match key_event {
+      KeyEvent { code: KeyCode::Esc, modifiers: KeyModifiers::NONE, kind: _, state: _ } => {
+        self.clear();
+        Some(Action::SetMode(Mode::Default))
+      },
+      KeyEvent { code: KeyCode::Enter, modifiers: _, kind: _, state: _ } => {
+        let partial = self.get_text();
+        self.clear();
+        match partial {
+          Some(partial) => Some(Action::QuickCheckout(partial)),
+          None => Some(Action::SetMode(Mode::Default)),
+        }
+      },
+      _ => {
+        if self.text_input.input(Input::from(key_event)) {
+          self.update_hint(branch_names);
+        }
+        None
+      },
+    }
+  }
+
+  pub fn render(&mut self, f: &mut Frame<'_>, area: Rect) {
+    f.render_widget(&self.text_input, area);
+  }
+}
@@ -0,0 +1,66 @@
+use ratatui::{
+  layout::Rect,
+  style::{Color, Modifier, Style},
+  text::{Line, Span},
+  widgets::{Block, Clear, List, ListItem, ListState},
+};
+
+use crate::{components::popup::centered_area, git::git_repo::GitReflogEntry, tui::Frame};
+
+/// Where HEAD has been recently, for finding work lost to a reset, rebase or deleted branch.
+#[derive(Debug, Default, Clone)]
+pub struct Reflog {
+  pub entries: Vec<GitReflogEntry>,
+  list_state: ListState,
+}
+
+impl Reflog {
+  pub fn new(entries: Vec<GitReflogEntry>) -> Self {
+    let selected = if entries.is_empty() { None } else { Some(0) };
+    Reflog { entries, list_state: ListState::default().with_selected(selected) }
+  }
+
+  pub fn selected(&self) -> Option<&GitReflogEntry> {
+    self.entries.get(self.list_state.selected()?)
+  }
+
+  pub fn select_previous(&mut self) {
+    if let Some(selected) = self.list_state.selected() {
+      self.list_state.select(Some(selected.saturating_sub(1)));
+    }
+  }
+
+  pub fn select_next(&mut self) {
+    if let Some(selected) = self.list_state.selected() {
+      self.list_state.select(Some((selected + 1).min(self.entries.len().saturating_sub(1))));
+    }
+  }
+
+  pub fn render(&mut self, f: &mut Frame<'_>, area: Rect, can_create: bool) {
+    let items: Vec<ListItem> = self
+      .entries
+      .iter()
+      .map(|entry| {
+        ListItem::from(Line::from(vec![
+          Span::styled(format!("{} ", entry.short_sha()), Style::default().fg(Color::Yellow)),
+          Span::styled(format!("{}: ", entry.action), Style::default().add_modifier(Modifier::DIM)),
+          Span::raw(entry.message.clone()),
+        ]))
+      })
+      .collect();
+    let instructions = if can_create {
+      "↑/↓: Select | n: New branch here | esc/enter: Close"
+    } else {
+      "↑/↓: Select | esc/enter: Close"
+    };
+    let title = if self.entries.is_empty() { "Reflog (empty)" } else { "Reflog" };
+    let list = List::new(items)
+      .block(Block::bordered().title(title).title_bottom(instructions))
+      .style(Style::default().fg(Color::White))
+      .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+      .highlight_symbol("→");
+    let popup_area = centered_area(area, 80, 80);
+    f.render_widget(Clear, popup_area);
+    f.render_stateful_widget(list, popup_area, &mut self.list_state);
+  }
+}
@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  layout::Rect,
+  style::{Color, Modifier, Style},
+  text::{Line, Span},
+  widgets::{Block, Clear, List, ListItem, ListState},
+};
+
+use crate::{
+  action::{Action, HistoryEntry},
+  components::{popup::centered_area, Component},
+  tui::Frame,
+  utils::{format_relative_time, now_timestamp},
+};
+
+// Oldest entries are dropped past this, it's an audit trail for the session rather than a permanent record.
+const HISTORY_LIMIT: usize = 200;
+
+/// The operations run this session, newest first, shown over everything else.
+#[derive(Debug, Default)]
+pub struct History {
+  entries: VecDeque<HistoryEntry>,
+  list_state: ListState,
+}
+
+impl History {
+  pub fn push(&mut self, entry: HistoryEntry) {
+    if self.entries.len() == HISTORY_LIMIT {
+      self.entries.pop_front();
+    }
+    self.entries.push_back(entry);
+  }
+}
+
+impl Component for History {
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    match key {
+      KeyEvent { code: KeyCode::Up, modifiers: _, kind: _, state: _ } => Ok(Some(Action::ScrollHistoryUp)),
+      KeyEvent { code: KeyCode::Down, modifiers: _, kind: _, state: _ } => Ok(Some(Action::ScrollHistoryDown)),
+      KeyEvent { code: KeyCode::Esc | KeyCode::Enter, modifiers: _, kind: _, state: _ }
+      | KeyEvent { code: KeyCode::Char('h' | 'H'), modifiers: KeyModifiers::SHIFT, kind: _, state: _ } => {
+        Ok(Some(Action::CloseHistory))
+      },
+      _ => Ok(None),
+    }
+  }
+
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    match action {
+      Action::ShowHistory => self.list_state.select(if self.entries.is_empty() { None } else { Some(0) }),
+      Action::ScrollHistoryUp => {
+        self.list_state.select(self.list_state.selected().map(|selected| selected.saturating_sub(1)));
+      },
+      Action::ScrollHistoryDown => {
+        let last = self.entries.len().saturating_sub(1);
+        self.list_state.select(self.list_state.selected().map(|selected| (selected + 1).min(last)));
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    let now = now_timestamp();
+    let items: Vec<ListItem> = self
+      .entries
+      .iter()
+      .rev()
+      .map(|entry| {
+        let mut spans = vec![
+          Span::styled(
+            format!("{:>16} ", format_relative_time(entry.time, now)),
+            Style::default().add_modifier(Modifier::DIM),
+          ),
+          match &entry.error {
+            None => Span::styled("✓ ", Style::default().fg(Color::Green)),
+            Some(_) => Span::styled("✗ ", Style::default().fg(Color::Red)),
+          },
+          Span::raw(entry.description.clone()),
+        ];
+        if let Some(error) = &entry.error {
+          spans.push(Span::styled(format!(": {}", error), Style::default().fg(Color::Red)));
+        }
+        ListItem::from(Line::from(spans))
+      })
+      .collect();
+    let title = if items.is_empty() { "History (nothing run yet)" } else { "History" };
+    let list = List::new(items)
+      .block(Block::bordered().title(title).title_bottom("↑/↓: Scroll | esc/enter: Close"))
+      .style(Style::default().fg(Color::White))
+      .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+      .highlight_symbol("→");
+    let popup_area = centered_area(area, 80, 80);
+    f.render_widget(Clear, popup_area);
+    f.render_stateful_widget(list, popup_area, &mut self.list_state);
+    Ok(())
+  }
+}
@@ -0,0 +1,22 @@
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+
+/// A fixed size area centered in `area`, shrunk to fit if `area` is smaller.
+pub fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+  let [vertical] = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center).areas(area);
+  let [centered] = Layout::horizontal([Constraint::Length(width)]).flex(Flex::Center).areas(vertical);
+  centered
+}
+
+// Narrower than this a centered popup would be too cramped to read, so popups take over the whole area instead.
+const NARROW_WIDTH: u16 = 80;
+
+/// The area for a popup centered in `area`, taking up the given percentage of its width and height. In narrow
+/// terminals popups fill `area` entirely.
+pub fn centered_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+  if area.width < NARROW_WIDTH {
+    return area;
+  }
+  let [vertical] = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center).areas(area);
+  let [centered] = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center).areas(vertical);
+  centered
+}
@@ -0,0 +1,52 @@
+use color_eyre::eyre::Result;
+use ratatui::{
+  layout::Rect,
+  style::{Color, Style},
+  widgets::{Block, Clear, Paragraph},
+};
+
+use crate::{
+  action::Action,
+  components::{popup::centered_rect, Component},
+  tui::Frame,
+};
+
+const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// A labelled spinner shown over everything while a long running operation is in flight, advanced by ticks.
+#[derive(Debug, Default, Clone)]
+pub struct Spinner {
+  label: String,
+  frame: usize,
+}
+
+impl Spinner {
+  pub fn new(label: String) -> Self {
+    Spinner { label, frame: 0 }
+  }
+
+  /// Say how far the operation has got, for operations that can tell.
+  pub fn set_label(&mut self, label: String) {
+    self.label = label;
+  }
+}
+
+impl Component for Spinner {
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    if action == Action::Tick {
+      self.frame = (self.frame + 1) % FRAMES.len();
+    }
+    Ok(None)
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+    let text = format!("{} {}", FRAMES[self.frame], self.label);
+    // Room for the borders and a space either side.
+    let width = u16::try_from(text.chars().count() + 4).unwrap_or(u16::MAX);
+    let popup_area = centered_rect(area, width, 3);
+    let popup = Paragraph::new(format!(" {}", text)).block(Block::bordered()).style(Style::default().fg(Color::Yellow));
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup, popup_area);
+    Ok(())
+  }
+}
@@ -8,7 +8,9 @@ use ratatui::{
 
 use crate::{
   components::Component,
+  config::Config,
   git::git_repo::{GitRepo, GitStash},
+  theme::Highlight,
 };
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -44,24 +46,30 @@ impl StashItem {
 pub struct StashList {
   stashes: Vec<StashItem>,
   list_state: ListState,
+  highlight: Highlight,
 }
 
 impl StashList {
   pub fn new(mut repo: Box<dyn GitRepo>) -> Self {
     let stashes: Vec<StashItem> =
       repo.stashes().unwrap().iter().map(|git_stash| StashItem::new(git_stash.clone())).collect();
-    StashList { stashes, list_state: ListState::default() }
+    StashList { stashes, list_state: ListState::default(), highlight: Highlight::default() }
   }
 }
 
 impl Component for StashList {
+  fn register_config_handler(&mut self, config: Config) -> color_eyre::Result<()> {
+    self.highlight = Highlight::new(config.config.highlight_symbol.clone(), config.config.selection_style);
+    Ok(())
+  }
+
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> color_eyre::Result<()> {
     let render_items: Vec<ListItem> = self.stashes.iter().map(|stash| stash.render()).collect();
     let list = List::new(render_items)
       .block(Block::default().title("Stashes").borders(Borders::ALL))
       .style(Style::default().fg(Color::White))
-      .highlight_style(Style::default().add_modifier(Modifier::BOLD))
-      .highlight_symbol("→")
+      .highlight_style(self.highlight.style)
+      .highlight_symbol(&self.highlight.symbol)
       .repeat_highlight_symbol(true);
     f.render_stateful_widget(list, area, &mut self.list_state);
     Ok(())
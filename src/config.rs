@@ -1,14 +1,150 @@
-use std::path::PathBuf;
+use std::{
+  cmp::Ordering,
+  collections::HashMap,
+  env::current_dir,
+  path::{Path, PathBuf},
+};
 
 use color_eyre::eyre::Result;
+use ratatui::style::Color;
 use serde::Deserialize;
 
+use crate::{theme::SelectionStyle, utils::LogFormat};
+
 #[derive(Clone, Debug, Deserialize, Default)]
 pub struct AppConfig {
   #[serde(default)]
   pub _data_dir: PathBuf,
   #[serde(default)]
   pub _config_dir: PathBuf,
+  /// Regexes for branches that are noise in the list, e.g. `^release/` or `^ci-`.
+  #[serde(default)]
+  pub hidden_branch_patterns: Vec<String>,
+  /// Globs for branches that can't be staged or deleted, e.g. `main` or `release/*`.
+  #[serde(default)]
+  pub protected_branch_patterns: Vec<String>,
+  /// Hide branches that are already merged into the default branch.
+  #[serde(default)]
+  pub hide_merged_branches: bool,
+  /// The branch cleanup and comparisons are based on, autodetected from origin/HEAD, init.defaultBranch or main/master
+  /// when unset.
+  #[serde(default)]
+  pub default_base: Option<String>,
+  /// The only remote whose branches are shown when remote branches are, every remote's when unset.
+  #[serde(default)]
+  pub shown_remote: Option<String>,
+  /// The git executable to run, defaults to `git` on the PATH.
+  #[serde(default)]
+  pub git_path: Option<PathBuf>,
+  /// How the log file is written, overridden by the `GIT_BRANCH_MANAGER_LOG_FORMAT` environment variable.
+  #[serde(default)]
+  pub log_format: LogFormat,
+  /// Longest a branch name is shown before being cut off with an ellipsis, names also shrink to fit the terminal.
+  #[serde(default)]
+  pub branch_name_max_width: Option<usize>,
+  /// Lay the list out in as many columns at least this wide as fit, once there's room for two. One column when unset.
+  #[serde(default)]
+  pub column_width: Option<u16>,
+  /// Enables creating a branch with an empty first commit using this message, `{branch}` is replaced with its name.
+  #[serde(default)]
+  pub empty_commit_message: Option<String>,
+  /// Branches without a commit in this many days are highlighted as stale, 90 when unset.
+  #[serde(default)]
+  pub stale_after_days: Option<u64>,
+  /// The color stale branch names are drawn in, dark gray when unset.
+  #[serde(default)]
+  pub stale_branch_color: Option<Color>,
+  /// Drawn before the selected branch or stash, `→` when unset. `>` suits terminals that don't render the arrow.
+  #[serde(default)]
+  pub highlight_symbol: Option<String>,
+  /// How the selected branch or stash stands out, bold when unset.
+  #[serde(default)]
+  pub selection_style: SelectionStyle,
+  /// What the list is ordered by on start, `s` switches it and `⇧ + s` reverses it.
+  #[serde(default)]
+  pub sort_by: BranchSort,
+  #[serde(default)]
+  pub sort_direction: SortDirection,
+  /// Orders branches that tie on `sort_by`, e.g. ones with the same last commit time.
+  #[serde(default)]
+  pub then_sort_by: Option<BranchSort>,
+  #[serde(default)]
+  pub then_sort_direction: SortDirection,
+  /// Show `git notes` under each commit in the log, off by default as they can be long.
+  #[serde(default)]
+  pub show_commit_notes: bool,
+  /// Copy the full 40 character sha instead of the abbreviated one.
+  #[serde(default)]
+  pub copy_full_sha: bool,
+  /// Show how many commits each branch has that the default branch doesn't, or in total without a default branch.
+  #[serde(default)]
+  pub show_commit_counts: bool,
+  /// Rebase with `git rebase -i`, editing the todo list in the editor git is configured with, rather than straight
+  /// through.
+  #[serde(default)]
+  pub interactive_rebase: bool,
+  /// Run `git submodule update --init --recursive` after a checkout that changes `.gitmodules`, rather than only
+  /// warning that submodules may be out of sync.
+  #[serde(default)]
+  pub update_submodules_on_checkout: bool,
+  /// Namespaces shown as `…` at the start of branch names, e.g. `john` shows `john/feature/x` as `…/feature/x`.
+  #[serde(default)]
+  pub abbreviated_branch_prefixes: Vec<String>,
+  /// Self hosted code review sites by hostname, mapped to `github` or `gitlab` so branches can be opened in the browser.
+  #[serde(default)]
+  pub web_hosts: HashMap<String, String>,
+  /// A GitHub token for noting a branch's open pull request in its details, nothing is looked up without one.
+  #[serde(default)]
+  pub github_token: Option<String>,
+  /// Draw just the branch list, without borders or the footer, for small panes.
+  #[serde(default)]
+  pub compact: bool,
+  /// Disable everything that changes the repository, for safely browsing.
+  #[serde(default)]
+  pub read_only: bool,
+  /// Draw in the normal screen rather than the alternate one, leaving the last view in the scrollback on exit.
+  #[serde(default)]
+  pub no_alternate_screen: bool,
+  /// Ticks a second, 10 when unset and kept between 1 and 30. Lower saves CPU, higher animates the spinner smoother.
+  #[serde(default)]
+  pub tick_rate: Option<f64>,
+  /// Redraws a second, 30 when unset and kept between 1 and 60. Lower saves bandwidth over SSH.
+  #[serde(default)]
+  pub frame_rate: Option<f64>,
+}
+
+/// What the branch list is ordered by.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BranchSort {
+  #[default]
+  Name,
+  /// By last commit time, ascending puts the oldest and likeliest cleanup candidates first.
+  Age,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+  #[default]
+  Ascending,
+  Descending,
+}
+
+impl SortDirection {
+  pub fn apply(self, ordering: Ordering) -> Ordering {
+    match self {
+      SortDirection::Ascending => ordering,
+      SortDirection::Descending => ordering.reverse(),
+    }
+  }
+
+  pub fn reversed(self) -> Self {
+    match self {
+      SortDirection::Ascending => SortDirection::Descending,
+      SortDirection::Descending => SortDirection::Ascending,
+    }
+  }
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -25,26 +161,161 @@ impl Config {
       .set_default("_data_dir", data_dir.to_str().unwrap())?
       .set_default("_config_dir", config_dir.to_str().unwrap())?;
 
-    let config_files = [
-      ("config.json5", config::FileFormat::Json5),
-      ("config.json", config::FileFormat::Json),
-      ("config.yaml", config::FileFormat::Yaml),
-      ("config.toml", config::FileFormat::Toml),
-      ("config.ini", config::FileFormat::Ini),
-    ];
     let mut found_config = false;
-    for (file, format) in &config_files {
-      builder = builder.add_source(config::File::from(config_dir.join(file)).format(*format).required(false));
-      if config_dir.join(file).exists() {
-        found_config = true
+    for (file, format) in &CONFIG_FILES {
+      let path = config_dir.join(file);
+      if path.exists() {
+        found_config = true;
+        validate_config_file(&path, *format)?;
       }
+      builder = builder.add_source(config::File::from(path).format(*format).required(false));
+    }
+    // Added last so settings that travel with the repo override the global ones.
+    if let Some(path) = repo_config_path().filter(|path| path.exists()) {
+      found_config = true;
+      validate_config_file(&path, config::FileFormat::Toml)?;
+      builder = builder.add_source(config::File::from(path).format(config::FileFormat::Toml));
     }
     if !found_config {
       log::error!("No configuration file found. Application may not behave as expected");
     }
 
-    let cfg: Self = builder.build()?.try_deserialize()?;
+    let cfg: Self = builder.build()?.try_deserialize().map_err(|err| {
+      config::ConfigError::Message(format!("Invalid configuration in {}: {}", config_dir.display(), err))
+    })?;
 
     Ok(cfg)
   }
 }
+
+const CONFIG_FILES: [(&str, config::FileFormat); 5] = [
+  ("config.json5", config::FileFormat::Json5),
+  ("config.json", config::FileFormat::Json),
+  ("config.yaml", config::FileFormat::Yaml),
+  ("config.toml", config::FileFormat::Toml),
+  ("config.ini", config::FileFormat::Ini),
+];
+
+// Written when editing the config before one exists, so the available settings are discoverable.
+const DEFAULT_CONFIG: &str = r#"# git-branch-manager configuration, uncomment a setting to change it.
+
+# Regexes for branches that are noise in the list.
+# hidden_branch_patterns = ["^release/", "^ci-"]
+
+# Globs for branches that can't be staged or deleted.
+# protected_branch_patterns = ["main", "master", "release/*"]
+
+# Hide branches that are already merged into the default branch.
+# hide_merged_branches = false
+
+# The branch cleanup and comparisons are based on, autodetected from origin/HEAD, init.defaultBranch or main/master
+# when unset.
+# default_base = "main"
+
+# Only show the branches of this remote when remote branches are shown (r), rather than those of every remote.
+# shown_remote = "upstream"
+
+# The git executable to run, only picked up on restart.
+# git_path = "git"
+
+# Write the log as "text" or as "json" lines, only picked up on restart. GIT_BRANCH_MANAGER_LOG_FORMAT overrides it.
+# log_format = "text"
+
+# Longest a branch name is shown before being cut off with an ellipsis.
+# branch_name_max_width = 40
+
+# In terminals wide enough for two, lay the list out in as many columns this wide as fit. Left and right move between
+# them.
+# column_width = 60
+
+# Allow creating a branch with an empty first commit (ctrl + e), `{branch}` is replaced with the branch name.
+# empty_commit_message = "Start {branch}"
+
+# Branches without a commit in this many days are highlighted as stale, and the color to highlight them in.
+# stale_after_days = 90
+# stale_branch_color = "darkgray"
+
+# What marks the selected row, "bold", "reversed", "underlined" or "plain", and the symbol drawn before it. Use ">" if
+# the arrow doesn't render.
+# selection_style = "bold"
+# highlight_symbol = "→"
+
+# How the list is ordered on start, by "name" or "age", "ascending" or "descending", with an optional tiebreaker.
+# sort_by = "name"
+# sort_direction = "ascending"
+# then_sort_by = "name"
+# then_sort_direction = "ascending"
+
+# Namespaces shown as an ellipsis at the start of branch names, the full name is still used for everything else.
+# abbreviated_branch_prefixes = ["john"]
+
+# Self hosted code review sites, by hostname, so branches on them can be opened in the browser.
+# web_hosts = { "git.example.com" = "gitlab" }
+
+# A GitHub token with read access to pull requests, to show a branch's open pull request in its details.
+# github_token = "ghp_..."
+
+# Show git notes under each commit in the log.
+# show_commit_notes = false
+
+# Copy the full sha instead of the abbreviated one.
+# copy_full_sha = false
+
+# Show how many commits each branch has beyond the default branch, counted in the background.
+# show_commit_counts = false
+
+# Rebase interactively, picking what happens to each commit in git's editor.
+# interactive_rebase = false
+
+# Update submodules after a checkout that adds or removes them, otherwise there's only a warning.
+# update_submodules_on_checkout = false
+
+# Draw just the branch list, without borders or the footer.
+# compact = false
+
+# Disable everything that changes the repository.
+# read_only = false
+
+# Leave the last view in the terminal's scrollback on exit, like `git branch` output. Only picked up on restart.
+# no_alternate_screen = false
+
+# Ticks and redraws a second, lower them over SSH or on slow terminals to save CPU and bandwidth. Ticks are kept
+# between 1 and 30 and redraws between 1 and 60. Only picked up on restart.
+# tick_rate = 10
+# frame_rate = 30
+"#;
+
+/// The global config file to edit, the first one that exists or a new `config.toml`.
+pub fn editable_config_path() -> PathBuf {
+  let config_dir = crate::utils::get_config_dir();
+  CONFIG_FILES
+    .iter()
+    .map(|(file, _)| config_dir.join(file))
+    .find(|path| path.exists())
+    .unwrap_or_else(|| config_dir.join("config.toml"))
+}
+
+/// Create a config at `path` with every setting commented out.
+pub fn write_default_config(path: &Path) -> std::io::Result<()> {
+  if let Some(parent) = path.parent() {
+    std::fs::create_dir_all(parent)?;
+  }
+  std::fs::write(path, DEFAULT_CONFIG)
+}
+
+// Per repo config, looked for in the root of the repository being managed.
+const REPO_CONFIG_FILE: &str = ".git-branch-manager.toml";
+
+fn repo_config_path() -> Option<PathBuf> {
+  let repo = git2::Repository::discover(current_dir().ok()?).ok()?;
+  Some(repo.workdir()?.join(REPO_CONFIG_FILE))
+}
+
+/// Parse a file on its own so a broken one is reported by name rather than as an opaque merge error.
+fn validate_config_file(path: &Path, format: config::FileFormat) -> Result<(), config::ConfigError> {
+  let parsed = config::Config::builder().add_source(config::File::from(path.to_path_buf()).format(format)).build();
+  if let Err(err) = parsed {
+    return Err(config::ConfigError::Message(format!("Invalid config file {}: {}", path.display(), err)));
+  }
+  Ok(())
+}
@@ -5,9 +5,69 @@ pub enum Error {
   #[error(transparent)]
   Git2(#[from] git2::Error),
 
+  #[error("git executable `{0}` not found, install git or point git_path / --git-path at it")]
+  GitNotFound(String),
+
+  #[error("`{0}` is not a working git executable, check git_path / --git-path")]
+  GitNotExecutable(String),
+
+  #[error("{0} is too old, git 2.24 or later is needed")]
+  GitTooOld(String),
+
+  /// Failures that aren't from a git command or that don't fit a more specific variant.
   #[error("Git operation failed: {0}")]
   Git(String),
 
+  #[error("`git {args}` failed: {stderr}")]
+  CommandFailed { args: String, stderr: String },
+
+  #[error("Not inside a git repository, run this from within one")]
+  NotARepository,
+
+  /// Shown as is when a rebase or cherry-pick is refused before it starts, checkouts turn it into
+  /// [`Error::CheckoutConflict`] so they can offer to discard the changes.
+  #[error("Local changes are in the way of `git {0}`, commit or stash them first")]
+  DirtyWorkingTree(String),
+
+  #[error("Authentication required for `git {0}`, configure a credential helper or SSH key as prompts are disabled")]
+  AuthenticationRequired(String),
+
+  #[error(
+    "Cherry-picking {0} hit conflicts, resolve them and run `git cherry-pick --continue`, or `git cherry-pick --abort` \
+     to undo it"
+  )]
+  CherryPickConflict(String),
+
+  #[error(
+    "Rebasing onto {0} stopped part way, resolve any conflicts and run `git rebase --continue`, or `git rebase --abort` \
+     to undo it"
+  )]
+  RebaseConflict(String),
+
+  #[error("Checking out {0} would overwrite local changes, commit or stash them first")]
+  CheckoutConflict(String),
+
+  #[error("{0} is checked out, switch to another branch before deleting it")]
+  DeleteCheckedOutBranch(String),
+
+  #[error("Pull request lookup failed: {0}")]
+  PullRequestLookup(String),
+
+  #[error("Operation cancelled")]
+  Cancelled,
+
+  #[error("Repository has no commits yet; make an initial commit first")]
+  UnbornHead,
+
+  #[error("{0} has commits that haven't been fetched, fetch and look at them before deleting it")]
+  RemoteBranchMoved(String),
+
+  #[error("No branch matches {0}")]
+  BranchNotFound(String),
+
+  #[error("{0} is ambiguous, it matches {}", .1.join(", "))]
+  AmbiguousBranch(String, Vec<String>),
+
   #[error(transparent)]
   ParsingError(#[from] std::string::FromUtf8Error),
 }
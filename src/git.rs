@@ -1,3 +1,7 @@
 pub mod git2_repo;
 pub mod git_cli_repo;
 pub mod git_repo;
+pub mod pull_request;
+#[cfg(test)]
+pub mod test_repo;
+pub mod web_url;
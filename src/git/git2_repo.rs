@@ -1,12 +1,19 @@
-use std::env::current_dir;
+use std::{env::current_dir, path::Path};
 
-use git2::{Branch, BranchType, Repository};
+use git2::{
+  build::CheckoutBuilder, Branch, BranchType, Commit, DiffStatsFormat, Direction, ErrorCode, FetchOptions, Oid,
+  PushOptions, RemoteCallbacks, Repository, RepositoryState, SubmoduleUpdateOptions,
+};
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
 
 use super::git_repo::GitStash;
 use crate::{
   error::Error,
-  git::git_repo::{GitBranch, GitRemoteBranch, GitRepo},
+  git::git_repo::{
+    default_remote, BranchNameValidity, FetchProgress, GitBranch, GitBranchDetails, GitCommit, GitReflogEntry,
+    GitRemoteBranch, GitRepo,
+  },
 };
 
 pub struct Git2Repo {
@@ -16,26 +23,122 @@ pub struct Git2Repo {
 impl Git2Repo {
   pub fn from_cwd() -> Result<Git2Repo, Error> {
     let path_buf = current_dir().expect("Unable to get current working directory");
-    let repo = Repository::discover(path_buf.as_path())?;
+    Git2Repo::from_path(path_buf.as_path())
+  }
+
+  pub fn from_path(path: &Path) -> Result<Git2Repo, Error> {
+    let repo = Repository::discover(path).map_err(|err| {
+      match err.code() {
+        ErrorCode::NotFound => Error::NotARepository,
+        _ => Error::from(err),
+      }
+    })?;
     Ok(Git2Repo { repo })
   }
 
-  fn create_git_branch(&self, result: Result<(Branch, BranchType), git2::Error>) -> Option<GitBranch> {
+  fn create_git_branch(
+    &self,
+    result: Result<(Branch, BranchType), git2::Error>,
+    remotes: &[String],
+  ) -> Option<GitBranch> {
     let (branch, _branch_type) = result.ok()?;
     let name = branch.name().ok()??;
-    let upstream = extract_upstream_branch(&branch);
-    Some(GitBranch { name: String::from(name), is_head: branch.is_head(), upstream })
+    let mut upstream = extract_upstream_branch(&branch, remotes);
+    // A configured upstream that no longer resolves was deleted on the remote.
+    let mut upstream_gone = false;
+    if upstream.is_none() {
+      if let Some(configured) = branch.get().name().and_then(|refname| self.repo.branch_upstream_name(refname).ok()) {
+        let configured = configured.as_str().unwrap_or_default();
+        let short = configured.strip_prefix("refs/remotes/").or(configured.strip_prefix("refs/heads/"));
+        upstream = Some(GitRemoteBranch::with_remotes(String::from(short.unwrap_or(configured)), remotes));
+        upstream_gone = true;
+      }
+    }
+    let last_commit_time = branch.get().peel_to_commit().ok().map(|commit| commit.time().seconds());
+    let tip_sha = branch.get().target().map(|oid| oid.to_string());
+    let description = self.git_config_get(&format!("branch.{}.description", name)).ok().flatten();
+    Some(GitBranch {
+      name: String::from(name),
+      is_head: branch.is_head(),
+      tip_sha,
+      upstream,
+      upstream_gone,
+      last_commit_time,
+      description: description
+        .and_then(|text| text.lines().map(str::trim).find(|line| !line.is_empty()).map(String::from)),
+      parse_failed: false,
+    })
+  }
+
+  /// Checkout `branch_name`, refusing to overwrite local changes unless `force` is set.
+  fn checkout_from_name(&self, branch_name: &str, force: bool) -> Result<(), Error> {
+    info!("Checking out branch {}", branch_name);
+    let branch = match self.repo.find_branch(branch_name, BranchType::Local) {
+      Ok(branch) => branch,
+      Err(err) if err.code() == ErrorCode::NotFound => return Err(Error::BranchNotFound(String::from(branch_name))),
+      Err(err) => return Err(Error::Git2(err)),
+    };
+    let branch_ref = branch.get();
+    let Some(ref_name) = branch_ref.name() else {
+      return Err(Error::Git(format!("The ref for {} isn't valid utf-8", branch_name)));
+    };
+    info!("Found branch with ref {}", ref_name);
+
+    let tree = branch_ref.peel_to_tree()?;
+    // Safe checkout refuses before writing anything if a local change would be overwritten, and HEAD only moves once
+    // the worktree matches, so a refused checkout leaves the index and worktree as they were.
+    let mut checkout = CheckoutBuilder::new();
+    if force {
+      checkout.force();
+    } else {
+      checkout.safe();
+    }
+    match self.repo.checkout_tree(tree.as_object(), Some(&mut checkout)) {
+      Err(err) if err.code() == ErrorCode::Conflict => return Err(Error::CheckoutConflict(String::from(branch_name))),
+      Err(err) => {
+        error!("Failed to checkout tree: {}", err);
+        return Err(Error::Git2(err));
+      },
+      Ok(()) => {},
+    }
+
+    self.repo.set_head(ref_name)?;
+    Ok(())
+  }
+
+  /// The remote tracking branches of `remote`, like `origin/feature`.
+  fn remote_tracking_branches(&self, remote: &str) -> Result<Vec<String>, Error> {
+    let mut branches = Vec::new();
+    for reference in self.repo.references_glob(&format!("refs/remotes/{}/*", remote))? {
+      if let Some(name) = reference?.name().and_then(|name| name.strip_prefix("refs/remotes/")) {
+        branches.push(String::from(name));
+      }
+    }
+    Ok(branches)
   }
 }
 
 impl GitRepo for Git2Repo {
+  fn try_clone(&self) -> Result<Box<dyn GitRepo>, Error> {
+    Ok(Box::new(Git2Repo { repo: Repository::open(self.repo.path())? }))
+  }
+
+  fn is_bare(&self) -> Result<bool, Error> {
+    Ok(self.repo.is_bare())
+  }
+
+  fn is_shallow(&self) -> Result<bool, Error> {
+    Ok(self.repo.is_shallow())
+  }
+
   fn local_branches(&self) -> Result<Vec<GitBranch>, Error> {
     let branches = self.repo.branches(Some(BranchType::Local));
     if branches.is_err() {
       return Err(Error::Git2(branches.err().unwrap()));
     }
+    let remotes = self.remotes()?;
     let loaded_branches: Vec<GitBranch> =
-      branches.ok().unwrap().filter_map(|branch| self.create_git_branch(branch)).collect();
+      branches.ok().unwrap().filter_map(|branch| self.create_git_branch(branch, &remotes)).collect();
     Ok(loaded_branches)
   }
 
@@ -50,25 +153,35 @@ impl GitRepo for Git2Repo {
   }
 
   fn checkout_branch_from_name(&self, branch_name: &str) -> Result<(), Error> {
-    info!("Checking out branch {}", branch_name);
-    let branch = self.repo.find_branch(branch_name, BranchType::Local)?;
-    let branch_ref = branch.get();
-    info!("Found branch with ref {}", branch_ref.name().unwrap());
+    self.checkout_from_name(branch_name, false)
+  }
 
-    let tree = branch_ref.peel_to_tree()?;
-    let checkout_result = self.repo.checkout_tree(tree.as_object(), None);
+  fn force_checkout_branch_from_name(&self, branch_name: &str) -> Result<(), Error> {
+    self.checkout_from_name(branch_name, true)
+  }
 
-    if checkout_result.is_err() {
-      error!("Failed to checkout tree: {}", checkout_result.unwrap_err());
-      return Err(Error::Git("Failed to checkout tree".to_string()));
+  fn checkout_detached(&self, revision: &str) -> Result<(), Error> {
+    info!("Checking out {} detached", revision);
+    let commit = self.repo.revparse_single(revision)?.peel_to_commit()?;
+    match self.repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe())) {
+      Err(err) if err.code() == ErrorCode::Conflict => return Err(Error::CheckoutConflict(String::from(revision))),
+      res => res?,
     }
+    self.repo.set_head_detached(commit.id())?;
+    Ok(())
+  }
 
-    let set_head_result = self.repo.set_head(branch_ref.name().unwrap());
-    if set_head_result.is_err() {
-      error!("Failed to set head to: {}", branch_ref.name().unwrap());
-      return Err(Error::Git("Failed to set HEAD".to_string()));
+  fn checkout_remote_branch(&self, remote_branch: &GitRemoteBranch) -> Result<(), Error> {
+    let local_name = remote_branch.branch_name();
+    info!("Creating branch {} tracking {}", local_name, remote_branch.name);
+    let commit = self.repo.find_branch(&remote_branch.name, BranchType::Remote)?.get().peel_to_commit()?;
+    let mut branch = self.repo.branch(local_name, &commit, false)?;
+    branch.set_upstream(Some(&remote_branch.name))?;
+    // Like `git checkout -b`, a checkout that's refused doesn't leave the new branch behind.
+    if let Err(err) = self.checkout_from_name(local_name, false) {
+      branch.delete()?;
+      return Err(err);
     }
-
     Ok(())
   }
 
@@ -76,17 +189,40 @@ impl GitRepo for Git2Repo {
     self.checkout_branch_from_name(&branch.name)
   }
 
-  fn validate_branch_name(&self, name: &str) -> Result<bool, Error> {
-    Ok(Branch::name_is_valid(name)?)
+  fn gitmodules_changed(&self, revision: &str) -> Result<bool, Error> {
+    let gitmodules_id = |revision: &str| -> Result<Option<Oid>, Error> {
+      let tree = self.repo.revparse_single(revision)?.peel_to_tree()?;
+      let id = tree.get_name(".gitmodules").map(|entry| entry.id());
+      Ok(id)
+    };
+    Ok(gitmodules_id(revision)? != gitmodules_id("HEAD")?)
+  }
+
+  fn update_submodules(&self, cancel: &CancellationToken) -> Result<(), Error> {
+    update_submodules(&self.repo, cancel)
+  }
+
+  fn validate_branch_name(&self, name: &str) -> Result<BranchNameValidity, Error> {
+    if !Branch::name_is_valid(name)? {
+      return Ok(BranchNameValidity::InvalidFormat);
+    }
+    if self.repo.find_branch(name, BranchType::Local).is_ok() {
+      return Ok(BranchNameValidity::AlreadyExists);
+    }
+    Ok(BranchNameValidity::Valid)
   }
 
   fn create_branch(&self, to_create: &GitBranch) -> Result<(), Error> {
     info!("Creating branch {}", to_create.name);
-    let head = self.repo.head()?;
+    let head = match self.repo.head() {
+      Ok(head) => head,
+      Err(err) if err.code() == ErrorCode::UnbornBranch => return Err(Error::UnbornHead),
+      Err(err) => return Err(Error::Git2(err)),
+    };
     let head_oid = head.target();
 
     if head_oid.is_none() {
-      error!("Attempted to create a branch from a symbolic reference: {}", head_oid.unwrap());
+      error!("Attempted to create a branch from a symbolic reference: {}", head.name().unwrap_or_default());
       return Err(Error::Git("Attempted to create a branch from a symbolic reference".to_string()));
     }
 
@@ -97,28 +233,416 @@ impl GitRepo for Git2Repo {
     Ok(())
   }
 
+  fn create_branch_from(&self, to_create: &GitBranch, start_point: &str) -> Result<(), Error> {
+    info!("Creating branch {} from {}", to_create.name, start_point);
+    let commit = self.repo.revparse_single(start_point)?.peel_to_commit()?;
+    self.repo.branch(&to_create.name, &commit, false)?;
+    Ok(())
+  }
+
+  fn commit_empty(&self, message: &str) -> Result<(), Error> {
+    let head = self.repo.head()?.peel_to_commit()?;
+    let signature = self.repo.signature()?;
+    self.repo.commit(Some("HEAD"), &signature, &signature, message, &head.tree()?, &[&head])?;
+    Ok(())
+  }
+
   fn delete_branch(&self, to_delete: &GitBranch) -> Result<(), Error> {
-    let branches = self.repo.branches(Some(BranchType::Local))?;
-    for res in branches.into_iter() {
-      if res.is_err() {
+    let mut branch = match self.repo.find_branch(&to_delete.name, BranchType::Local) {
+      Ok(branch) => branch,
+      Err(err) if err.code() == ErrorCode::NotFound => return Err(Error::BranchNotFound(to_delete.name.clone())),
+      Err(err) => return Err(Error::Git2(err)),
+    };
+    // libgit2 refuses as well, but in terms of refs rather than the branch the user picked.
+    if branch.is_head() {
+      return Err(Error::DeleteCheckedOutBranch(to_delete.name.clone()));
+    }
+    branch.delete()?;
+    Ok(())
+  }
+
+  fn rename_branch(&self, from: &str, to: &str) -> Result<(), Error> {
+    let mut branch = match self.repo.find_branch(from, BranchType::Local) {
+      Ok(branch) => branch,
+      Err(err) if err.code() == ErrorCode::NotFound => return Err(Error::BranchNotFound(String::from(from))),
+      Err(err) => return Err(Error::Git2(err)),
+    };
+    // libgit2 moves the branch's config section too, like `git branch -m`.
+    branch.rename(to, false)?;
+    Ok(())
+  }
+
+  fn remote_default_branch(&self) -> Result<Option<String>, Error> {
+    let remote = default_remote(self)?;
+    let Ok(remote_head) = self.repo.find_reference(&format!("refs/remotes/{}/HEAD", remote)) else {
+      return Ok(None);
+    };
+    let prefix = format!("refs/remotes/{}/", remote);
+    let target = remote_head.symbolic_target().and_then(|target| target.strip_prefix(&prefix));
+    Ok(target.map(String::from))
+  }
+
+  fn remotes(&self) -> Result<Vec<String>, Error> {
+    Ok(self.repo.remotes()?.iter().flatten().map(String::from).collect())
+  }
+
+  fn remote_branches(&self) -> Result<Vec<GitBranch>, Error> {
+    let mut branches = Vec::new();
+    for result in self.repo.branches(Some(BranchType::Remote))? {
+      let (branch, _branch_type) = result?;
+      // `origin/HEAD` is a symbolic ref to the remote's default branch.
+      if branch.get().symbolic_target().is_some() {
         continue;
       }
-      let (mut branch, _branch_type) = res?;
-      if branch.name().is_err() {
+      let Some(name) = branch.name()? else {
         continue;
+      };
+      let mut remote_branch = GitBranch::new(String::from(name));
+      remote_branch.tip_sha = branch.get().target().map(|oid| oid.to_string());
+      remote_branch.last_commit_time = branch.get().peel_to_commit().ok().map(|commit| commit.time().seconds());
+      branches.push(remote_branch);
+    }
+    Ok(branches)
+  }
+
+  fn set_branch_description(&self, branch: &str, description: &str) -> Result<(), Error> {
+    let key = format!("branch.{}.description", branch);
+    let mut config = self.repo.config()?;
+    if !description.is_empty() {
+      config.set_str(&key, description)?;
+      return Ok(());
+    }
+    match config.remove(&key) {
+      Err(err) if err.code() != ErrorCode::NotFound => Err(Error::Git2(err)),
+      _ => Ok(()),
+    }
+  }
+
+  fn git_config_get(&self, key: &str) -> Result<Option<String>, Error> {
+    match self.repo.config()?.get_string(key) {
+      Ok(value) => Ok(Some(value)),
+      Err(err) if err.code() == ErrorCode::NotFound => Ok(None),
+      Err(err) => Err(Error::Git2(err)),
+    }
+  }
+
+  fn remote_url(&self, remote: &str) -> Result<Option<String>, Error> {
+    let Ok(remote) = self.repo.find_remote(remote) else {
+      return Ok(None);
+    };
+    Ok(remote.url().map(String::from))
+  }
+
+  fn merged_branches(&self, base: &str) -> Result<Vec<String>, Error> {
+    let base_oid = self.repo.revparse_single(base)?.peel_to_commit()?.id();
+    let mut merged = Vec::new();
+    for res in self.repo.branches(Some(BranchType::Local))? {
+      let (branch, _branch_type) = res?;
+      let (Some(name), Some(tip)) = (branch.name()?, branch.get().target()) else {
+        continue;
+      };
+      if tip == base_oid || self.repo.graph_descendant_of(base_oid, tip)? {
+        merged.push(String::from(name));
+      }
+    }
+    Ok(merged)
+  }
+
+  fn branch_details(&self, branch: &GitBranch) -> Result<GitBranchDetails, Error> {
+    let local = match self.repo.find_branch(&branch.name, BranchType::Local) {
+      Ok(local) => local,
+      Err(err) if err.code() == ErrorCode::NotFound => return Err(Error::BranchNotFound(branch.name.clone())),
+      Err(err) => return Err(Error::Git2(err)),
+    };
+    let commit = local.get().peel_to_commit()?;
+    let upstream = local.upstream().ok();
+    let upstream_name = upstream.as_ref().and_then(|upstream| upstream.name().ok().flatten()).map(String::from);
+    let ahead_behind = match upstream.as_ref().and_then(|upstream| upstream.get().target()) {
+      Some(upstream_oid) => Some(self.repo.graph_ahead_behind(commit.id(), upstream_oid)?),
+      None => None,
+    };
+    Ok(GitBranchDetails {
+      full_ref: String::from(local.get().name().unwrap_or_default()),
+      sha: commit.id().to_string(),
+      upstream: upstream_name,
+      ahead_behind,
+      last_commit: to_git_commit(&commit),
+    })
+  }
+
+  fn ahead_behind(&self, local: &str, upstream: &str) -> Result<(usize, usize), Error> {
+    let local_oid = self.repo.revparse_single(local)?.peel_to_commit()?.id();
+    let upstream_oid = self.repo.revparse_single(upstream)?.peel_to_commit()?.id();
+    Ok(self.repo.graph_ahead_behind(local_oid, upstream_oid)?)
+  }
+
+  fn commits_between(&self, base: &str, compare: &str) -> Result<Vec<GitCommit>, Error> {
+    let mut revwalk = self.repo.revwalk()?;
+    revwalk.push(self.repo.revparse_single(compare)?.peel_to_commit()?.id())?;
+    revwalk.hide(self.repo.revparse_single(base)?.peel_to_commit()?.id())?;
+    let mut commits = Vec::new();
+    for oid in revwalk {
+      commits.push(to_git_commit(&self.repo.find_commit(oid?)?));
+    }
+    Ok(commits)
+  }
+
+  fn count_commits(&self, range: &str) -> Result<usize, Error> {
+    let mut revwalk = self.repo.revwalk()?;
+    if range.contains("..") {
+      revwalk.push_range(range)?;
+    } else {
+      revwalk.push(self.repo.revparse_single(range)?.peel_to_commit()?.id())?;
+    }
+    let mut count = 0;
+    for oid in revwalk {
+      oid?;
+      count += 1;
+    }
+    Ok(count)
+  }
+
+  fn diff_stat(&self, base: &str, compare: &str) -> Result<String, Error> {
+    let base_commit = self.repo.revparse_single(base)?.peel_to_commit()?;
+    let compare_commit = self.repo.revparse_single(compare)?.peel_to_commit()?;
+    // Same as `base...compare`, only what changed on compare since the branches diverged.
+    let merge_base = self.repo.find_commit(self.repo.merge_base(base_commit.id(), compare_commit.id())?)?;
+    let diff = self.repo.diff_tree_to_tree(Some(&merge_base.tree()?), Some(&compare_commit.tree()?), None)?;
+    let stats = diff.stats()?.to_buf(DiffStatsFormat::FULL, 80)?;
+    Ok(String::from(stats.as_str().unwrap_or_default()))
+  }
+
+  fn commit_log(&self, branch: &str, skip: usize, limit: usize, with_notes: bool) -> Result<Vec<GitCommit>, Error> {
+    let mut revwalk = self.repo.revwalk()?;
+    revwalk.push(self.repo.revparse_single(branch)?.peel_to_commit()?.id())?;
+    let mut commits = Vec::new();
+    for oid in revwalk.skip(skip).take(limit) {
+      let oid = oid?;
+      let mut commit = to_git_commit(&self.repo.find_commit(oid)?);
+      if with_notes {
+        // A commit without a note is a not found error.
+        let note = self.repo.find_note(None, oid).ok();
+        commit.note = note.and_then(|note| note.message().map(|message| String::from(message.trim())));
+      }
+      commits.push(commit);
+    }
+    Ok(commits)
+  }
+
+  fn reflog(&self, limit: usize) -> Result<Vec<GitReflogEntry>, Error> {
+    let reflog = self.repo.reflog("HEAD")?;
+    let entries = reflog
+      .iter()
+      .take(limit)
+      .map(|entry| {
+        let subject = entry.message().unwrap_or_default();
+        let (action, message) = subject.split_once(": ").unwrap_or((subject, ""));
+        GitReflogEntry { sha: entry.id_new().to_string(), action: String::from(action), message: String::from(message) }
+      })
+      .collect();
+    Ok(entries)
+  }
+
+  fn cherry_pick(&self, sha: &str) -> Result<(), Error> {
+    let commit = self.repo.revparse_single(sha)?.peel_to_commit()?;
+    self.repo.cherrypick(&commit, None)?;
+    let mut index = self.repo.index()?;
+    if index.has_conflicts() {
+      return Err(Error::CherryPickConflict(String::from(sha)));
+    }
+    // Unlike the cli, libgit2 only updates the index and worktree, committing is left to us.
+    let tree = self.repo.find_tree(index.write_tree()?)?;
+    let head = self.repo.head()?.peel_to_commit()?;
+    let committer = self.repo.signature()?;
+    self
+      .repo
+      .commit(Some("HEAD"), &commit.author(), &committer, commit.message().unwrap_or_default(), &tree, &[&head])?;
+    self.repo.cleanup_state()?;
+    Ok(())
+  }
+
+  fn rebase(&self, onto: &str) -> Result<(), Error> {
+    let upstream = self.repo.find_annotated_commit(self.repo.revparse_single(onto)?.peel_to_commit()?.id())?;
+    let mut rebase = self.repo.rebase(None, Some(&upstream), None, None)?;
+    let committer = self.repo.signature()?;
+    while let Some(operation) = rebase.next() {
+      operation?;
+      // Left in progress like the cli does, so it can be finished or aborted with git.
+      if self.repo.index()?.has_conflicts() {
+        return Err(Error::RebaseConflict(String::from(onto)));
       }
-      let name = branch.name()?;
-      if name.is_some() && to_delete.name == name.unwrap() {
-        branch.delete()?;
-        break;
+      match rebase.commit(None, &committer, None) {
+        // The change is already on `onto`, so there's nothing to commit and the cli would drop it too.
+        Err(err) if err.code() == ErrorCode::Applied => {},
+        res => {
+          res?;
+        },
       }
     }
+    rebase.finish(Some(&committer))?;
+    Ok(())
+  }
+
+  fn rebase_in_progress(&self) -> Result<bool, Error> {
+    Ok(matches!(
+      self.repo.state(),
+      RepositoryState::Rebase
+        | RepositoryState::RebaseInteractive
+        | RepositoryState::RebaseMerge
+        | RepositoryState::ApplyMailboxOrRebase
+    ))
+  }
+
+  fn fetch(&self, cancel: &CancellationToken, progress: &mut dyn FnMut(FetchProgress)) -> Result<(), Error> {
+    let mut remote = self.repo.find_remote(&default_remote(self)?)?;
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.transfer_progress(|stats| {
+      progress(FetchProgress {
+        received_objects: stats.received_objects(),
+        total_objects: stats.total_objects(),
+        received_bytes: stats.received_bytes(),
+      });
+      // Returning false makes libgit2 abort the transfer.
+      !cancel.is_cancelled()
+    });
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(callbacks);
+    // No refspecs means the ones configured for the remote.
+    match remote.fetch(&[] as &[&str], Some(&mut options), None) {
+      // A cancel that lands after the transfer finished is too late to stop it, so the fetch still counts.
+      Ok(()) => Ok(()),
+      Err(_) if cancel.is_cancelled() => Err(Error::Cancelled),
+      Err(err) => Err(Error::Git2(err)),
+    }
+  }
+
+  fn prune_remote(&self, remote: &str, cancel: &CancellationToken) -> Result<Vec<String>, Error> {
+    let before = self.remote_tracking_branches(remote)?;
+    let mut git_remote = self.repo.find_remote(remote)?;
+    // Pruning compares against what the remote lists, so it needs a connection first.
+    git_remote.connect(Direction::Fetch)?;
+    if cancel.is_cancelled() {
+      return Err(Error::Cancelled);
+    }
+    git_remote.prune(None)?;
+    let after = self.remote_tracking_branches(remote)?;
+    Ok(before.into_iter().filter(|branch| !after.contains(branch)).collect())
+  }
+
+  fn push_renamed_branch(
+    &self,
+    branch: &str,
+    upstream: &GitRemoteBranch,
+    cancel: &CancellationToken,
+  ) -> Result<(), Error> {
+    let Some(remote_name) = upstream.remote.as_deref() else {
+      return Err(Error::Git(format!("{} isn't on a remote", upstream.name)));
+    };
+    let mut remote = self.repo.find_remote(remote_name)?;
+    let old_ref = format!("refs/heads/{}", upstream.branch_name());
+    let tracked = self.repo.refname_to_id(&format!("refs/remotes/{}", upstream.name))?;
+    // libgit2 has no lease, so the old branch is checked first. Commits pushed there since the last fetch would
+    // otherwise be deleted unseen.
+    remote.connect(Direction::Push)?;
+    let current = remote.list()?.iter().find(|head| head.name() == old_ref).map(|head| head.oid());
+    remote.disconnect()?;
+    if current.is_some_and(|oid| oid != tracked) {
+      return Err(Error::RemoteBranchMoved(upstream.name.clone()));
+    }
+    // There's no aborting a push part way, so this is the last chance to give up.
+    if cancel.is_cancelled() {
+      return Err(Error::Cancelled);
+    }
+    let mut rejected = Vec::new();
+    {
+      let mut callbacks = RemoteCallbacks::new();
+      // A ref the remote refuses doesn't fail the push, it's only reported here.
+      callbacks.push_update_reference(|refname, status| {
+        if let Some(status) = status {
+          rejected.push(format!("{}: {}", refname, status));
+        }
+        Ok(())
+      });
+      let mut options = PushOptions::new();
+      options.remote_callbacks(callbacks);
+      let push = format!("refs/heads/{}:refs/heads/{}", branch, branch);
+      let delete = format!(":{}", old_ref);
+      remote.push(&[push.as_str(), delete.as_str()], Some(&mut options))?;
+    }
+    if !rejected.is_empty() {
+      return Err(Error::Git(format!("{} rejected {}", remote_name, rejected.join(", "))));
+    }
+    // Pushing updated the remote tracking branches, so the new one is there to track.
+    self.repo.find_branch(branch, BranchType::Local)?.set_upstream(Some(&format!("{}/{}", remote_name, branch)))?;
     Ok(())
   }
 }
 
-fn extract_upstream_branch(local_branch: &Branch) -> Option<GitRemoteBranch> {
+fn to_git_commit(commit: &Commit) -> GitCommit {
+  GitCommit {
+    sha: commit.id().to_string(),
+    author: String::from(commit.author().name().unwrap_or_default()),
+    time: commit.time().seconds(),
+    subject: String::from(commit.summary().unwrap_or_default()),
+    note: None,
+  }
+}
+
+/// libgit2 only updates one level of submodules at a time, so recurse into each for `--recursive`.
+fn update_submodules(repo: &Repository, cancel: &CancellationToken) -> Result<(), Error> {
+  for mut submodule in repo.submodules()? {
+    if cancel.is_cancelled() {
+      return Err(Error::Cancelled);
+    }
+    info!("Updating submodule {}", submodule.path().display());
+    let mut callbacks = RemoteCallbacks::new();
+    // Returning false makes libgit2 abort fetching the submodule.
+    callbacks.transfer_progress(|_| !cancel.is_cancelled());
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks);
+    let mut options = SubmoduleUpdateOptions::new();
+    options.fetch(fetch_options);
+    let result = submodule.update(true, Some(&mut options));
+    if cancel.is_cancelled() {
+      return Err(Error::Cancelled);
+    }
+    result?;
+    update_submodules(&submodule.open()?, cancel)?;
+  }
+  Ok(())
+}
+
+fn extract_upstream_branch(local_branch: &Branch, remotes: &[String]) -> Option<GitRemoteBranch> {
   let upstream_branch = local_branch.upstream().ok()?;
   let upstream_name = upstream_branch.name().ok()??;
-  Some(GitRemoteBranch { name: String::from(upstream_name) })
+  Some(GitRemoteBranch::with_remotes(String::from(upstream_name), remotes))
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::git::test_repo::TestRepo;
+
+  #[test]
+  fn checkout_remote_branch_creates_tracking_branch() {
+    let upstream = TestRepo::new();
+    upstream.git(&["branch", "feature"]);
+    let test_repo = TestRepo::new();
+    test_repo.git(&["remote", "add", "origin", &upstream.path().display().to_string()]);
+    test_repo.git(&["fetch", "origin"]);
+    test_repo.git(&["remote", "set-head", "origin", "main"]);
+    let repo = Git2Repo::from_path(test_repo.path()).unwrap();
+
+    let mut names: Vec<String> = repo.remote_branches().unwrap().into_iter().map(|branch| branch.name).collect();
+    names.sort();
+    assert_eq!(names, vec!["origin/feature", "origin/main"]);
+
+    repo
+      .checkout_remote_branch(&GitRemoteBranch::with_remotes(String::from("origin/feature"), &repo.remotes().unwrap()))
+      .unwrap();
+    assert_eq!(test_repo.current_branch(), "feature");
+    assert_eq!(test_repo.git(&["rev-parse", "--abbrev-ref", "feature@{upstream}"]).trim(), "origin/feature");
+  }
 }
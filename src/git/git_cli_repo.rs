@@ -1,59 +1,312 @@
-use std::process::Command;
+use std::{
+  collections::{HashMap, HashSet},
+  env::current_dir,
+  io::{self, ErrorKind, Read},
+  path::{Path, PathBuf},
+  process::{Command, Output, Stdio},
+  thread::{self, JoinHandle},
+  time::Duration,
+};
 
+use lazy_static::lazy_static;
 use regex::Regex;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
 
 use crate::{
   error::Error,
-  git::git_repo::{GitBranch, GitRemoteBranch, GitRepo, GitStash},
+  git::git_repo::{
+    default_remote, BranchNameValidity, FetchProgress, GitBranch, GitBranchDetails, GitCommit, GitReflogEntry,
+    GitRemoteBranch, GitRepo, GitStash,
+  },
 };
 
-pub struct GitCliRepo {}
+// Git must never prompt on the terminal while the TUI owns it, a credential prompt would be drawn over the UI and
+// block waiting on input we never forward. With these set git fails fast instead and we report the auth failure.
+const NON_INTERACTIVE_ENV: [(&str, &str); 3] =
+  [("GIT_TERMINAL_PROMPT", "0"), ("GIT_ASKPASS", ""), ("GCM_INTERACTIVE", "never")];
+
+const AUTH_FAILURE_MARKERS: [&str; 4] =
+  ["terminal prompts disabled", "could not read Username", "could not read Password", "Authentication failed"];
+
+lazy_static! {
+  // `git branch -D` and friends on a branch that's gone.
+  static ref BRANCH_NOT_FOUND: Regex = Regex::new(r"(?i)branch '([^']+)' not found").unwrap();
+}
+
+// Config that changes what git prints, pinned so parsing doesn't depend on the user's git config. Specific color
+// settings like `color.branch=always` beat `color.ui`, so those are pinned too.
+const BASELINE_CONFIG: [&str; 7] = [
+  "color.ui=never",
+  "color.branch=never",
+  "color.diff=never",
+  "column.ui=never",
+  "log.decorate=no",
+  "log.showSignature=false",
+  "core.quotePath=false",
+];
+
+// How often a cancellable command checks whether it has been cancelled.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+fn read_in_background(mut pipe: impl Read + Send + 'static) -> JoinHandle<Vec<u8>> {
+  thread::spawn(move || {
+    let mut buffer = Vec::new();
+    let _ = pipe.read_to_end(&mut buffer);
+    buffer
+  })
+}
+
+/// `args` prefixed with the options every command is run with, see [`BASELINE_CONFIG`].
+fn with_baseline_args<'a>(args: &[&'a str]) -> Vec<&'a str> {
+  let mut all_args = vec!["--no-pager"];
+  for config in BASELINE_CONFIG {
+    all_args.push("-c");
+    all_args.push(config);
+  }
+  all_args.extend_from_slice(args);
+  all_args
+}
+
+/// Everything git printed for a successful command, some commands (e.g. `checkout`) report on stderr even on success.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GitOutput {
+  pub stdout: String,
+  pub stderr: String,
+}
+
+// Fields are NUL separated so subjects can contain anything.
+const COMMIT_FORMAT: &str = "--format=%H%x00%an%x00%ct%x00%s";
+// Notes can span several lines, so commits with them end in a record separator rather than a newline.
+const COMMIT_WITH_NOTES_FORMAT: &str = "--format=%H%x00%an%x00%ct%x00%s%x00%N%x1e";
+
+pub const DEFAULT_GIT_PATH: &str = "git";
+
+// Put before branch names and revisions, so a branch named like `-D` or `--help` isn't read as an option. Unlike `--`
+// it doesn't start pathspecs, which is also why checkouts use `switch`, `checkout` only learnt it in later gits.
+const END_OF_OPTIONS: &str = "--end-of-options";
+
+// `switch` came in git 2.23 and `--end-of-options` in 2.24.
+const MIN_GIT_VERSION: (u32, u32) = (2, 24);
+
+/// Checks `git_path` runs as git and is at least [`MIN_GIT_VERSION`], nothing works without it so this is done on start
+/// rather than on first action.
+pub fn ensure_git_installed(git_path: &Path) -> Result<(), Error> {
+  let display_path = git_path.display().to_string();
+  match Command::new(git_path).arg("--version").stdin(Stdio::null()).output() {
+    Ok(output) if output.status.success() => {
+      let version = String::from_utf8_lossy(&output.stdout);
+      // A version that can't be read is given the benefit of the doubt.
+      match parse_git_version(&version) {
+        Some(parsed) if parsed < MIN_GIT_VERSION => Err(Error::GitTooOld(String::from(version.trim()))),
+        _ => Ok(()),
+      }
+    },
+    Ok(_) => Err(Error::GitNotExecutable(display_path)),
+    Err(err) if err.kind() == ErrorKind::NotFound => Err(Error::GitNotFound(display_path)),
+    Err(err) if err.kind() == ErrorKind::PermissionDenied => Err(Error::GitNotExecutable(display_path)),
+    Err(err) => Err(Error::Git(format!("{}", err))),
+  }
+}
+
+/// The major and minor version from `git --version`, which looks like `git version 2.39.3 (Apple Git-146)`.
+fn parse_git_version(output: &str) -> Option<(u32, u32)> {
+  let version = output.trim().strip_prefix("git version ")?;
+  let mut parts = version.split(|c: char| !c.is_ascii_digit());
+  let major = parts.next()?.parse().ok()?;
+  let minor = parts.next()?.parse().ok()?;
+  Some((major, minor))
+}
+
+/// A switch to a branch that doesn't exist is reported as an invalid reference.
+fn missing_branch_as_not_found(result: Result<GitOutput, Error>, branch_name: &str) -> Result<(), Error> {
+  match result {
+    Err(Error::CommandFailed { stderr, .. }) if stderr.contains("invalid reference") => {
+      Err(Error::BranchNotFound(String::from(branch_name)))
+    },
+    res => res.map(|_| ()),
+  }
+}
+
+/// Turn a failed command's stderr into the most specific error, falling back to [`Error::CommandFailed`] with the raw
+/// text.
+fn classify_failure(args: String, stderr: String) -> Error {
+  if AUTH_FAILURE_MARKERS.iter().any(|marker| stderr.contains(marker)) {
+    return Error::AuthenticationRequired(args);
+  }
+  if stderr.contains("not a git repository") {
+    return Error::NotARepository;
+  }
+  if let Some(captures) = BRANCH_NOT_FOUND.captures(&stderr) {
+    return Error::BranchNotFound(String::from(&captures[1]));
+  }
+  // Covers both tracked changes and untracked files that an operation would replace.
+  if stderr.contains("would be overwritten by") || stderr.contains("Please commit your changes or stash them") {
+    return Error::DirtyWorkingTree(args);
+  }
+  Error::CommandFailed { args, stderr: String::from(stderr.trim()) }
+}
+
+#[derive(Clone)]
+pub struct GitCliRepo {
+  // Where git commands are run from
+  path: PathBuf,
+  // The git executable, `git` on the PATH unless configured
+  git_path: PathBuf,
+}
 
 impl GitCliRepo {
   pub fn from_cwd() -> Result<GitCliRepo, Error> {
     // TODO check that the user is in a repo and throw if not
-    Ok(GitCliRepo {})
+    let path_buf = current_dir().expect("Unable to get current working directory");
+    Ok(GitCliRepo::from_path(&path_buf))
   }
-}
 
-impl GitRepo for GitCliRepo {
-  fn local_branches(&self) -> Result<Vec<GitBranch>, Error> {
-    let res = run_git_command(&["branch", "--list", "-vv"])?;
+  pub fn from_path(path: &Path) -> GitCliRepo {
+    GitCliRepo { path: path.to_path_buf(), git_path: PathBuf::from(DEFAULT_GIT_PATH) }
+  }
+
+  pub fn with_git_path(mut self, git_path: PathBuf) -> Self {
+    self.git_path = git_path;
+    self
+  }
+
+  fn git_command(&self, args: &[&str]) -> Command {
+    let mut command = Command::new(&self.git_path);
+    command.args(with_baseline_args(args)).current_dir(&self.path).envs(NON_INTERACTIVE_ENV).stdin(Stdio::null());
+    command
+  }
+
+  fn run_git_command(&self, args: &[&str]) -> Result<GitOutput, Error> {
+    let args_log_command = args.join(" ");
+    info!("Running `git {}`", args_log_command);
+    let res = self.git_command(args).output();
+    self.handle_output(args_log_command, res)
+  }
+
+  /// Like [`GitCliRepo::run_git_command`] but kills git and returns [`Error::Cancelled`] as soon as `cancel` is
+  /// cancelled, for commands that can take a long time like talking to a remote.
+  fn run_cancellable_git_command(&self, args: &[&str], cancel: &CancellationToken) -> Result<GitOutput, Error> {
+    let args_log_command = args.join(" ");
+    info!("Running `git {}`", args_log_command);
+    let spawned = self.git_command(args).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn();
+    let mut child = match spawned {
+      Ok(child) => child,
+      Err(err) => return self.handle_output(args_log_command, Err(err)),
+    };
+    // Drain the pipes on their own threads so git can't block on a full pipe while we wait for it.
+    let stdout = child.stdout.take().map(read_in_background);
+    let stderr = child.stderr.take().map(read_in_background);
+    let status = loop {
+      if cancel.is_cancelled() {
+        info!("Cancelled `git {}`", args_log_command);
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err(Error::Cancelled);
+      }
+      match child.try_wait() {
+        Ok(Some(status)) => break status,
+        Ok(None) => thread::sleep(CANCEL_POLL_INTERVAL),
+        Err(err) => return self.handle_output(args_log_command, Err(err)),
+      }
+    };
+    let join = |reader: Option<JoinHandle<Vec<u8>>>| reader.and_then(|reader| reader.join().ok()).unwrap_or_default();
+    let output = Output { status, stdout: join(stdout), stderr: join(stderr) };
+    self.handle_output(args_log_command, Ok(output))
+  }
+
+  fn handle_output(&self, args_log_command: String, res: io::Result<Output>) -> Result<GitOutput, Error> {
+    if res.is_err() {
+      let err = res.err().unwrap();
+      error!("Failed to run `git {}`, error: {}", args_log_command, err);
+      if err.kind() == ErrorKind::NotFound {
+        return Err(Error::GitNotFound(self.git_path.display().to_string()));
+      }
+      return Err(Error::Git(format!("{}", err)));
+    }
+
+    let output = res.unwrap();
+    let err = String::from_utf8(output.stderr)?;
+    if !output.status.success() && !err.is_empty() {
+      error!("Failed to run `git {}`, error: {}", args_log_command, err);
+      return Err(classify_failure(args_log_command, err));
+    }
+    let content = String::from_utf8(output.stdout)?;
+    info!("Received git cli reply:\n{}{}", content, err);
+    Ok(GitOutput { stdout: content, stderr: err })
+  }
 
-    let branches: Vec<GitBranch> = res
+  /// Whether `name` exists in the git dir, like `CHERRY_PICK_HEAD` while a cherry-pick is stopped on conflicts.
+  fn git_path_exists(&self, name: &str) -> Result<bool, Error> {
+    // Relative to the working directory.
+    let path = self.run_git_command(&["rev-parse", "--git-path", name])?.stdout;
+    Ok(self.path.join(path.trim()).exists())
+  }
+
+  /// Committer times of every local branch tip, `branch -vv` doesn't include them.
+  fn branch_commit_times(&self) -> Result<HashMap<String, i64>, Error> {
+    let res =
+      self.run_git_command(&["for-each-ref", "--format=%(refname:short)%00%(committerdate:unix)", "refs/heads"])?;
+    let times = res
+      .stdout
       .lines()
-      .map(|line| {
-        let trimmed = line.trim();
-        // A regex to capture the following git list outputs
-        // * git-cli-repo 911ec26 [origin/git-cli-repo] Linting
-        //   main         8fb5d9b [origin/main] Fix build
-        //   stash-list   6442450 [origin/stash-list: gone] Formatting
-        //   test         dbcf785 Updates
-        let re = Regex::new(
-          r"((?<head>\*)\s+)?(?<name>\S+)\s+(?<sha>[A-Fa-f0-9]+)\s+(\[(?<upstream>[^:|^\]]+)(?<gone>[:\sgone]+)?)?",
-        )
-        .unwrap();
-        let Some(captures) = re.captures(trimmed) else {
-          error!("Failed to capture git branch information for: {}", trimmed);
-          return GitBranch::new(String::from(trimmed));
-        };
-        let is_head = captures.name("head").is_some();
-        let name = String::from(captures.name("name").unwrap().as_str());
-        let upstream = captures.name("upstream");
-        GitBranch {
-          name,
-          is_head,
-          upstream: upstream.map(|upstream_name| GitRemoteBranch::new(String::from(upstream_name.as_str()))),
-        }
+      .filter_map(|line| {
+        let (name, time) = line.split_once('\0')?;
+        Some((String::from(name), time.parse().ok()?))
       })
       .collect();
+    Ok(times)
+  }
+
+  /// Descriptions of every branch that has one, by branch name.
+  fn branch_descriptions(&self) -> HashMap<String, String> {
+    // Exits with 1 when no branch has a description.
+    let res = self
+      .run_git_command(&["config", "-z", "--get-regexp", r"^branch\..*\.description$"])
+      .map(|output| output.stdout)
+      .unwrap_or_default();
+    parse_branch_descriptions(&res)
+  }
+
+  /// The remote tracking branches of `remote`, like `origin/feature`.
+  fn remote_tracking_branches(&self, remote: &str) -> Result<Vec<String>, Error> {
+    let res = self.run_git_command(&["for-each-ref", "--format=%(refname)", &format!("refs/remotes/{}/", remote)])?;
+    Ok(res.stdout.lines().filter_map(|line| line.strip_prefix("refs/remotes/")).map(String::from).collect())
+  }
+}
+
+impl GitRepo for GitCliRepo {
+  fn try_clone(&self) -> Result<Box<dyn GitRepo>, Error> {
+    Ok(Box::new(self.clone()))
+  }
+
+  fn is_bare(&self) -> Result<bool, Error> {
+    let res = self.run_git_command(&["rev-parse", "--is-bare-repository"])?.stdout;
+    Ok(res.trim() == "true")
+  }
+
+  fn is_shallow(&self) -> Result<bool, Error> {
+    let res = self.run_git_command(&["rev-parse", "--is-shallow-repository"])?.stdout;
+    Ok(res.trim() == "true")
+  }
+
+  fn local_branches(&self) -> Result<Vec<GitBranch>, Error> {
+    let res = self.run_git_command(&["branch", "--list", "-vv", "--no-abbrev", "--no-color"])?.stdout;
+    let commit_times = self.branch_commit_times()?;
+    let mut descriptions = self.branch_descriptions();
+    let remotes = self.remotes()?;
 
+    let mut branches = parse_branch_vv(&res);
+    for branch in branches.iter_mut() {
+      branch.last_commit_time = commit_times.get(&branch.name).copied();
+      branch.description = descriptions.remove(&branch.name);
+      branch.upstream = branch.upstream.take().map(|upstream| GitRemoteBranch::with_remotes(upstream.name, &remotes));
+    }
     Ok(branches)
   }
 
   fn stashes(&mut self) -> Result<Vec<GitStash>, Error> {
-    let res = run_git_command(&["branch", "--list"])?;
+    let res = self.run_git_command(&["branch", "--list"])?.stdout;
 
     let stashes: Vec<GitStash> = res
       .lines()
@@ -65,47 +318,703 @@ impl GitRepo for GitCliRepo {
   }
 
   fn checkout_branch_from_name(&self, branch_name: &str) -> Result<(), Error> {
-    run_git_command(&["checkout", branch_name])?;
-    Ok(())
+    match self.run_git_command(&["switch", END_OF_OPTIONS, branch_name]) {
+      Err(Error::DirtyWorkingTree(_)) => Err(Error::CheckoutConflict(String::from(branch_name))),
+      res => missing_branch_as_not_found(res, branch_name),
+    }
+  }
+
+  fn force_checkout_branch_from_name(&self, branch_name: &str) -> Result<(), Error> {
+    missing_branch_as_not_found(
+      self.run_git_command(&["switch", "--discard-changes", END_OF_OPTIONS, branch_name]),
+      branch_name,
+    )
+  }
+
+  fn checkout_detached(&self, revision: &str) -> Result<(), Error> {
+    match self.run_git_command(&["switch", "--detach", END_OF_OPTIONS, revision]) {
+      Err(Error::DirtyWorkingTree(_)) => Err(Error::CheckoutConflict(String::from(revision))),
+      res => res.map(|_| ()),
+    }
+  }
+
+  fn checkout_remote_branch(&self, remote_branch: &GitRemoteBranch) -> Result<(), Error> {
+    let local_name = remote_branch.branch_name();
+    let create = format!("--create={}", local_name);
+    match self.run_git_command(&["switch", &create, "--track", END_OF_OPTIONS, &remote_branch.name]) {
+      Err(Error::DirtyWorkingTree(_)) => Err(Error::CheckoutConflict(String::from(local_name))),
+      res => res.map(|_| ()),
+    }
   }
 
   fn checkout_branch(&self, branch: &GitBranch) -> Result<(), Error> {
     self.checkout_branch_from_name(&branch.name)
   }
 
-  fn validate_branch_name(&self, name: &str) -> Result<bool, Error> {
-    let res = run_git_command(&["check-ref-format", "--branch", name]);
-    Ok(res.is_ok())
+  fn gitmodules_changed(&self, revision: &str) -> Result<bool, Error> {
+    let res =
+      self.run_git_command(&["diff", "--name-only", END_OF_OPTIONS, revision, "HEAD", "--", ".gitmodules"])?.stdout;
+    Ok(!res.trim().is_empty())
+  }
+
+  fn update_submodules(&self, cancel: &CancellationToken) -> Result<(), Error> {
+    self.run_cancellable_git_command(&["submodule", "update", "--init", "--recursive"], cancel).map(|_| ())
+  }
+
+  fn validate_branch_name(&self, name: &str) -> Result<BranchNameValidity, Error> {
+    if self.run_git_command(&["check-ref-format", "--branch", name]).is_err() {
+      return Ok(BranchNameValidity::InvalidFormat);
+    }
+    // Prints the sha when the branch exists and nothing otherwise.
+    let existing = self.run_git_command(&["rev-parse", "--verify", "--quiet", &format!("refs/heads/{}", name)])?;
+    if !existing.stdout.trim().is_empty() {
+      return Ok(BranchNameValidity::AlreadyExists);
+    }
+    Ok(BranchNameValidity::Valid)
   }
 
   fn create_branch(&self, to_create: &GitBranch) -> Result<(), Error> {
-    run_git_command(&["checkout", "-b", &to_create.name])?;
+    // An unborn HEAD has nothing to branch from, `--quiet` makes that an empty reply rather than an error.
+    if self.run_git_command(&["rev-parse", "--verify", "--quiet", "HEAD"])?.stdout.trim().is_empty() {
+      return Err(Error::UnbornHead);
+    }
+    self.run_git_command(&["branch", END_OF_OPTIONS, &to_create.name])?;
+    Ok(())
+  }
+
+  fn create_branch_from(&self, to_create: &GitBranch, start_point: &str) -> Result<(), Error> {
+    self.run_git_command(&["branch", END_OF_OPTIONS, &to_create.name, start_point])?;
+    Ok(())
+  }
+
+  fn commit_empty(&self, message: &str) -> Result<(), Error> {
+    self.run_git_command(&["commit", "--allow-empty", "-m", message])?;
     Ok(())
   }
 
   fn delete_branch(&self, to_delete: &GitBranch) -> Result<(), Error> {
-    run_git_command(&["branch", "-D", &to_delete.name])?;
+    match self.run_git_command(&["branch", "-D", END_OF_OPTIONS, &to_delete.name]) {
+      // Older gits say "Cannot delete branch 'x' checked out at", newer ones "cannot delete branch 'x' used by worktree".
+      Err(Error::CommandFailed { stderr, .. }) if stderr.to_lowercase().contains("cannot delete branch") => {
+        Err(Error::DeleteCheckedOutBranch(to_delete.name.clone()))
+      },
+      res => res.map(|_| ()),
+    }
+  }
+
+  fn rename_branch(&self, from: &str, to: &str) -> Result<(), Error> {
+    self.run_git_command(&["branch", "-m", END_OF_OPTIONS, from, to])?;
     Ok(())
   }
+
+  fn remote_default_branch(&self) -> Result<Option<String>, Error> {
+    let remote = default_remote(self)?;
+    // Fails quietly when there is no such remote or its HEAD was never set, which just means there's nothing to detect.
+    let res = self
+      .run_git_command(&["symbolic-ref", "--quiet", "--short", &format!("refs/remotes/{}/HEAD", remote)])
+      .unwrap_or_default()
+      .stdout;
+    Ok(res.trim().strip_prefix(&format!("{}/", remote)).map(String::from))
+  }
+
+  fn remotes(&self) -> Result<Vec<String>, Error> {
+    let res = self.run_git_command(&["remote"])?.stdout;
+    Ok(res.lines().map(|line| String::from(line.trim())).filter(|remote| !remote.is_empty()).collect())
+  }
+
+  fn remote_branches(&self) -> Result<Vec<GitBranch>, Error> {
+    let res = self
+      .run_git_command(&[
+        "for-each-ref",
+        "--format=%(refname)%00%(symref)%00%(objectname)%00%(committerdate:unix)",
+        "refs/remotes",
+      ])?
+      .stdout;
+    let branches = res
+      .lines()
+      .filter_map(|line| {
+        let [refname, symref, sha, time] = line.split('\0').collect::<Vec<&str>>()[..] else {
+          return None;
+        };
+        // `origin/HEAD` is a symbolic ref to the remote's default branch.
+        if !symref.is_empty() {
+          return None;
+        }
+        let mut branch = GitBranch::new(String::from(refname.strip_prefix("refs/remotes/")?));
+        branch.tip_sha = Some(String::from(sha));
+        branch.last_commit_time = time.parse().ok();
+        Some(branch)
+      })
+      .collect();
+    Ok(branches)
+  }
+
+  fn set_branch_description(&self, branch: &str, description: &str) -> Result<(), Error> {
+    let key = format!("branch.{}.description", branch);
+    if description.is_empty() {
+      // Exits with 5 when there was no description to remove, which is fine.
+      let _ = self.run_git_command(&["config", "--unset", &key]);
+      return Ok(());
+    }
+    self.run_git_command(&["config", &key, description])?;
+    Ok(())
+  }
+
+  fn git_config_get(&self, key: &str) -> Result<Option<String>, Error> {
+    let args = ["config", "--get", key];
+    let args_log_command = args.join(" ");
+    info!("Running `git {}`", args_log_command);
+    let output = self.git_command(&args).output();
+    // Exits with 1 when the key isn't set, anything else like a broken config file is a real failure.
+    if matches!(&output, Ok(output) if output.status.code() == Some(1)) {
+      return Ok(None);
+    }
+    let res = self.handle_output(args_log_command, output)?.stdout;
+    let value = res.trim();
+    Ok((!value.is_empty()).then(|| String::from(value)))
+  }
+
+  fn remote_url(&self, remote: &str) -> Result<Option<String>, Error> {
+    let res = self.run_git_command(&["remote", "get-url", remote]).map(|output| output.stdout).unwrap_or_default();
+    let url = res.trim();
+    Ok((!url.is_empty()).then(|| String::from(url)))
+  }
+
+  fn merged_branches(&self, base: &str) -> Result<Vec<String>, Error> {
+    let merged = format!("--merged={}", base);
+    let res = self.run_git_command(&["branch", "--format=%(refname:short)", &merged])?.stdout;
+    Ok(res.lines().map(|line| String::from(line.trim())).filter(|name| !name.is_empty()).collect())
+  }
+
+  fn branch_details(&self, branch: &GitBranch) -> Result<GitBranchDetails, Error> {
+    let full_ref = format!("refs/heads/{}", branch.name);
+    let res = self.run_git_command(&[
+      "for-each-ref",
+      "--format=%(refname)%00%(objectname)%00%(upstream:short)%00%(authorname)%00%(committerdate:unix)%00%(contents:subject)",
+      &full_ref,
+    ])?
+    .stdout;
+    // for-each-ref matches by prefix, so `feature` also lists `feature/x`.
+    let Some(fields) = res.lines().map(|line| line.split('\0').collect::<Vec<&str>>()).find(|f| f[0] == full_ref)
+    else {
+      return Err(Error::BranchNotFound(branch.name.clone()));
+    };
+    let [_, sha, upstream, author, time, subject] = fields[..] else {
+      return Err(Error::Git(format!("Unexpected branch details for {}", branch.name)));
+    };
+    let upstream = (!upstream.is_empty()).then(|| String::from(upstream));
+    let ahead_behind = match &upstream {
+      Some(upstream) => Some(self.ahead_behind(&branch.name, upstream)?),
+      None => None,
+    };
+    Ok(GitBranchDetails {
+      full_ref,
+      sha: String::from(sha),
+      upstream,
+      ahead_behind,
+      last_commit: GitCommit {
+        sha: String::from(sha),
+        author: String::from(author),
+        time: time.parse().unwrap_or_default(),
+        subject: String::from(subject),
+        note: None,
+      },
+    })
+  }
+
+  fn ahead_behind(&self, local: &str, upstream: &str) -> Result<(usize, usize), Error> {
+    let res = self
+      .run_git_command(&["rev-list", "--left-right", "--count", END_OF_OPTIONS, &format!("{}...{}", local, upstream)])?
+      .stdout;
+    let counts: Vec<usize> = res.split_whitespace().filter_map(|count| count.parse().ok()).collect();
+    let [ahead, behind] = counts[..] else {
+      return Err(Error::Git(format!("Unexpected rev-list output: {}", res.trim())));
+    };
+    Ok((ahead, behind))
+  }
+
+  fn commits_between(&self, base: &str, compare: &str) -> Result<Vec<GitCommit>, Error> {
+    let res = self.run_git_command(&["log", COMMIT_FORMAT, END_OF_OPTIONS, &format!("{}..{}", base, compare)])?.stdout;
+    Ok(parse_commits(&res))
+  }
+
+  fn count_commits(&self, range: &str) -> Result<usize, Error> {
+    let res = self.run_git_command(&["rev-list", "--count", END_OF_OPTIONS, range])?.stdout;
+    res.trim().parse().map_err(|_| Error::Git(format!("Unexpected rev-list output: {}", res.trim())))
+  }
+
+  fn diff_stat(&self, base: &str, compare: &str) -> Result<String, Error> {
+    let res = self
+      .run_git_command(&["diff", "--stat", "--no-color", END_OF_OPTIONS, &format!("{}...{}", base, compare)])?
+      .stdout;
+    Ok(res)
+  }
+
+  fn commit_log(&self, branch: &str, skip: usize, limit: usize, with_notes: bool) -> Result<Vec<GitCommit>, Error> {
+    let format = if with_notes { COMMIT_WITH_NOTES_FORMAT } else { COMMIT_FORMAT };
+    let res = self
+      .run_git_command(&[
+        "log",
+        format,
+        &format!("--skip={}", skip),
+        &format!("--max-count={}", limit),
+        END_OF_OPTIONS,
+        branch,
+      ])?
+      .stdout;
+    Ok(if with_notes { parse_commits_with_notes(&res) } else { parse_commits(&res) })
+  }
+
+  fn reflog(&self, limit: usize) -> Result<Vec<GitReflogEntry>, Error> {
+    let res = self
+      .run_git_command(&["reflog", "show", "--format=%H%x00%gs", &format!("--max-count={}", limit), "HEAD"])?
+      .stdout;
+    Ok(parse_reflog(&res))
+  }
+
+  fn cherry_pick(&self, sha: &str) -> Result<(), Error> {
+    match self.run_git_command(&["cherry-pick", END_OF_OPTIONS, sha]) {
+      // Checked through git's state rather than its output, which is translated.
+      Err(Error::CommandFailed { .. }) if self.git_path_exists("CHERRY_PICK_HEAD").unwrap_or(false) => {
+        Err(Error::CherryPickConflict(String::from(sha)))
+      },
+      res => res.map(|_| ()),
+    }
+  }
+
+  fn rebase(&self, onto: &str) -> Result<(), Error> {
+    match self.run_git_command(&["rebase", END_OF_OPTIONS, onto]) {
+      // Other failures, like local changes in the way, refuse before starting.
+      Err(Error::CommandFailed { .. }) if self.rebase_in_progress().unwrap_or(false) => {
+        Err(Error::RebaseConflict(String::from(onto)))
+      },
+      res => res.map(|_| ()),
+    }
+  }
+
+  fn rebase_in_progress(&self) -> Result<bool, Error> {
+    // Where git keeps the state of each kind of rebase.
+    for state in ["rebase-merge", "rebase-apply"] {
+      if self.git_path_exists(state)? {
+        return Ok(true);
+      }
+    }
+    Ok(false)
+  }
+
+  fn fetch(&self, cancel: &CancellationToken, _progress: &mut dyn FnMut(FetchProgress)) -> Result<(), Error> {
+    // Named rather than left to git, which would pick the checked out branch's remote.
+    self.run_cancellable_git_command(&["fetch", END_OF_OPTIONS, &default_remote(self)?], cancel)?;
+    Ok(())
+  }
+
+  fn prune_remote(&self, remote: &str, cancel: &CancellationToken) -> Result<Vec<String>, Error> {
+    // Compared before and after rather than parsed from git's output, which is translated.
+    let before = self.remote_tracking_branches(remote)?;
+    self.run_cancellable_git_command(&["remote", "prune", remote], cancel)?;
+    let after: HashSet<String> = self.remote_tracking_branches(remote)?.into_iter().collect();
+    Ok(before.into_iter().filter(|branch| !after.contains(branch)).collect())
+  }
+
+  fn push_renamed_branch(
+    &self,
+    branch: &str,
+    upstream: &GitRemoteBranch,
+    cancel: &CancellationToken,
+  ) -> Result<(), Error> {
+    let Some(remote) = upstream.remote.as_deref() else {
+      return Err(Error::Git(format!("{} isn't on a remote", upstream.name)));
+    };
+    let tracked = self.run_git_command(&["rev-parse", "--verify", &format!("refs/remotes/{}", upstream.name)])?.stdout;
+    // Only deleted while it's where it was last fetched, commits pushed there since would be lost unseen. Atomic so
+    // a refused deletion doesn't leave the new name pushed next to the old one.
+    let lease = format!("--force-with-lease=refs/heads/{}:{}", upstream.branch_name(), tracked.trim());
+    // Full refs, so neither side can be mistaken for a tag or another remote's branch.
+    let push = format!("refs/heads/{}:refs/heads/{}", branch, branch);
+    let delete = format!(":refs/heads/{}", upstream.branch_name());
+    let args = ["push", "--atomic", "--set-upstream", &lease, END_OF_OPTIONS, remote, &push, &delete];
+    match self.run_cancellable_git_command(&args, cancel) {
+      Err(Error::CommandFailed { stderr, .. }) if stderr.contains("stale info") => {
+        Err(Error::RemoteBranchMoved(upstream.name.clone()))
+      },
+      result => result.map(|_| ()),
+    }
+  }
+}
+
+/// Parse the output of `git branch --list -vv`, e.g.
+/// ```text
+/// * git-cli-repo 911ec26 [origin/git-cli-repo] Linting
+///   main         8fb5d9b [origin/main: ahead 1] Fix build
+///   stash-list   6442450 [origin/stash-list: gone] Formatting
+/// + worktree     dbcf785 Checked out in another worktree
+///   test         dbcf785 Updates
+/// ```
+/// A detached HEAD is listed as `* (HEAD detached at 911ec26)`, it isn't a branch so it is left out. Colors are
+/// requested off, but escape codes are stripped anyway in case a git config or wrapper forces them on.
+pub fn parse_branch_vv(output: &str) -> Vec<GitBranch> {
+  let output = strip_ansi_escapes::strip_str(output);
+  let re =
+    Regex::new(r"^(?<marker>[*+ ])\s+(?<name>\S+)\s+(?<sha>[A-Fa-f0-9]+)(\s+\[(?<upstream>[^:\]]+)(?<gone>: gone)?)?")
+      .unwrap();
+
+  output
+    .lines()
+    .filter(|line| !line.trim().is_empty())
+    .filter_map(|line| {
+      if line.get(2..).is_some_and(|rest| rest.starts_with('(')) {
+        return None;
+      }
+      let Some(captures) = re.captures(line) else {
+        error!("Failed to capture git branch information for: {}", line.trim());
+        return Some(fallback_branch(line));
+      };
+      Some(GitBranch {
+        name: String::from(&captures["name"]),
+        is_head: &captures["marker"] == "*",
+        tip_sha: Some(String::from(&captures["sha"])),
+        upstream: captures.name("upstream").map(|upstream| GitRemoteBranch::new(String::from(upstream.as_str()))),
+        upstream_gone: captures.name("gone").is_some(),
+        last_commit_time: None,
+        description: None,
+        parse_failed: false,
+      })
+    })
+    .collect()
+}
+
+/// A best guess for a line of `git branch -vv` that didn't parse, the name always comes first so take the first word
+/// after the marker rather than the whole line with its sha and subject.
+fn fallback_branch(line: &str) -> GitBranch {
+  let name = line.trim_start_matches(['*', '+']).split_whitespace().next().unwrap_or_default();
+  GitBranch { is_head: line.starts_with('*'), parse_failed: true, ..GitBranch::new(String::from(name)) }
+}
+
+/// Parse the output of `git config -z --get-regexp` for branch descriptions, each entry is the key and value separated
+/// by a newline, e.g. `branch.feature/x.description\nTidy up\0`. Descriptions can span lines, only the first is kept.
+fn parse_branch_descriptions(output: &str) -> HashMap<String, String> {
+  output
+    .split('\0')
+    .filter_map(|entry| {
+      let (key, value) = entry.split_once('\n')?;
+      let name = key.strip_prefix("branch.")?.strip_suffix(".description")?;
+      let description = value.lines().map(str::trim).find(|line| !line.is_empty())?;
+      Some((String::from(name), String::from(description)))
+    })
+    .collect()
 }
 
-fn run_git_command(args: &[&str]) -> Result<String, Error> {
-  let args_log_command = args.join(" ");
-  info!("Running `git {}`", args_log_command);
-  let res = Command::new("git").args(args).output();
-  if res.is_err() {
-    let err = res.err().unwrap();
-    error!("Failed to run `git {}`, error: {}", args_log_command, err);
-    return Err(Error::Git(format!("{}", err)));
+/// Parse reflog lines of a sha and subject separated by NUL, e.g. `<sha>\0checkout: moving from main to feature`.
+fn parse_reflog(output: &str) -> Vec<GitReflogEntry> {
+  output
+    .lines()
+    .filter_map(|line| {
+      let (sha, subject) = line.split_once('\0')?;
+      // Entries written by other tools don't always follow the `action: message` shape.
+      let (action, message) = subject.split_once(": ").unwrap_or((subject, ""));
+      Some(GitReflogEntry { sha: String::from(sha), action: String::from(action), message: String::from(message) })
+    })
+    .collect()
+}
+
+fn parse_commit(record: &str) -> Option<GitCommit> {
+  let mut fields = record.split('\0');
+  Some(GitCommit {
+    sha: String::from(fields.next()?),
+    author: String::from(fields.next()?),
+    time: fields.next()?.parse().unwrap_or_default(),
+    subject: String::from(fields.next().unwrap_or_default()),
+    note: fields.next().map(str::trim).filter(|note| !note.is_empty()).map(String::from),
+  })
+}
+
+fn parse_commits(output: &str) -> Vec<GitCommit> {
+  output.lines().filter_map(parse_commit).collect()
+}
+
+/// Parse output in [`COMMIT_WITH_NOTES_FORMAT`], where each commit ends in a record separator.
+fn parse_commits_with_notes(output: &str) -> Vec<GitCommit> {
+  output
+    .split('\x1e')
+    .map(|record| record.trim_start_matches('\n'))
+    .filter(|record| !record.is_empty())
+    .filter_map(parse_commit)
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::git::{git_repo::detect_default_branch, test_repo::TestRepo};
+
+  #[test]
+  fn parse_git_version_cases() {
+    let cases = vec![
+      ("git version 2.39.2\n", Some((2, 39))),
+      ("git version 2.39.3 (Apple Git-146)\n", Some((2, 39))),
+      ("git version 2.45.1.windows.1\n", Some((2, 45))),
+      ("git version 2.23.0\n", Some((2, 23))),
+      ("not git\n", None),
+    ];
+    for (output, expected) in cases {
+      assert_eq!(parse_git_version(output), expected, "{}", output);
+    }
+  }
+
+  #[test]
+  fn classify_failure_cases() {
+    let cases = vec![
+      (
+        "auth prompt refused",
+        "fatal: could not read Username for 'https://github.com': terminal prompts disabled\n",
+        Error::AuthenticationRequired(String::from("fetch")),
+      ),
+      (
+        "outside a repository",
+        "fatal: not a git repository (or any of the parent directories): .git\n",
+        Error::NotARepository,
+      ),
+      (
+        "local changes",
+        "error: Your local changes to the following files would be overwritten by checkout:\n\tfile.txt\n\
+         Please commit your changes or stash them before you switch branches.\nAborting\n",
+        Error::DirtyWorkingTree(String::from("fetch")),
+      ),
+      ("deleted branch", "error: branch 'topic' not found.\n", Error::BranchNotFound(String::from("topic"))),
+      ("anything else", "fatal: couldn't find remote ref nope\n", Error::CommandFailed {
+        args: String::from("fetch"),
+        stderr: String::from("fatal: couldn't find remote ref nope"),
+      }),
+    ];
+
+    for (description, stderr, expected) in cases {
+      let error = classify_failure(String::from("fetch"), String::from(stderr));
+      assert_eq!(error.to_string(), expected.to_string(), "{}", description);
+    }
+  }
+
+  #[test]
+  fn branch_names_that_look_like_options_are_used_as_names() {
+    let test_repo = TestRepo::new();
+    // Git won't create these with `git branch`, but other tools can.
+    test_repo.git(&["update-ref", "refs/heads/-D", "HEAD"]);
+    test_repo.git(&["update-ref", "refs/heads/--help", "HEAD"]);
+    test_repo.git(&["commit", "--allow-empty", "-m", "Only on main"]);
+    let repo = GitCliRepo::from_path(test_repo.path());
+
+    assert_eq!(repo.ahead_behind("main", "-D").unwrap(), (1, 0));
+    assert_eq!(repo.commit_log("--help", 0, 10, false).unwrap().len(), 1);
+    assert_eq!(repo.count_commits("-D..main").unwrap(), 1);
+
+    repo.checkout_branch_from_name("-D").unwrap();
+    assert_eq!(test_repo.current_branch(), "-D");
+    repo.checkout_branch_from_name("main").unwrap();
+
+    repo.delete_branch(&GitBranch::new(String::from("--help"))).unwrap();
+    assert_eq!(test_repo.git(&["for-each-ref", "--format=%(refname:short)", "refs/heads/"]), "-D\nmain\n");
   }
 
-  let output = res.unwrap();
-  let err = String::from_utf8(output.stderr)?;
-  if !output.status.success() && !err.is_empty() {
-    error!("Failed to run `git {}`, error: {}", args_log_command, err);
-    return Err(Error::Git(err));
+  fn branch(name: &str, sha: &str, is_head: bool, upstream: Option<&str>) -> GitBranch {
+    GitBranch {
+      name: String::from(name),
+      is_head,
+      tip_sha: Some(String::from(sha)),
+      upstream: upstream.map(|upstream| GitRemoteBranch::new(String::from(upstream))),
+      upstream_gone: false,
+      last_commit_time: None,
+      description: None,
+      parse_failed: false,
+    }
+  }
+
+  #[test]
+  fn parse_branch_vv_cases() {
+    let cases = vec![
+      ("head marker", "* main 8fb5d9b Fix build", vec![branch("main", "8fb5d9b", true, None)]),
+      ("no upstream", "  test dbcf785 Updates", vec![branch("test", "dbcf785", false, None)]),
+      ("upstream", "  main 8fb5d9b [origin/main] Fix build", vec![branch(
+        "main",
+        "8fb5d9b",
+        false,
+        Some("origin/main"),
+      )]),
+      ("upstream ahead", "* main 8fb5d9b [origin/main: ahead 1, behind 2] Fix build", vec![branch(
+        "main",
+        "8fb5d9b",
+        true,
+        Some("origin/main"),
+      )]),
+      ("gone upstream", "  stash-list 6442450 [origin/stash-list: gone] Formatting", vec![GitBranch {
+        upstream_gone: true,
+        ..branch("stash-list", "6442450", false, Some("origin/stash-list"))
+      }]),
+      ("worktree", "+ worktree dbcf785 Elsewhere", vec![branch("worktree", "dbcf785", false, None)]),
+      ("detached head", "* (HEAD detached at 911ec26) 911ec26 Linting", vec![]),
+      ("no branch", "* (no branch, rebasing main) 911ec26 Linting", vec![]),
+      ("names with slashes", "  feature/ui/list 911ec26 [origin/feature/ui/list] Linting", vec![branch(
+        "feature/ui/list",
+        "911ec26",
+        false,
+        Some("origin/feature/ui/list"),
+      )]),
+      (
+        "aligned columns",
+        "* git-cli-repo 911ec26 [origin/git-cli-repo] Linting\n  main         8fb5d9b Fix build\n",
+        vec![
+          branch("git-cli-repo", "911ec26", true, Some("origin/git-cli-repo")),
+          branch("main", "8fb5d9b", false, None),
+        ],
+      ),
+      (
+        "colored",
+        "* \x1b[32mmain\x1b[m 8fb5d9b [\x1b[34morigin/main\x1b[m] Fix build\n  \x1b[31mtest\x1b[m dbcf785 Updates",
+        vec![branch("main", "8fb5d9b", true, Some("origin/main")), branch("test", "dbcf785", false, None)],
+      ),
+      ("subject with brackets", "  main 8fb5d9b Fix [skip ci]", vec![branch("main", "8fb5d9b", false, None)]),
+      ("malformed sha", "  feature/x not-a-sha [origin/feature/x] Fix build", vec![GitBranch {
+        parse_failed: true,
+        ..GitBranch::new(String::from("feature/x"))
+      }]),
+      ("malformed head", "* main", vec![GitBranch {
+        is_head: true,
+        parse_failed: true,
+        ..GitBranch::new(String::from("main"))
+      }]),
+    ];
+
+    for (description, output, expected) in cases {
+      assert_eq!(parse_branch_vv(output), expected, "{}", description);
+    }
+  }
+
+  #[test]
+  fn parse_reflog_cases() {
+    let entry = |sha: &str, action: &str, message: &str| {
+      GitReflogEntry { sha: String::from(sha), action: String::from(action), message: String::from(message) }
+    };
+    let cases = vec![
+      ("checkout", "8fb5d9b\0checkout: moving from main to feature", vec![entry(
+        "8fb5d9b",
+        "checkout",
+        "moving from main to feature",
+      )]),
+      ("amend", "911ec26\0commit (amend): Fix build", vec![entry("911ec26", "commit (amend)", "Fix build")]),
+      ("rename", "dbcf785\0Branch: renamed", vec![entry("dbcf785", "Branch", "renamed")]),
+      ("unstructured", "dbcf785\0updated by script", vec![entry("dbcf785", "updated by script", "")]),
+      ("malformed", "dbcf785", vec![]),
+    ];
+
+    for (description, output, expected) in cases {
+      assert_eq!(parse_reflog(output), expected, "{}", description);
+    }
+  }
+
+  #[test]
+  fn commit_log_reads_multi_line_notes() {
+    let test_repo = TestRepo::new();
+    test_repo.git(&["commit", "--allow-empty", "-m", "Second"]);
+    test_repo.git(&["notes", "add", "-m", "CI passed", "-m", "Reviewed", "HEAD"]);
+    let repo = GitCliRepo::from_path(test_repo.path());
+
+    let notes: Vec<Option<String>> =
+      repo.commit_log("main", 0, 10, true).unwrap().into_iter().map(|commit| commit.note).collect();
+    assert_eq!(notes, vec![Some(String::from("CI passed\n\nReviewed")), None]);
+    let without_notes = repo.commit_log("main", 0, 10, false).unwrap();
+    assert!(without_notes.iter().all(|commit| commit.note.is_none()));
+  }
+
+  #[test]
+  fn detect_default_branch_reads_git_config() {
+    let test_repo = TestRepo::new();
+    test_repo.git(&["branch", "trunk"]);
+    let repo = GitCliRepo::from_path(test_repo.path());
+    assert_eq!(detect_default_branch(&repo, None).unwrap().as_deref(), Some("main"));
+
+    test_repo.git(&["config", "init.defaultBranch", "trunk"]);
+    assert_eq!(detect_default_branch(&repo, None).unwrap().as_deref(), Some("trunk"));
+
+    test_repo.git(&["remote", "add", "upstream", "https://example.com/repo.git"]);
+    test_repo.git(&["update-ref", "refs/remotes/upstream/release", "HEAD"]);
+    test_repo.git(&["symbolic-ref", "refs/remotes/upstream/HEAD", "refs/remotes/upstream/release"]);
+    test_repo.git(&["config", "checkout.defaultRemote", "upstream"]);
+    assert_eq!(detect_default_branch(&repo, None).unwrap().as_deref(), Some("release"));
+  }
+
+  #[test]
+  fn git_config_get_only_treats_a_missing_key_as_unset() {
+    let test_repo = TestRepo::new();
+    let repo = GitCliRepo::from_path(test_repo.path());
+    assert_eq!(repo.git_config_get("gbm.missing").unwrap(), None);
+
+    test_repo.git(&["config", "gbm.present", "value"]);
+    assert_eq!(repo.git_config_get("gbm.present").unwrap().as_deref(), Some("value"));
+
+    let config = test_repo.path().join(".git").join("config");
+    let mut contents = std::fs::read_to_string(&config).unwrap();
+    contents.push_str("[broken\n");
+    std::fs::write(&config, contents).unwrap();
+    assert!(repo.git_config_get("gbm.present").is_err());
+  }
+
+  #[test]
+  fn parse_branch_descriptions_cases() {
+    let entry = |name: &str, text: &str| (String::from(name), String::from(text));
+    let cases = vec![
+      ("single", "branch.main.description\nRelease line\0", vec![entry("main", "Release line")]),
+      ("multi line", "branch.feature/x.description\nTidy up\nand more\n\0", vec![entry("feature/x", "Tidy up")]),
+      ("dotted name", "branch.v1.2.description\nPatch\0", vec![entry("v1.2", "Patch")]),
+      ("blank", "branch.main.description\n\n\0", vec![]),
+      ("empty output", "", vec![]),
+    ];
+
+    for (description, output, expected) in cases {
+      assert_eq!(parse_branch_descriptions(output), HashMap::from_iter(expected), "{}", description);
+    }
+  }
+
+  #[test]
+  fn branch_descriptions_round_trip() {
+    let test_repo = TestRepo::new();
+    test_repo.git(&["branch", "feature"]);
+    let repo = GitCliRepo::from_path(test_repo.path());
+    let description_of =
+      |name: &str| repo.local_branches().unwrap().into_iter().find(|branch| branch.name == name).unwrap().description;
+
+    repo.set_branch_description("feature", "Try the new layout").unwrap();
+    assert_eq!(description_of("feature").as_deref(), Some("Try the new layout"));
+    assert_eq!(description_of("main"), None);
+    repo.set_branch_description("feature", "").unwrap();
+    assert_eq!(description_of("feature"), None);
+  }
+
+  #[test]
+  fn local_branches_reports_upstream_remote() {
+    let test_repo = TestRepo::new();
+    test_repo.git(&["remote", "add", "team/fork", "https://example.com/fork.git"]);
+    test_repo.git(&["update-ref", "refs/remotes/team/fork/feature/ui", "HEAD"]);
+    test_repo.git(&["branch", "feature"]);
+    test_repo.git(&["branch", "--set-upstream-to=team/fork/feature/ui", "feature"]);
+    let repo = GitCliRepo::from_path(test_repo.path());
+
+    let branches = repo.local_branches().unwrap();
+    let upstream = branches.iter().find(|branch| branch.name == "feature").unwrap().upstream.clone().unwrap();
+    assert_eq!(upstream.remote.as_deref(), Some("team/fork"));
+    assert_eq!(upstream.branch_name(), "feature/ui");
+  }
+
+  #[test]
+  fn local_branches_ignores_forced_color() {
+    let test_repo = TestRepo::new();
+    test_repo.git(&["config", "color.ui", "always"]);
+    test_repo.git(&["config", "color.branch", "always"]);
+    test_repo.git(&["branch", "feature"]);
+    let repo = GitCliRepo::from_path(test_repo.path());
+
+    let branches = repo.local_branches().unwrap();
+    let names: Vec<&str> = branches.iter().map(|branch| branch.name.as_str()).collect();
+    assert_eq!(names, vec!["feature", "main"]);
+    assert!(branches.iter().find(|branch| branch.name == "main").unwrap().is_head);
   }
-  let content = String::from_utf8(output.stdout)?;
-  info!("Received git cli reply:\n{}", content);
-  Ok(content)
 }
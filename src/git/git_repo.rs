@@ -1,13 +1,37 @@
+use tokio_util::sync::CancellationToken;
+
 use crate::error::Error;
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct GitRemoteBranch {
+  /// The full `remote/branch` name, or just the branch name when the upstream is another local branch.
   pub name: String,
+  /// The remote the branch is on, `None` for a local upstream.
+  pub remote: Option<String>,
 }
 
 impl GitRemoteBranch {
   pub fn new(name: String) -> Self {
-    GitRemoteBranch { name }
+    GitRemoteBranch { name, remote: None }
+  }
+
+  /// Work out which of `remotes` the branch is on. Remote names can contain `/` so the longest matching remote wins
+  /// rather than the first segment of the name.
+  pub fn with_remotes(name: String, remotes: &[String]) -> Self {
+    let remote = remotes
+      .iter()
+      .filter(|remote| name.strip_prefix(remote.as_str()).is_some_and(|rest| rest.starts_with('/')))
+      .max_by_key(|remote| remote.len())
+      .cloned();
+    GitRemoteBranch { name, remote }
+  }
+
+  /// The branch's name on its remote, without the remote in front.
+  pub fn branch_name(&self) -> &str {
+    match &self.remote {
+      Some(remote) => &self.name[remote.len() + 1..],
+      None => &self.name,
+    }
   }
 }
 
@@ -15,15 +39,116 @@ impl GitRemoteBranch {
 pub struct GitBranch {
   pub name: String,
   pub is_head: bool,
+  /// Full sha of the commit the branch points at, unknown for branches that were only just named.
+  pub tip_sha: Option<String>,
   pub upstream: Option<GitRemoteBranch>,
+  /// The upstream was deleted on the remote, usually because it was merged.
+  pub upstream_gone: bool,
+  /// Committer time of the tip commit as seconds since the unix epoch.
+  pub last_commit_time: Option<i64>,
+  /// What the branch is for, as noted with `git branch --edit-description`.
+  pub description: Option<String>,
+  /// Git's listing of the branch couldn't be understood, the name is a best guess and everything else is missing.
+  pub parse_failed: bool,
 }
 
 impl GitBranch {
   pub fn new(name: String) -> Self {
-    GitBranch { name, is_head: false, upstream: None }
+    GitBranch {
+      name,
+      is_head: false,
+      tip_sha: None,
+      upstream: None,
+      upstream_gone: false,
+      last_commit_time: None,
+      description: None,
+      parse_failed: false,
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GitCommit {
+  pub sha: String,
+  pub author: String,
+  /// Commit time as seconds since the unix epoch.
+  pub time: i64,
+  pub subject: String,
+  /// Text attached with `git notes`, only loaded when asked for.
+  pub note: Option<String>,
+}
+
+impl GitCommit {
+  pub fn short_sha(&self) -> &str {
+    short_sha(&self.sha)
   }
 }
 
+/// The abbreviated sha git shows by default.
+pub fn short_sha(sha: &str) -> &str {
+  &sha[..sha.len().min(7)]
+}
+
+/// A move of HEAD recorded in the reflog, e.g. a checkout, commit or reset.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GitReflogEntry {
+  /// Where HEAD pointed after the move.
+  pub sha: String,
+  /// What moved HEAD, e.g. `checkout` or `commit (amend)`.
+  pub action: String,
+  pub message: String,
+}
+
+impl GitReflogEntry {
+  pub fn short_sha(&self) -> &str {
+    short_sha(&self.sha)
+  }
+
+  /// The branch or sha a checkout moved HEAD away from, what `git checkout -` goes back to.
+  pub fn checkout_from(&self) -> Option<&str> {
+    if self.action != "checkout" {
+      return None;
+    }
+    let (from, _to) = self.message.strip_prefix("moving from ")?.split_once(" to ")?;
+    Some(from)
+  }
+}
+
+/// How far a fetch has got, as libgit2 reports it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FetchProgress {
+  pub received_objects: usize,
+  pub total_objects: usize,
+  pub received_bytes: usize,
+}
+
+impl FetchProgress {
+  /// e.g. `Fetching 120/450 objects, 1.5 MiB`.
+  pub fn label(&self) -> String {
+    let mebibytes = self.received_bytes as f64 / (1024.0 * 1024.0);
+    format!("Fetching {}/{} objects, {:.1} MiB", self.received_objects, self.total_objects, mebibytes)
+  }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GitBranchDetails {
+  pub full_ref: String,
+  pub sha: String,
+  pub upstream: Option<String>,
+  /// Commits (ahead, behind) relative to the upstream.
+  pub ahead_behind: Option<(usize, usize)>,
+  pub last_commit: GitCommit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchNameValidity {
+  Valid,
+  /// Rejected by git's ref name rules.
+  InvalidFormat,
+  /// A local branch with the name already exists.
+  AlreadyExists,
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct GitStash {
   pub index: usize,
@@ -37,12 +162,490 @@ impl GitStash {
   }
 }
 
-pub trait GitRepo {
+pub trait GitRepo: Send {
+  /// A separate handle on the same repository, for running long operations off the UI thread.
+  fn try_clone(&self) -> Result<Box<dyn GitRepo>, Error>;
+  fn is_bare(&self) -> Result<bool, Error>;
+  /// A clone made with `--depth`, which is missing older history.
+  fn is_shallow(&self) -> Result<bool, Error>;
   fn local_branches(&self) -> Result<Vec<GitBranch>, Error>;
   fn stashes(&mut self) -> Result<Vec<GitStash>, Error>;
   fn checkout_branch_from_name(&self, branch_name: &str) -> Result<(), Error>;
+  /// Checkout even when local changes would be overwritten, throwing them away. Only for after the user has seen an
+  /// [`Error::CheckoutConflict`] and confirmed.
+  fn force_checkout_branch_from_name(&self, branch_name: &str) -> Result<(), Error>;
+  /// Check out `revision` on no branch, detaching HEAD. Like a branch checkout, local changes in the way give a
+  /// [`Error::CheckoutConflict`].
+  fn checkout_detached(&self, revision: &str) -> Result<(), Error>;
   fn checkout_branch(&self, branch: &GitBranch) -> Result<(), Error>;
-  fn validate_branch_name(&self, name: &str) -> Result<bool, Error>;
+  /// Whether `.gitmodules` differs between `revision` and HEAD, meaning a checkout from `revision` added, removed or
+  /// moved submodules.
+  fn gitmodules_changed(&self, revision: &str) -> Result<bool, Error>;
+  /// Check every submodule out at the commit HEAD records for it, like `git submodule update --init --recursive`, stopping
+  /// with [`Error::Cancelled`] once `cancel` fires.
+  fn update_submodules(&self, cancel: &CancellationToken) -> Result<(), Error>;
+  fn validate_branch_name(&self, name: &str) -> Result<BranchNameValidity, Error>;
+  /// Create a branch at HEAD without checking it out.
   fn create_branch(&self, to_create: &GitBranch) -> Result<(), Error>;
+  /// Create a branch at `start_point`, a branch or sha, without checking it out.
+  fn create_branch_from(&self, to_create: &GitBranch, start_point: &str) -> Result<(), Error>;
+  /// Commit onto HEAD without changing anything, so a new branch gets a tip of its own.
+  fn commit_empty(&self, message: &str) -> Result<(), Error>;
   fn delete_branch(&self, to_delete: &GitBranch) -> Result<(), Error>;
+  /// Rename a local branch, its upstream and description go with it. The remote isn't touched.
+  fn rename_branch(&self, from: &str, to: &str) -> Result<(), Error>;
+  /// The branch the default remote's HEAD points at, e.g. `main` for `origin/HEAD -> origin/main`.
+  fn remote_default_branch(&self) -> Result<Option<String>, Error>;
+  /// The configured remotes, as `git remote` lists them.
+  fn remotes(&self) -> Result<Vec<String>, Error>;
+  /// Every remote tracking branch, named like `origin/feature`. A remote's `HEAD` only points at one of the others so
+  /// it's left out.
+  fn remote_branches(&self) -> Result<Vec<GitBranch>, Error>;
+  /// Create a local branch tracking `remote_branch`, named as it is on the remote, and check it out.
+  fn checkout_remote_branch(&self, remote_branch: &GitRemoteBranch) -> Result<(), Error>;
+  /// Note what `branch` is for in its `branch.<name>.description`, an empty description removes it.
+  fn set_branch_description(&self, branch: &str, description: &str) -> Result<(), Error>;
+  /// The value of a git config `key`, like `init.defaultBranch`, as `git config --get` reads it. `None` when unset.
+  fn git_config_get(&self, key: &str) -> Result<Option<String>, Error>;
+  /// The url `remote` fetches from, if there is such a remote.
+  fn remote_url(&self, remote: &str) -> Result<Option<String>, Error>;
+  fn merged_branches(&self, base: &str) -> Result<Vec<String>, Error>;
+  fn branch_details(&self, branch: &GitBranch) -> Result<GitBranchDetails, Error>;
+  fn ahead_behind(&self, local: &str, upstream: &str) -> Result<(usize, usize), Error>;
+  /// Commits reachable from `compare` but not `base`, newest first.
+  fn commits_between(&self, base: &str, compare: &str) -> Result<Vec<GitCommit>, Error>;
+  /// How many commits are in `range`, a revision like `feature` for everything reachable from it or `main..feature`
+  /// for just what isn't on `main`.
+  fn count_commits(&self, range: &str) -> Result<usize, Error>;
+  /// A `git diff --stat` style summary of what `compare` changed since it diverged from `base`.
+  fn diff_stat(&self, base: &str, compare: &str) -> Result<String, Error>;
+  /// Up to `limit` commits reachable from `branch`, newest first, leaving out the `skip` newest. Their notes are
+  /// loaded too when `with_notes` is set.
+  fn commit_log(&self, branch: &str, skip: usize, limit: usize, with_notes: bool) -> Result<Vec<GitCommit>, Error>;
+  /// Up to `limit` of HEAD's reflog entries, newest first.
+  fn reflog(&self, limit: usize) -> Result<Vec<GitReflogEntry>, Error>;
+  /// Apply the commit onto HEAD, leaving the conflicts in the worktree if it doesn't apply cleanly.
+  fn cherry_pick(&self, sha: &str) -> Result<(), Error>;
+  /// Replay HEAD's commits that `onto` doesn't have on top of it. One that doesn't apply cleanly leaves the rebase in
+  /// progress with its conflicts in the worktree, as [`Error::RebaseConflict`].
+  fn rebase(&self, onto: &str) -> Result<(), Error>;
+  /// Whether a rebase was stopped part way, for conflicts or by an interactive `edit`.
+  fn rebase_in_progress(&self) -> Result<bool, Error>;
+  /// Update remote tracking branches from the default remote, giving up with [`Error::Cancelled`] once `cancel` is.
+  /// `progress` is told how the transfer is going by backends that can tell, git's own output isn't parsed for it.
+  fn fetch(&self, cancel: &CancellationToken, progress: &mut dyn FnMut(FetchProgress)) -> Result<(), Error>;
+  /// Delete the remote tracking branches of `remote` that are gone from it, returning their names like
+  /// `origin/feature`. Gives up with [`Error::Cancelled`] once `cancel` is.
+  fn prune_remote(&self, remote: &str, cancel: &CancellationToken) -> Result<Vec<String>, Error>;
+  /// Bring `upstream`'s remote in line with a local rename to `branch`, pushing `branch` there under its own name to
+  /// track and deleting `upstream` from it. Gives up with [`Error::Cancelled`] once `cancel` is.
+  fn push_renamed_branch(
+    &self,
+    branch: &str,
+    upstream: &GitRemoteBranch,
+    cancel: &CancellationToken,
+  ) -> Result<(), Error>;
+}
+
+/// Resolve a partial name to a single branch name, like git does for abbreviated shas. An exact match always wins,
+/// otherwise the name has to be a unique prefix of one branch.
+pub fn resolve_branch_prefix<'a>(names: impl IntoIterator<Item = &'a str>, partial: &str) -> Result<&'a str, Error> {
+  let candidates: Vec<&str> = names.into_iter().filter(|name| name.starts_with(partial)).collect();
+  if let Some(exact) = candidates.iter().find(|name| **name == partial) {
+    return Ok(*exact);
+  }
+  match candidates[..] {
+    [single] => Ok(single),
+    [] => Err(Error::BranchNotFound(String::from(partial))),
+    _ => {
+      Err(Error::AmbiguousBranch(String::from(partial), candidates.iter().map(|name| String::from(*name)).collect()))
+    },
+  }
+}
+
+const CONVENTIONAL_DEFAULT_BRANCHES: [&str; 2] = ["main", "master"];
+const CONVENTIONAL_REMOTE: &str = "origin";
+
+/// The remote to use when the user hasn't named one, git's `checkout.defaultRemote` or else `origin`.
+pub fn default_remote(repo: &dyn GitRepo) -> Result<String, Error> {
+  Ok(repo.git_config_get("checkout.defaultRemote")?.unwrap_or_else(|| String::from(CONVENTIONAL_REMOTE)))
+}
+
+/// Work out the repository's default branch. A configured `default_base` wins, then what the default remote's HEAD
+/// points at, then the user's `init.defaultBranch` and finally a conventional default name, as long as there's a
+/// local branch by that name. Anything the user explicitly picked should be preferred over this by the caller.
+pub fn detect_default_branch(repo: &dyn GitRepo, configured: Option<&str>) -> Result<Option<String>, Error> {
+  if let Some(configured) = configured {
+    return Ok(Some(String::from(configured)));
+  }
+  if let Some(remote_default) = repo.remote_default_branch()? {
+    return Ok(Some(remote_default));
+  }
+  let local_branches = repo.local_branches()?;
+  let init_default = repo.git_config_get("init.defaultBranch")?;
+  let candidates = init_default.iter().map(String::as_str).chain(CONVENTIONAL_DEFAULT_BRANCHES);
+  let detected =
+    candidates.find(|candidate| local_branches.iter().any(|branch| branch.name == *candidate)).map(String::from);
+  Ok(detected)
+}
+
+/// Local branches already merged into the default branch, detected as [`detect_default_branch`] does from `configured`.
+/// The default branch is always "merged" into itself, but it's never one of them.
+pub fn merged_into_default_branch(repo: &dyn GitRepo, configured: Option<&str>) -> Result<Vec<String>, Error> {
+  let Some(default_branch) = detect_default_branch(repo, configured)? else {
+    return Ok(Vec::new());
+  };
+  let mut merged = repo.merged_branches(&default_branch)?;
+  merged.retain(|name| *name != default_branch);
+  Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::git::test_repo::{Backend, TestRepo};
+
+  #[test]
+  fn is_shallow_detects_a_depth_limited_clone() {
+    let source = TestRepo::new();
+    source.git(&["commit", "--allow-empty", "-m", "Second"]);
+    let shallow = TestRepo::shallow_clone(&source);
+
+    for backend in Backend::ALL {
+      assert!(shallow.open(backend).is_shallow().unwrap(), "{:?}", backend);
+      assert!(!source.open(backend).is_shallow().unwrap(), "{:?}", backend);
+    }
+  }
+
+  #[test]
+  fn validate_branch_name_rejects_existing_branch() {
+    let test_repo = TestRepo::new();
+    test_repo.git(&["branch", "feature"]);
+
+    for backend in Backend::ALL {
+      let repo = test_repo.open(backend);
+      assert_eq!(repo.validate_branch_name("feature").unwrap(), BranchNameValidity::AlreadyExists, "{:?}", backend);
+      assert_eq!(repo.validate_branch_name("feature-2").unwrap(), BranchNameValidity::Valid, "{:?}", backend);
+      assert_eq!(repo.validate_branch_name("bad..name").unwrap(), BranchNameValidity::InvalidFormat, "{:?}", backend);
+    }
+  }
+
+  fn branch_names(repo: &dyn GitRepo) -> Vec<String> {
+    let mut names: Vec<String> = repo.local_branches().unwrap().into_iter().map(|branch| branch.name).collect();
+    names.sort();
+    names
+  }
+
+  #[test]
+  fn local_branches_reports_head_and_upstream() {
+    let test_repo = TestRepo::new();
+    test_repo.git(&["branch", "feature"]);
+    test_repo.git(&["branch", "feature/nested"]);
+    test_repo.git(&["branch", "--set-upstream-to=main", "feature"]);
+
+    for backend in Backend::ALL {
+      let repo = test_repo.open(backend);
+      let branches = repo.local_branches().unwrap();
+      assert_eq!(branch_names(&*repo), vec!["feature", "feature/nested", "main"], "{:?}", backend);
+      let head: Vec<&str> =
+        branches.iter().filter(|branch| branch.is_head).map(|branch| branch.name.as_str()).collect();
+      assert_eq!(head, vec!["main"], "{:?}", backend);
+      let feature = branches.iter().find(|branch| branch.name == "feature").unwrap();
+      assert_eq!(feature.upstream.as_ref().map(|upstream| upstream.name.as_str()), Some("main"), "{:?}", backend);
+      assert!(feature.last_commit_time.is_some(), "{:?}", backend);
+      let nested = branches.iter().find(|branch| branch.name == "feature/nested").unwrap();
+      assert_eq!(nested.upstream, None, "{:?}", backend);
+    }
+  }
+
+  #[test]
+  fn create_checkout_and_delete_branch() {
+    for backend in Backend::ALL {
+      let test_repo = TestRepo::new();
+      let repo = test_repo.open(backend);
+
+      repo.create_branch(&GitBranch::new(String::from("topic"))).unwrap();
+      assert_eq!(branch_names(&*repo), vec!["main", "topic"], "{:?}", backend);
+      assert_eq!(test_repo.current_branch(), "main", "{:?}", backend);
+
+      repo.checkout_branch(&GitBranch::new(String::from("topic"))).unwrap();
+      assert_eq!(test_repo.current_branch(), "topic", "{:?}", backend);
+      repo.checkout_branch_from_name("main").unwrap();
+      assert_eq!(test_repo.current_branch(), "main", "{:?}", backend);
+
+      repo.delete_branch(&GitBranch::new(String::from("topic"))).unwrap();
+      assert_eq!(branch_names(&*repo), vec!["main"], "{:?}", backend);
+    }
+  }
+
+  #[test]
+  fn delete_branch_reports_checked_out_and_missing_branches() {
+    let test_repo = TestRepo::new();
+
+    for backend in Backend::ALL {
+      let repo = test_repo.open(backend);
+      let result = repo.delete_branch(&GitBranch::new(String::from("main")));
+      assert!(
+        matches!(result, Err(Error::DeleteCheckedOutBranch(ref branch)) if branch == "main"),
+        "{:?}: {:?}",
+        backend,
+        result
+      );
+      assert_eq!(branch_names(&*repo), vec!["main"], "{:?}", backend);
+
+      let result = repo.delete_branch(&GitBranch::new(String::from("missing")));
+      assert!(
+        matches!(result, Err(Error::BranchNotFound(ref branch)) if branch == "missing"),
+        "{:?}: {:?}",
+        backend,
+        result
+      );
+    }
+  }
+
+  #[test]
+  fn force_checkout_discards_local_changes() {
+    for backend in Backend::ALL {
+      let test_repo = TestRepo::new();
+      let file = test_repo.uncommitted_change_topic_would_overwrite();
+      let repo = test_repo.open(backend);
+
+      repo.force_checkout_branch_from_name("topic").unwrap();
+
+      assert_eq!(test_repo.current_branch(), "topic", "{:?}", backend);
+      assert_eq!(std::fs::read_to_string(&file).unwrap(), "topic\n", "{:?}", backend);
+      assert_eq!(test_repo.git(&["status", "--porcelain"]), "", "{:?}", backend);
+    }
+  }
+
+  #[test]
+  fn checkout_refuses_to_overwrite_local_changes() {
+    for backend in Backend::ALL {
+      let test_repo = TestRepo::new();
+      let file = test_repo.uncommitted_change_topic_would_overwrite();
+      let repo = test_repo.open(backend);
+
+      let result = repo.checkout_branch_from_name("topic");
+
+      assert!(
+        matches!(result, Err(Error::CheckoutConflict(ref branch)) if branch == "topic"),
+        "{:?}: {:?}",
+        backend,
+        result
+      );
+      assert_eq!(test_repo.current_branch(), "main", "{:?}", backend);
+      assert_eq!(std::fs::read_to_string(&file).unwrap(), "uncommitted\n", "{:?}", backend);
+      assert_eq!(test_repo.git(&["status", "--porcelain"]), " M notes.txt\n", "{:?}", backend);
+    }
+  }
+
+  #[test]
+  fn checkout_detached_leaves_head_on_no_branch() {
+    for backend in Backend::ALL {
+      let test_repo = TestRepo::new();
+      let first = test_repo.git(&["rev-parse", "HEAD"]);
+      test_repo.git(&["commit", "--allow-empty", "-m", "Second"]);
+      let repo = test_repo.open(backend);
+
+      repo.checkout_detached(first.trim()).unwrap();
+
+      assert_eq!(test_repo.current_branch(), "", "{:?}", backend);
+      assert_eq!(test_repo.git(&["rev-parse", "HEAD"]), first, "{:?}", backend);
+    }
+  }
+
+  /// A clone of a repository with `old` on it, tracking it locally.
+  fn tracking_old(origin: &TestRepo) -> TestRepo {
+    origin.git(&["branch", "old"]);
+    let test_repo = TestRepo::with_origin(origin);
+    test_repo.git(&["branch", "--track", "old", "origin/old"]);
+    test_repo
+  }
+
+  fn old_upstream() -> GitRemoteBranch {
+    GitRemoteBranch::with_remotes(String::from("origin/old"), &[String::from("origin")])
+  }
+
+  #[test]
+  fn rename_branch_keeps_its_upstream_and_description() {
+    for backend in Backend::ALL {
+      let origin = TestRepo::new();
+      let test_repo = tracking_old(&origin);
+      test_repo.git(&["config", "branch.old.description", "Old work"]);
+      let repo = test_repo.open(backend);
+
+      repo.rename_branch("old", "new").unwrap();
+
+      let new = repo.local_branches().unwrap().into_iter().find(|branch| branch.name == "new").unwrap();
+      assert_eq!(new.upstream.map(|upstream| upstream.name), Some(String::from("origin/old")), "{:?}", backend);
+      assert_eq!(new.description.as_deref(), Some("Old work"), "{:?}", backend);
+      assert!(repo.rename_branch("old", "other").is_err(), "{:?}", backend);
+    }
+  }
+
+  #[test]
+  fn push_renamed_branch_moves_the_branch_on_the_remote() {
+    for backend in Backend::ALL {
+      let origin = TestRepo::new();
+      let test_repo = tracking_old(&origin);
+      let repo = test_repo.open(backend);
+
+      repo.rename_branch("old", "new").unwrap();
+      repo.push_renamed_branch("new", &old_upstream(), &CancellationToken::new()).unwrap();
+
+      assert_eq!(origin.git(&["branch", "--format=%(refname:short)"]), "main\nnew\n", "{:?}", backend);
+      assert_eq!(test_repo.git(&["rev-parse", "--abbrev-ref", "new@{upstream}"]), "origin/new\n", "{:?}", backend);
+    }
+  }
+
+  #[test]
+  fn push_renamed_branch_keeps_commits_pushed_since_the_last_fetch() {
+    for backend in Backend::ALL {
+      let origin = TestRepo::new();
+      let test_repo = tracking_old(&origin);
+      origin.git(&["checkout", "old"]);
+      origin.git(&["commit", "--allow-empty", "-m", "Pushed by someone else"]);
+      origin.git(&["checkout", "main"]);
+      let repo = test_repo.open(backend);
+
+      repo.rename_branch("old", "new").unwrap();
+      let result = repo.push_renamed_branch("new", &old_upstream(), &CancellationToken::new());
+
+      assert!(matches!(result, Err(Error::RemoteBranchMoved(ref name)) if name == "origin/old"), "{:?}", result);
+      assert_eq!(origin.git(&["branch", "--format=%(refname:short)"]), "main\nold\n", "{:?}", backend);
+    }
+  }
+
+  #[test]
+  fn resolve_branch_prefix_prefers_an_exact_match_then_a_unique_prefix() {
+    let names = ["feature/login", "feature/logout", "fix", "fix-typo", "main"];
+
+    assert_eq!(resolve_branch_prefix(names, "fix").unwrap(), "fix");
+    assert_eq!(resolve_branch_prefix(names, "feature/logi").unwrap(), "feature/login");
+    assert_eq!(
+      resolve_branch_prefix(names, "feature/log").unwrap_err().to_string(),
+      "feature/log is ambiguous, it matches feature/login, feature/logout"
+    );
+    assert!(matches!(resolve_branch_prefix(names, "dev"), Err(Error::BranchNotFound(name)) if name == "dev"));
+  }
+
+  #[test]
+  fn prune_remote_reports_pruned_branches() {
+    for backend in Backend::ALL {
+      let upstream = TestRepo::new();
+      upstream.git(&["branch", "merged"]);
+      upstream.git(&["branch", "active"]);
+      let test_repo = TestRepo::with_origin(&upstream);
+      upstream.git(&["branch", "-D", "merged"]);
+      let repo = test_repo.open(backend);
+
+      let cancel = CancellationToken::new();
+      assert_eq!(repo.prune_remote("origin", &cancel).unwrap(), vec!["origin/merged"], "{:?}", backend);
+      assert_eq!(repo.prune_remote("origin", &cancel).unwrap(), Vec::<String>::new(), "{:?}", backend);
+    }
+  }
+
+  #[test]
+  fn gitmodules_changed_compares_with_head() {
+    let test_repo = TestRepo::new();
+    let without_submodules = test_repo.git(&["rev-parse", "HEAD"]);
+    std::fs::write(test_repo.path().join(".gitmodules"), "[submodule \"lib\"]\n\tpath = lib\n\turl = ../lib\n")
+      .unwrap();
+    test_repo.git(&["add", ".gitmodules"]);
+    test_repo.git(&["commit", "-m", "Add lib"]);
+    let with_submodules = test_repo.git(&["rev-parse", "HEAD"]);
+    test_repo.git(&["commit", "--allow-empty", "-m", "Unrelated"]);
+
+    for backend in Backend::ALL {
+      let repo = test_repo.open(backend);
+      assert!(repo.gitmodules_changed(without_submodules.trim()).unwrap(), "{:?}", backend);
+      assert!(!repo.gitmodules_changed(with_submodules.trim()).unwrap(), "{:?}", backend);
+    }
+  }
+
+  #[test]
+  fn count_commits_counts_whole_history_or_range() {
+    let test_repo = TestRepo::new();
+    test_repo.git(&["checkout", "-b", "feature"]);
+    test_repo.git(&["commit", "--allow-empty", "-m", "One"]);
+    test_repo.git(&["commit", "--allow-empty", "-m", "Two"]);
+
+    for backend in Backend::ALL {
+      let repo = test_repo.open(backend);
+      assert_eq!(repo.count_commits("feature").unwrap(), 3, "{:?}", backend);
+      assert_eq!(repo.count_commits("main..feature").unwrap(), 2, "{:?}", backend);
+      assert_eq!(repo.count_commits("feature..main").unwrap(), 0, "{:?}", backend);
+    }
+  }
+
+  #[test]
+  fn rebase_replays_commits_onto_the_branch() {
+    for backend in Backend::ALL {
+      let test_repo = TestRepo::new();
+      test_repo.feature_behind_main(None);
+      let repo = test_repo.open(backend);
+
+      repo.rebase("main").unwrap();
+
+      assert_eq!(test_repo.current_branch(), "feature", "{:?}", backend);
+      let main = test_repo.git(&["rev-parse", "main"]);
+      assert_eq!(test_repo.git(&["merge-base", "main", "feature"]), main, "{:?}", backend);
+      assert_eq!(test_repo.git(&["log", "--format=%s", "-1"]), "Feature\n", "{:?}", backend);
+      assert!(!repo.rebase_in_progress().unwrap(), "{:?}", backend);
+    }
+  }
+
+  #[test]
+  fn rebase_stops_on_conflicts_and_leaves_the_rebase_in_progress() {
+    for backend in Backend::ALL {
+      let test_repo = TestRepo::new();
+      test_repo.feature_behind_main(Some("main\n"));
+      let repo = test_repo.open(backend);
+
+      let result = repo.rebase("main");
+
+      assert!(matches!(result, Err(Error::RebaseConflict(ref onto)) if onto == "main"), "{:?}: {:?}", backend, result);
+      assert!(repo.rebase_in_progress().unwrap(), "{:?}", backend);
+    }
+  }
+
+  #[test]
+  fn fetch_uses_the_default_remote_over_the_upstream() {
+    for backend in Backend::ALL {
+      let origin = TestRepo::new();
+      let fork = TestRepo::new();
+      let test_repo = TestRepo::with_origin(&origin);
+      test_repo.git(&["remote", "add", "fork", &fork.path().display().to_string()]);
+      // A bare `git fetch` would go to the checked out branch's remote instead.
+      test_repo.git(&["config", "branch.main.remote", "fork"]);
+      test_repo.git(&["config", "branch.main.merge", "refs/heads/main"]);
+      origin.git(&["branch", "fetched"]);
+      let repo = test_repo.open(backend);
+
+      repo.fetch(&CancellationToken::new(), &mut |_| {}).unwrap();
+
+      assert!(test_repo.git(&["branch", "--remotes"]).contains("origin/fetched"), "{:?}", backend);
+    }
+  }
+
+  #[test]
+  fn cherry_pick_reports_conflicts() {
+    for backend in Backend::ALL {
+      let test_repo = TestRepo::new();
+      test_repo.feature_behind_main(Some("main\n"));
+      let sha = test_repo.git(&["rev-parse", "main"]).trim().to_string();
+      let repo = test_repo.open(backend);
+
+      let result = repo.cherry_pick(&sha);
+
+      assert!(
+        matches!(result, Err(Error::CherryPickConflict(ref picked)) if *picked == sha),
+        "{:?}: {:?}",
+        backend,
+        result
+      );
+    }
+  }
 }
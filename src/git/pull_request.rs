@@ -0,0 +1,193 @@
+use std::{
+  io::Write,
+  process::{Command, Stdio},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  error::Error,
+  git::web_url::{WebHost, WebRepo},
+};
+
+// Seconds before giving up on the API, the lookup only ever adds a line to the details popup.
+const REQUEST_TIMEOUT: &str = "10";
+
+/// An open pull request from a branch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PullRequest {
+  pub number: u64,
+  pub title: String,
+  pub url: String,
+}
+
+/// Finds a branch's open pull request on its code review site. Lookups go over the network, so they're made off the UI
+/// thread.
+pub trait PullRequestLookup: Send {
+  /// Whether lookups can find anything, there's no point starting one otherwise.
+  fn is_enabled(&self) -> bool {
+    true
+  }
+  /// The open pull request from `branch`, its name on the remote.
+  fn open_pull_request(&self, branch: &str) -> Result<Option<PullRequest>, Error>;
+}
+
+/// The lookup for `repo`, or one that never finds anything when there's no token or the site isn't supported.
+pub fn pull_request_lookup(repo: Option<&WebRepo>, github_token: Option<&str>) -> Box<dyn PullRequestLookup> {
+  let lookup = repo.zip(github_token).and_then(|(repo, token)| GitHubPullRequests::new(repo, String::from(token)));
+  match lookup {
+    Some(lookup) => Box::new(lookup),
+    None => Box::new(NoPullRequests),
+  }
+}
+
+/// Used when pull requests aren't configured.
+pub struct NoPullRequests;
+
+impl PullRequestLookup for NoPullRequests {
+  fn is_enabled(&self) -> bool {
+    false
+  }
+
+  fn open_pull_request(&self, _branch: &str) -> Result<Option<PullRequest>, Error> {
+    Ok(None)
+  }
+}
+
+/// Looks pull requests up with the GitHub REST API through curl, rather than building in an HTTP client.
+pub struct GitHubPullRequests {
+  /// e.g. `https://api.github.com/repos/owner/repo`.
+  api_url: String,
+  owner: String,
+  token: String,
+}
+
+impl GitHubPullRequests {
+  /// A lookup for `repo` if it's on github.com or GitHub Enterprise.
+  pub fn new(repo: &WebRepo, token: String) -> Option<Self> {
+    if repo.host != WebHost::GitHub {
+      return None;
+    }
+    let (site, path) = repo.base_url.strip_prefix("https://")?.split_once('/')?;
+    let (owner, _name) = path.split_once('/')?;
+    let api_url = match site {
+      "github.com" => format!("https://api.github.com/repos/{}", path),
+      _ => format!("https://{}/api/v3/repos/{}", site, path),
+    };
+    Some(GitHubPullRequests { api_url, owner: String::from(owner), token })
+  }
+}
+
+impl PullRequestLookup for GitHubPullRequests {
+  fn open_pull_request(&self, branch: &str) -> Result<Option<PullRequest>, Error> {
+    let url =
+      format!("{}/pulls?state=open&head={}", self.api_url, encode_query_value(&format!("{}:{}", self.owner, branch)));
+    // The token is passed as a header on stdin so it doesn't show up in the process list.
+    let mut child = Command::new("curl")
+      .args(["--silent", "--show-error", "--fail", "--max-time", REQUEST_TIMEOUT, "--header", "@-"])
+      .args(["--header", "Accept: application/vnd.github+json", &url])
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()
+      .map_err(|err| Error::PullRequestLookup(format!("Failed to run curl: {}", err)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+      writeln!(stdin, "Authorization: Bearer {}", self.token)
+        .map_err(|err| Error::PullRequestLookup(format!("Failed to pass the token to curl: {}", err)))?;
+    }
+    let output =
+      child.wait_with_output().map_err(|err| Error::PullRequestLookup(format!("curl didn't finish: {}", err)))?;
+    if !output.status.success() {
+      return Err(Error::PullRequestLookup(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    parse_pull_requests(&String::from_utf8_lossy(&output.stdout))
+  }
+}
+
+#[derive(Deserialize)]
+struct ApiPullRequest {
+  number: u64,
+  title: String,
+  html_url: String,
+}
+
+/// The first pull request in a GitHub API list of them.
+fn parse_pull_requests(json: &str) -> Result<Option<PullRequest>, Error> {
+  let pull_requests: Vec<ApiPullRequest> =
+    serde_json::from_str(json).map_err(|err| Error::PullRequestLookup(format!("Unexpected response: {}", err)))?;
+  Ok(pull_requests.into_iter().next().map(|pull_request| {
+    PullRequest { number: pull_request.number, title: pull_request.title, url: pull_request.html_url }
+  }))
+}
+
+/// Percent encode everything but the characters that are always safe in a query value, branch names can hold `&`, `#`
+/// or `+`.
+fn encode_query_value(value: &str) -> String {
+  value
+    .bytes()
+    .map(|byte| {
+      match byte {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' | b':' => String::from(byte as char),
+        _ => format!("%{:02X}", byte),
+      }
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn parse_pull_requests_cases() {
+    let cases = vec![
+      ("none open", "[]", None),
+      (
+        "first of several",
+        r#"[{"number": 12, "title": "Add sorting", "html_url": "https://github.com/o/r/pull/12", "draft": false},
+            {"number": 9, "title": "Older", "html_url": "https://github.com/o/r/pull/9"}]"#,
+        Some(PullRequest {
+          number: 12,
+          title: String::from("Add sorting"),
+          url: String::from("https://github.com/o/r/pull/12"),
+        }),
+      ),
+    ];
+
+    for (description, json, expected) in cases {
+      assert_eq!(parse_pull_requests(json).unwrap(), expected, "{}", description);
+    }
+    assert!(parse_pull_requests(r#"{"message": "Bad credentials"}"#).is_err());
+  }
+
+  #[test]
+  fn encode_query_value_keeps_safe_characters() {
+    assert_eq!(encode_query_value("owner:feature/a-b_c.d"), "owner:feature/a-b_c.d");
+    assert_eq!(encode_query_value("owner:fix#1&2+3 é"), "owner:fix%231%262%2B3%20%C3%A9");
+  }
+
+  #[test]
+  fn github_api_url_cases() {
+    let repo = |host: WebHost, base_url: &str| WebRepo { host, base_url: String::from(base_url) };
+    let api_url = |repo: &WebRepo| GitHubPullRequests::new(repo, String::new()).map(|lookup| lookup.api_url);
+    let cases = vec![
+      (
+        "github.com",
+        repo(WebHost::GitHub, "https://github.com/owner/repo"),
+        Some("https://api.github.com/repos/owner/repo"),
+      ),
+      (
+        "enterprise",
+        repo(WebHost::GitHub, "https://git.example.com/team/repo"),
+        Some("https://git.example.com/api/v3/repos/team/repo"),
+      ),
+      ("gitlab", repo(WebHost::GitLab, "https://gitlab.com/group/repo"), None),
+    ];
+
+    for (description, web_repo, expected) in cases {
+      assert_eq!(api_url(&web_repo).as_deref(), expected, "{}", description);
+    }
+  }
+}
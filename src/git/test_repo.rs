@@ -0,0 +1,113 @@
+use std::{
+  path::{Path, PathBuf},
+  process::Command,
+};
+
+use tempfile::TempDir;
+
+use crate::git::{git2_repo::Git2Repo, git_cli_repo::GitCliRepo, git_repo::GitRepo};
+
+/// The [`GitRepo`] implementations, so behaviour the trait promises is tested once for all of them.
+#[derive(Debug, Clone, Copy)]
+pub enum Backend {
+  Git2,
+  Cli,
+}
+
+impl Backend {
+  pub const ALL: [Backend; 2] = [Backend::Git2, Backend::Cli];
+}
+
+/// A throwaway repository with an initial commit on `main`, deleted when dropped.
+pub struct TestRepo {
+  dir: TempDir,
+}
+
+impl TestRepo {
+  pub fn new() -> Self {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let repo = TestRepo { dir };
+    repo.git(&["init", "--initial-branch=main"]);
+    repo.git(&["commit", "--allow-empty", "-m", "Initial commit"]);
+    repo
+  }
+
+  /// A repository with `origin` as its `origin` remote, already fetched from.
+  pub fn with_origin(origin: &TestRepo) -> Self {
+    let repo = TestRepo::new();
+    repo.git(&["remote", "add", "origin", &origin.path().display().to_string()]);
+    repo.git(&["fetch", "origin"]);
+    repo
+  }
+
+  /// A `git clone --depth 1` of `source`, which only has its latest commit. Local clones ignore `--depth` unless
+  /// they go through a `file://` URL.
+  pub fn shallow_clone(source: &TestRepo) -> Self {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let repo = TestRepo { dir };
+    repo.git(&["clone", "--depth", "1", &format!("file://{}", source.path().display()), "."]);
+    repo
+  }
+
+  /// The repository opened with `backend`.
+  pub fn open(&self, backend: Backend) -> Box<dyn GitRepo> {
+    match backend {
+      Backend::Git2 => Box::new(Git2Repo::from_path(self.path()).expect("Failed to open with git2")),
+      Backend::Cli => Box::new(GitCliRepo::from_path(self.path())),
+    }
+  }
+
+  pub fn path(&self) -> &Path {
+    self.dir.path()
+  }
+
+  /// The name of the checked out branch.
+  pub fn current_branch(&self) -> String {
+    String::from(self.git(&["branch", "--show-current"]).trim())
+  }
+
+  /// Leave `main` checked out with an uncommitted change to a file that `topic` changes too, returning the file.
+  pub fn uncommitted_change_topic_would_overwrite(&self) -> PathBuf {
+    let file = self.path().join("notes.txt");
+    std::fs::write(&file, "main\n").expect("Failed to write notes.txt");
+    self.git(&["add", "notes.txt"]);
+    self.git(&["commit", "-m", "Add notes"]);
+    self.git(&["checkout", "-b", "topic"]);
+    std::fs::write(&file, "topic\n").expect("Failed to write notes.txt");
+    self.git(&["commit", "-am", "Change notes"]);
+    self.git(&["checkout", "main"]);
+    std::fs::write(&file, "uncommitted\n").expect("Failed to write notes.txt");
+    file
+  }
+
+  /// Put `feature` a commit changing `file.txt` ahead of where `main` was, then move `main` on and check `feature` out.
+  /// When `main_change` is given `main` writes it to `file.txt`, so rebasing `feature` onto it conflicts. Rebasing
+  /// commits locally needs an identity outside of [`TestRepo::git`], so one is configured.
+  pub fn feature_behind_main(&self, main_change: Option<&str>) {
+    self.git(&["config", "user.name", "Test"]);
+    self.git(&["config", "user.email", "test@example.com"]);
+    self.git(&["checkout", "-b", "feature"]);
+    std::fs::write(self.path().join("file.txt"), "feature\n").expect("Failed to write file.txt");
+    self.git(&["add", "file.txt"]);
+    self.git(&["commit", "-m", "Feature"]);
+    self.git(&["checkout", "main"]);
+    if let Some(contents) = main_change {
+      std::fs::write(self.path().join("file.txt"), contents).expect("Failed to write file.txt");
+      self.git(&["add", "file.txt"]);
+    }
+    self.git(&["commit", "--allow-empty", "-m", "Main moved"]);
+    self.git(&["checkout", "feature"]);
+  }
+
+  /// Run git in the repo with a fixed identity, panicking if it fails.
+  pub fn git(&self, args: &[&str]) -> String {
+    let output = Command::new("git")
+      .args(["-c", "user.name=Test", "-c", "user.email=test@example.com", "-c", "commit.gpgsign=false"])
+      .args(args)
+      .current_dir(self.path())
+      .output()
+      .expect("Failed to run git");
+    assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).expect("git output was not utf8")
+  }
+}
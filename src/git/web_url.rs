@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+/// The code review sites we know the URL shapes of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebHost {
+  GitHub,
+  GitLab,
+}
+
+impl WebHost {
+  fn from_name(name: &str) -> Option<Self> {
+    match name.to_ascii_lowercase().as_str() {
+      "github" => Some(WebHost::GitHub),
+      "gitlab" => Some(WebHost::GitLab),
+      _ => None,
+    }
+  }
+}
+
+/// A repository on a code review site, derived from a remote's url.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebRepo {
+  pub host: WebHost,
+  /// The repository's home page, e.g. `https://github.com/owner/repo`.
+  pub base_url: String,
+}
+
+impl WebRepo {
+  /// Work out the web address from a remote url in any of the forms git accepts, e.g. `git@github.com:owner/repo.git`,
+  /// `ssh://git@github.com/owner/repo` or `https://github.com/owner/repo.git`. `hosts` maps extra hostnames, like a
+  /// self hosted GitLab, to `github` or `gitlab`.
+  pub fn from_remote_url(remote_url: &str, hosts: &HashMap<String, String>) -> Option<Self> {
+    let remote_url = remote_url.trim();
+    let without_scheme = match remote_url.split_once("://") {
+      Some((_scheme, rest)) => rest,
+      // scp-like syntax, `user@host:path`.
+      None => remote_url,
+    };
+    let without_user = without_scheme.rsplit_once('@').map_or(without_scheme, |(_user, rest)| rest);
+    let (host, path) = without_user.split_once([':', '/'])?;
+    // A port only turns up in the url form, after the host.
+    let path = match path.split_once('/') {
+      Some((port, rest)) if port.chars().all(|c| c.is_ascii_digit()) => rest,
+      _ => path,
+    };
+    let path = path.trim_matches('/').trim_end_matches(".git");
+    if path.is_empty() {
+      return None;
+    }
+    let web_host = match hosts.get(host) {
+      Some(name) => WebHost::from_name(name)?,
+      None if host == "github.com" => WebHost::GitHub,
+      None if host == "gitlab.com" => WebHost::GitLab,
+      None => return None,
+    };
+    Some(WebRepo { host: web_host, base_url: format!("https://{}/{}", host, path) })
+  }
+
+  /// The page listing `branch`'s files.
+  pub fn branch_url(&self, branch: &str) -> String {
+    match self.host {
+      WebHost::GitHub => format!("{}/tree/{}", self.base_url, encode_path(branch)),
+      WebHost::GitLab => format!("{}/-/tree/{}", self.base_url, encode_path(branch)),
+    }
+  }
+
+  /// The page for opening a pull or merge request from `branch`.
+  pub fn new_pull_request_url(&self, branch: &str) -> String {
+    match self.host {
+      WebHost::GitHub => format!("{}/compare/{}?expand=1", self.base_url, encode_path(branch)),
+      WebHost::GitLab => {
+        format!("{}/-/merge_requests/new?merge_request%5Bsource_branch%5D={}", self.base_url, encode_component(branch))
+      },
+    }
+  }
+}
+
+/// Percent-encode everything but the characters a url never needs escaped, so `#`, `?`, `%` and spaces in a branch
+/// name stay part of it.
+fn encode_component(text: &str) -> String {
+  let mut encoded = String::new();
+  for byte in text.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(char::from(byte)),
+      _ => encoded.push_str(&format!("%{:02X}", byte)),
+    }
+  }
+  encoded
+}
+
+/// Percent-encode each segment of a branch name, keeping the slashes between them.
+fn encode_path(branch: &str) -> String {
+  branch.split('/').map(encode_component).collect::<Vec<_>>().join("/")
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn from_remote_url_cases() {
+    let hosts = HashMap::from([(String::from("git.example.com"), String::from("gitlab"))]);
+    let github = |base_url: &str| Some(WebRepo { host: WebHost::GitHub, base_url: String::from(base_url) });
+    let gitlab = |base_url: &str| Some(WebRepo { host: WebHost::GitLab, base_url: String::from(base_url) });
+    let cases = vec![
+      ("scp ssh", "git@github.com:owner/repo.git", github("https://github.com/owner/repo")),
+      ("ssh url", "ssh://git@github.com/owner/repo", github("https://github.com/owner/repo")),
+      ("https", "https://github.com/owner/repo.git", github("https://github.com/owner/repo")),
+      ("https with user", "https://me@github.com/owner/repo", github("https://github.com/owner/repo")),
+      ("gitlab subgroup", "git@gitlab.com:group/sub/repo.git", gitlab("https://gitlab.com/group/sub/repo")),
+      ("configured host", "ssh://git@git.example.com:2222/team/repo.git", gitlab("https://git.example.com/team/repo")),
+      ("unknown host", "git@example.org:owner/repo.git", None),
+      ("local path", "/srv/git/repo.git", None),
+    ];
+
+    for (description, remote_url, expected) in cases {
+      assert_eq!(WebRepo::from_remote_url(remote_url, &hosts), expected, "{}", description);
+    }
+  }
+
+  #[test]
+  fn branch_names_are_percent_encoded() {
+    let github = WebRepo { host: WebHost::GitHub, base_url: String::from("https://github.com/owner/repo") };
+    let gitlab = WebRepo { host: WebHost::GitLab, base_url: String::from("https://gitlab.com/group/repo") };
+    let branch = "fix/issue#12 50%";
+
+    assert_eq!(github.branch_url(branch), "https://github.com/owner/repo/tree/fix/issue%2312%2050%25");
+    assert_eq!(
+      github.new_pull_request_url(branch),
+      "https://github.com/owner/repo/compare/fix/issue%2312%2050%25?expand=1"
+    );
+    assert_eq!(gitlab.branch_url(branch), "https://gitlab.com/group/repo/-/tree/fix/issue%2312%2050%25");
+    assert_eq!(
+      gitlab.new_pull_request_url(branch),
+      "https://gitlab.com/group/repo/-/merge_requests/new?merge_request%5Bsource_branch%5D=fix%2Fissue%2312%2050%25"
+    );
+    assert_eq!(github.branch_url("feature/ünïcode"), "https://github.com/owner/repo/tree/feature/%C3%BCn%C3%AFcode");
+  }
+}
@@ -4,27 +4,71 @@ use color_eyre::eyre::Result;
 use crate::{
   app::App,
   cli::Cli,
-  utils::{initialize_logging, initialize_panic_handler},
+  config::Config,
+  error::Error,
+  utils::{initialize_logging, initialize_panic_handler, override_config_dir, override_data_dir},
 };
 
 pub mod action;
 pub mod app;
 pub mod cli;
+pub mod clipboard;
 pub mod components;
 pub mod config;
 pub mod error;
 pub mod git;
 pub mod mode;
+pub mod subcommands;
+pub mod theme;
 pub mod tui;
 pub mod utils;
 
 async fn tokio_main() -> Result<()> {
-  initialize_logging()?;
+  let args = Cli::parse();
+  // Overrides need to be in place before logging picks its directory.
+  if let Some(config_dir) = args.config_dir.clone() {
+    override_config_dir(config_dir);
+  }
+  if let Some(data_dir) = args.data_dir.clone() {
+    override_data_dir(data_dir);
+  }
+
+  // Read ahead of the app so the format is known before anything is logged, a broken config is reported by the app or
+  // subcommand it's handed to.
+  let config = Config::new();
+  let log_format = config.as_ref().map(|config| config.config.log_format).unwrap_or_default();
+  initialize_logging(log_format)?;
 
   initialize_panic_handler()?;
 
-  Cli::parse();
-  let mut app = App::new()?;
+  if let Some(command) = &args.command {
+    match subcommands::run(&args, command, config) {
+      Ok(true) => return Ok(()),
+      Ok(false) => std::process::exit(1),
+      Err(err) => {
+        log::error!("{}", err);
+        eprintln!("{} error: {}", env!("CARGO_PKG_NAME"), err);
+        std::process::exit(1);
+      },
+    }
+  }
+
+  let mut app = match App::new(&args, config) {
+    Ok(app) => app,
+    Err(err) => {
+      match err.downcast_ref::<Error>() {
+        // The terminal hasn't been taken over yet, so a missing git or repository gets a plain message and a clean exit.
+        Some(
+          git_err @ (Error::GitNotFound(_) | Error::GitNotExecutable(_) | Error::GitTooOld(_) | Error::NotARepository),
+        ) => {
+          log::error!("{}", git_err);
+          eprintln!("{} error: {}", env!("CARGO_PKG_NAME"), git_err);
+          std::process::exit(1);
+        },
+        _ => return Err(err),
+      }
+    },
+  };
   app.run().await?;
 
   Ok(())
@@ -1,8 +1,19 @@
 use serde::{Deserialize, Serialize};
 
+/// What currently has the user's attention, the app routes keys by it. Global shortcuts like quitting or editing the
+/// config only apply in `Default`, every other mode leaves keys to the component that entered it.
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Mode {
   #[default]
   Default,
-  Input,
+  /// Typing the name of a new branch.
+  BranchInput,
+  /// Typing a branch prefix to checkout.
+  QuickCheckout,
+  /// Typing what the selected branch is for.
+  DescriptionInput,
+  /// Typing the start of a branch name to jump the selection to it.
+  BranchJump,
+  /// A popup over the list, like branch details, a comparison or the commit log.
+  Overlay,
 }
@@ -0,0 +1,177 @@
+use std::io::Write;
+
+use color_eyre::eyre::{eyre, Result};
+use regex::Regex;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+  cli::{Cli, Command},
+  config::Config,
+  git::{
+    git2_repo::Git2Repo,
+    git_cli_repo::{ensure_git_installed, GitCliRepo},
+    git_repo::{detect_default_branch, resolve_branch_prefix, GitRepo},
+  },
+  utils::glob_to_regex,
+};
+
+/// Run `command` without the interface, printing what it does. Returns whether everything it set out to do was done,
+/// so scripts can tell from the exit code.
+pub fn run(cli: &Cli, command: &Command, config: Result<Config, config::ConfigError>) -> Result<bool> {
+  // Unlike the interface a broken config isn't worked around, it could be what protects a branch.
+  let config = config?;
+  if cli.read_only || config.config.read_only {
+    refuse_in_read_only(command)?;
+  }
+  let git_path = cli.git_path(&config.config);
+  ensure_git_installed(&git_path)?;
+  // Opened first so running outside a repository fails before any git command does.
+  Git2Repo::from_cwd()?;
+  let repo = GitCliRepo::from_cwd()?.with_git_path(git_path);
+
+  match command {
+    Command::Delete { merged: _, base, dry_run } => {
+      let protected = config
+        .config
+        .protected_branch_patterns
+        .iter()
+        .map(|glob| glob_to_regex(glob).map_err(|err| eyre!("Invalid protected branch pattern {}: {}", glob, err)))
+        .collect::<Result<Vec<Regex>>>()?;
+      let base = base.as_deref().or(config.config.default_base.as_deref());
+      delete_merged(&repo, base, &protected, *dry_run, &mut std::io::stdout())
+    },
+    Command::Checkout { partial } => {
+      checkout(&repo, partial, config.config.update_submodules_on_checkout, &mut std::io::stdout())?;
+      Ok(true)
+    },
+  }
+}
+
+/// Fail `command` if it would change the repository, which read-only mode disables here as it does in the interface. A
+/// dry run only reports what it would do, so it's still allowed.
+fn refuse_in_read_only(command: &Command) -> Result<()> {
+  match command {
+    Command::Delete { dry_run: false, .. } => {
+      Err(eyre!("Deleting branches is disabled in read-only mode, pass --dry-run to see what would be deleted"))
+    },
+    Command::Checkout { .. } => Err(eyre!("Checking out is disabled in read-only mode")),
+    Command::Delete { dry_run: true, .. } => Ok(()),
+  }
+}
+
+/// Check out the local branch `partial` resolves to, the same way the interface's checkout by prefix does. An ambiguous
+/// name fails listing what it matched.
+fn checkout(repo: &dyn GitRepo, partial: &str, update_submodules: bool, out: &mut dyn Write) -> Result<()> {
+  let branches = repo.local_branches()?;
+  let name = resolve_branch_prefix(branches.iter().map(|branch| branch.name.as_str()), partial)?;
+  let previous_tip = branches.iter().find(|branch| branch.is_head).and_then(|branch| branch.tip_sha.clone());
+  repo.checkout_branch_from_name(name)?;
+  writeln!(out, "Switched to {}", name)?;
+  if previous_tip.is_some_and(|tip| repo.gitmodules_changed(&tip).unwrap_or(false)) {
+    if update_submodules {
+      repo.update_submodules(&CancellationToken::new())?;
+      writeln!(out, "Submodules updated")?;
+    } else {
+      writeln!(out, "Submodules changed, run `git submodule update --init --recursive` to bring them in line")?;
+    }
+  }
+  Ok(())
+}
+
+/// Delete the local branches merged into `base`, or the default branch, with the same detection the interface hides
+/// merged branches with. Protected branches and the checked out one are kept, and a branch that fails to delete doesn't
+/// stop the rest.
+fn delete_merged(
+  repo: &dyn GitRepo,
+  base: Option<&str>,
+  protected: &[Regex],
+  dry_run: bool,
+  out: &mut dyn Write,
+) -> Result<bool> {
+  let Some(default_branch) = detect_default_branch(repo, base)? else {
+    return Err(eyre!("Couldn't work out the default branch, pass it with --base"));
+  };
+  let mut merged = repo.merged_branches(&default_branch)?;
+  merged.retain(|name| *name != default_branch);
+  let mut all_deleted = true;
+  for branch in repo.local_branches()?.into_iter().filter(|branch| merged.contains(&branch.name)) {
+    if branch.is_head {
+      writeln!(out, "Kept {}, it's checked out", branch.name)?;
+    } else if protected.iter().any(|pattern| pattern.is_match(&branch.name)) {
+      writeln!(out, "Kept {}, it's protected", branch.name)?;
+    } else if dry_run {
+      writeln!(out, "Would delete {}", branch.name)?;
+    } else {
+      match repo.delete_branch(&branch) {
+        Ok(()) => writeln!(out, "Deleted {}", branch.name)?,
+        Err(err) => {
+          all_deleted = false;
+          writeln!(out, "Failed to delete {}: {}", branch.name, err)?;
+        },
+      }
+    }
+  }
+  Ok(all_deleted)
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::git::test_repo::TestRepo;
+
+  fn delete(repo: &GitCliRepo, dry_run: bool) -> String {
+    let mut out = Vec::new();
+    let protected = vec![glob_to_regex("release/*").unwrap()];
+    assert!(delete_merged(repo, Some("main"), &protected, dry_run, &mut out).unwrap());
+    String::from_utf8(out).unwrap()
+  }
+
+  #[test]
+  fn delete_merged_keeps_protected_and_unmerged_branches() {
+    let test_repo = TestRepo::new();
+    test_repo.git(&["branch", "merged"]);
+    test_repo.git(&["branch", "release/1"]);
+    test_repo.git(&["checkout", "-b", "unmerged"]);
+    test_repo.git(&["commit", "--allow-empty", "-m", "Not on main"]);
+    test_repo.git(&["checkout", "main"]);
+    let repo = GitCliRepo::from_path(test_repo.path());
+
+    assert_eq!(delete(&repo, true), "Would delete merged\nKept release/1, it's protected\n");
+    assert!(test_repo.git(&["branch", "--list", "merged"]).contains("merged"));
+
+    assert_eq!(delete(&repo, false), "Deleted merged\nKept release/1, it's protected\n");
+    assert_eq!(test_repo.git(&["branch", "--list", "merged"]), "");
+  }
+
+  #[test]
+  fn read_only_only_allows_a_dry_run() {
+    let delete = |dry_run| Command::Delete { merged: true, base: None, dry_run };
+    assert!(refuse_in_read_only(&delete(true)).is_ok());
+    assert_eq!(
+      refuse_in_read_only(&delete(false)).unwrap_err().to_string(),
+      "Deleting branches is disabled in read-only mode, pass --dry-run to see what would be deleted"
+    );
+    let checkout = Command::Checkout { partial: String::from("main") };
+    assert_eq!(refuse_in_read_only(&checkout).unwrap_err().to_string(), "Checking out is disabled in read-only mode");
+  }
+
+  #[test]
+  fn checkout_resolves_a_unique_prefix() {
+    let test_repo = TestRepo::new();
+    test_repo.git(&["branch", "feature/login"]);
+    test_repo.git(&["branch", "feature/logout"]);
+    test_repo.git(&["branch", "fix/typo"]);
+    let repo = GitCliRepo::from_path(test_repo.path());
+    let mut out = Vec::new();
+
+    checkout(&repo, "fix", false, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "Switched to fix/typo\n");
+    assert_eq!(test_repo.current_branch(), "fix/typo");
+
+    let err = checkout(&repo, "feature/log", false, &mut Vec::new()).unwrap_err();
+    assert_eq!(err.to_string(), "feature/log is ambiguous, it matches feature/login, feature/logout");
+    assert_eq!(test_repo.current_branch(), "fix/typo");
+  }
+}
@@ -0,0 +1,110 @@
+use lazy_static::lazy_static;
+use ratatui::{
+  buffer::Buffer,
+  style::{Color, Modifier, Style},
+};
+use serde::Deserialize;
+
+// Drawn before the selected row unless the config picks another, `>` being the ASCII fallback.
+const DEFAULT_HIGHLIGHT_SYMBOL: &str = "→";
+
+lazy_static! {
+  // https://no-color.org, any non empty value turns colors off.
+  static ref NO_COLOR: bool = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+}
+
+/// Whether colors are drawn, off when `NO_COLOR` is set. Modifiers like bold and reversed always are.
+pub fn styling_enabled() -> bool {
+  !*NO_COLOR
+}
+
+/// How the selected row of a list stands out from the others.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SelectionStyle {
+  #[default]
+  Bold,
+  Reversed,
+  Underlined,
+  /// Only the highlight symbol marks the selection.
+  Plain,
+}
+
+impl SelectionStyle {
+  pub fn style(self) -> Style {
+    match self {
+      SelectionStyle::Bold => Style::default().add_modifier(Modifier::BOLD),
+      SelectionStyle::Reversed => Style::default().add_modifier(Modifier::REVERSED),
+      SelectionStyle::Underlined => Style::default().add_modifier(Modifier::UNDERLINED),
+      SelectionStyle::Plain => Style::default(),
+    }
+  }
+}
+
+/// How the branch and stash lists mark their selected row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Highlight {
+  pub symbol: String,
+  pub style: Style,
+}
+
+impl Highlight {
+  /// The configured `symbol` and `selection_style`, the default symbol when none is.
+  pub fn new(symbol: Option<String>, selection_style: SelectionStyle) -> Self {
+    Highlight {
+      symbol: symbol.unwrap_or_else(|| String::from(DEFAULT_HIGHLIGHT_SYMBOL)),
+      style: selection_style.style(),
+    }
+  }
+}
+
+impl Default for Highlight {
+  fn default() -> Self {
+    Highlight { symbol: String::from(DEFAULT_HIGHLIGHT_SYMBOL), style: SelectionStyle::default().style() }
+  }
+}
+
+/// Reset the colors of everything drawn into the frame when they're disabled. Run once per frame after every component
+/// has drawn, so none of them need to check for themselves.
+pub fn finish_frame(buffer: &mut Buffer) {
+  if styling_enabled() {
+    return;
+  }
+  strip_colors(buffer);
+}
+
+/// NO_COLOR only asks for no color. Modifiers are kept, without them nothing would mark the selected row or the cursor.
+fn strip_colors(buffer: &mut Buffer) {
+  for cell in buffer.content.iter_mut() {
+    cell.set_fg(Color::Reset).set_bg(Color::Reset);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+  use ratatui::{
+    layout::Rect,
+    widgets::{List, ListState, StatefulWidget},
+  };
+
+  use super::*;
+
+  #[test]
+  fn strip_colors_keeps_the_selection_visible() {
+    let area = Rect::new(0, 0, 10, 2);
+    let mut buffer = Buffer::empty(area);
+    let list = List::new(["main", "feature"])
+      .style(Style::default().fg(Color::Green))
+      .highlight_style(SelectionStyle::Reversed.style().bg(Color::Blue));
+    let mut state = ListState::default().with_selected(Some(1));
+    StatefulWidget::render(list, area, &mut buffer, &mut state);
+
+    strip_colors(&mut buffer);
+
+    let main = &buffer[(0, 0)];
+    let feature = &buffer[(0, 1)];
+    assert_eq!((main.fg, main.bg, main.modifier), (Color::Reset, Color::Reset, Modifier::empty()));
+    assert_eq!((feature.fg, feature.bg, feature.modifier), (Color::Reset, Color::Reset, Modifier::REVERSED));
+  }
+}
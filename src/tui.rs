@@ -1,5 +1,6 @@
 use std::{
   ops::{Deref, DerefMut},
+  process::{Command, ExitStatus},
   time::Duration,
 };
 
@@ -10,6 +11,7 @@ use crossterm::{
     DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event as CrosstermEvent,
     KeyEvent, KeyEventKind, MouseEvent,
   },
+  style::Print,
   terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
 use futures::{FutureExt, StreamExt};
@@ -54,6 +56,8 @@ pub struct Tui {
   pub tick_rate: f64,
   pub mouse: bool,
   pub paste: bool,
+  /// Draw on the alternate screen, which is thrown away on exit. Otherwise the last frame stays in the scrollback.
+  pub alternate_screen: bool,
 }
 
 impl Tui {
@@ -66,7 +70,19 @@ impl Tui {
     let task = tokio::spawn(async {});
     let mouse = false;
     let paste = false;
-    Ok(Self { terminal, task, cancellation_token, event_rx, event_tx, frame_rate, tick_rate, mouse, paste })
+    let alternate_screen = true;
+    Ok(Self {
+      terminal,
+      task,
+      cancellation_token,
+      event_rx,
+      event_tx,
+      frame_rate,
+      tick_rate,
+      mouse,
+      paste,
+      alternate_screen,
+    })
   }
 
   pub fn tick_rate(mut self, tick_rate: f64) -> Self {
@@ -89,6 +105,11 @@ impl Tui {
     self
   }
 
+  pub fn alternate_screen(mut self, alternate_screen: bool) -> Self {
+    self.alternate_screen = alternate_screen;
+    self
+  }
+
   pub fn start(&mut self) {
     let tick_delay = std::time::Duration::from_secs_f64(1.0 / self.tick_rate);
     let render_delay = std::time::Duration::from_secs_f64(1.0 / self.frame_rate);
@@ -172,7 +193,13 @@ impl Tui {
 
   pub fn enter(&mut self) -> Result<()> {
     crossterm::terminal::enable_raw_mode()?;
-    crossterm::execute!(io(), EnterAlternateScreen, cursor::Hide)?;
+    crossterm::execute!(io(), cursor::Hide)?;
+    if self.alternate_screen {
+      crossterm::execute!(io(), EnterAlternateScreen)?;
+    } else {
+      // Start from a blank screen, whatever was on it would otherwise show through between the widgets.
+      self.terminal.clear()?;
+    }
     if self.mouse {
       crossterm::execute!(io(), EnableMouseCapture)?;
     }
@@ -193,7 +220,14 @@ impl Tui {
       if self.mouse {
         crossterm::execute!(io(), DisableMouseCapture)?;
       }
-      crossterm::execute!(io(), LeaveAlternateScreen, cursor::Show)?;
+      if self.alternate_screen {
+        crossterm::execute!(io(), LeaveAlternateScreen)?;
+      } else {
+        // The last frame stays put, the prompt goes on a new line below it rather than over the footer.
+        let (_, rows) = crossterm::terminal::size()?;
+        crossterm::execute!(io(), cursor::MoveTo(0, rows.saturating_sub(1)), Print("\r\n"))?;
+      }
+      crossterm::execute!(io(), cursor::Show)?;
       crossterm::terminal::disable_raw_mode()?;
     }
     Ok(())
@@ -215,6 +249,16 @@ impl Tui {
     Ok(())
   }
 
+  /// Hand the terminal over to a full screen program like a pager or editor, taking it back once the program exits.
+  pub fn run_external(&mut self, command: &mut Command) -> Result<ExitStatus> {
+    self.exit()?;
+    let status = command.status();
+    self.enter()?;
+    // The program drew over everything, so nothing on screen matches what ratatui thinks is there.
+    self.terminal.clear()?;
+    Ok(status?)
+  }
+
   pub async fn next(&mut self) -> Option<Event> {
     self.event_rx.recv().await
   }
@@ -1,11 +1,19 @@
-use std::path::PathBuf;
+use std::{
+  path::PathBuf,
+  process::{Command, Stdio},
+  sync::OnceLock,
+  time::{SystemTime, UNIX_EPOCH},
+};
 
 use color_eyre::eyre::Result;
 use directories::ProjectDirs;
 use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
 use tracing::error;
 use tracing_error::ErrorLayer;
 use tracing_subscriber::{prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt, Layer};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 const VERSION_MESSAGE: &str =
   concat!(env!("CARGO_PKG_VERSION"), "-", env!("VERGEN_GIT_DESCRIBE"), " (", env!("VERGEN_BUILD_DATE"), ")");
@@ -17,9 +25,43 @@ lazy_static! {
   pub static ref CONFIG_FOLDER: Option<PathBuf> =
     std::env::var(format!("{}_CONFIG", PROJECT_NAME.clone())).ok().map(PathBuf::from);
   pub static ref LOG_ENV: String = format!("{}_LOGLEVEL", PROJECT_NAME.clone());
+  pub static ref LOG_FORMAT_ENV: String = format!("{}_LOG_FORMAT", PROJECT_NAME.clone());
   pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
 }
 
+// Set from CLI flags, these take precedence over the environment and platform defaults.
+static DATA_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+static CONFIG_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// The user's editor from `$VISUAL` or `$EDITOR`, which may carry arguments like `code --wait`.
+pub fn editor_command() -> Command {
+  let editor = ["VISUAL", "EDITOR"]
+    .iter()
+    .filter_map(|var| std::env::var(var).ok())
+    .find(|editor| !editor.trim().is_empty())
+    .unwrap_or_else(|| String::from(if cfg!(windows) { "notepad" } else { "vi" }));
+  let mut parts = editor.split_whitespace();
+  let mut command = Command::new(parts.next().unwrap());
+  command.args(parts);
+  command
+}
+
+/// Open `url` in the default browser without waiting for it.
+pub fn open_in_browser(url: &str) -> std::io::Result<()> {
+  let mut command = if cfg!(target_os = "macos") {
+    Command::new("open")
+  } else if cfg!(windows) {
+    let mut command = Command::new("cmd");
+    // The empty argument is the window title, otherwise `start` takes a quoted url as one.
+    command.args(["/C", "start", ""]);
+    command
+  } else {
+    Command::new("xdg-open")
+  };
+  command.arg(url).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn()?;
+  Ok(())
+}
+
 fn project_directory() -> Option<ProjectDirs> {
   ProjectDirs::from("com", "rrushton", env!("CARGO_PKG_NAME"))
 }
@@ -72,8 +114,22 @@ pub fn initialize_panic_handler() -> Result<()> {
   Ok(())
 }
 
+pub fn override_data_dir(directory: PathBuf) {
+  if DATA_DIR_OVERRIDE.set(directory).is_err() {
+    error!("Data directory was already overridden");
+  }
+}
+
+pub fn override_config_dir(directory: PathBuf) {
+  if CONFIG_DIR_OVERRIDE.set(directory).is_err() {
+    error!("Config directory was already overridden");
+  }
+}
+
 pub fn get_data_dir() -> PathBuf {
-  let directory = if let Some(s) = DATA_FOLDER.clone() {
+  let directory = if let Some(s) = DATA_DIR_OVERRIDE.get().cloned() {
+    s
+  } else if let Some(s) = DATA_FOLDER.clone() {
     s
   } else if let Some(proj_dirs) = project_directory() {
     proj_dirs.data_local_dir().to_path_buf()
@@ -84,7 +140,9 @@ pub fn get_data_dir() -> PathBuf {
 }
 
 pub fn get_config_dir() -> PathBuf {
-  let directory = if let Some(s) = CONFIG_FOLDER.clone() {
+  let directory = if let Some(s) = CONFIG_DIR_OVERRIDE.get().cloned() {
+    s
+  } else if let Some(s) = CONFIG_FOLDER.clone() {
     s
   } else if let Some(proj_dirs) = project_directory() {
     proj_dirs.config_local_dir().to_path_buf()
@@ -94,7 +152,25 @@ pub fn get_config_dir() -> PathBuf {
   directory
 }
 
-pub fn initialize_logging() -> Result<()> {
+/// How lines are written to the log file. JSON lines are for shipping logs to an aggregator.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+  #[default]
+  Text,
+  Json,
+}
+
+/// The log format from the `<PROJECT>_LOG_FORMAT` environment variable, falling back to `configured`.
+fn log_format(configured: LogFormat) -> LogFormat {
+  match std::env::var(LOG_FORMAT_ENV.clone()).map(|format| format.to_lowercase()).as_deref() {
+    Ok("json") => LogFormat::Json,
+    Ok("text") => LogFormat::Text,
+    _ => configured,
+  }
+}
+
+pub fn initialize_logging(configured_format: LogFormat) -> Result<()> {
   let directory = get_data_dir();
   std::fs::create_dir_all(directory.clone())?;
   let log_path = directory.join(LOG_FILE.clone());
@@ -105,13 +181,13 @@ pub fn initialize_logging() -> Result<()> {
       .or_else(|_| std::env::var(LOG_ENV.clone()))
       .unwrap_or_else(|_| format!("{}=info", env!("CARGO_CRATE_NAME"))),
   );
-  let file_subscriber = tracing_subscriber::fmt::layer()
-    .with_file(true)
-    .with_line_number(true)
-    .with_writer(log_file)
-    .with_target(false)
-    .with_ansi(false)
-    .with_filter(tracing_subscriber::filter::EnvFilter::from_default_env());
+  let file_subscriber =
+    tracing_subscriber::fmt::layer().with_file(true).with_line_number(true).with_writer(log_file).with_target(false);
+  let file_subscriber = match log_format(configured_format) {
+    LogFormat::Text => file_subscriber.with_ansi(false).boxed(),
+    LogFormat::Json => file_subscriber.json().boxed(),
+  }
+  .with_filter(tracing_subscriber::filter::EnvFilter::from_default_env());
   tracing_subscriber::registry().with(file_subscriber).with(ErrorLayer::default()).init();
   Ok(())
 }
@@ -142,6 +218,87 @@ macro_rules! trace_dbg {
     };
 }
 
+/// Format seconds since the unix epoch as a UTC date and time, e.g. `2024-09-14 08:31 UTC`.
+pub fn format_timestamp(timestamp: i64) -> String {
+  let days = timestamp.div_euclid(86_400);
+  let seconds_of_day = timestamp.rem_euclid(86_400);
+  // Days to a civil date, see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+  let shifted = days + 719_468;
+  let era = shifted.div_euclid(146_097);
+  let day_of_era = shifted.rem_euclid(146_097);
+  let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let shifted_month = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+  let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+  let year = year_of_era + era * 400 + i64::from(month <= 2);
+  format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, seconds_of_day / 3600, seconds_of_day % 3600 / 60)
+}
+
+pub fn now_timestamp() -> i64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs() as i64).unwrap_or_default()
+}
+
+const RELATIVE_TIME_UNITS: [(i64, &str); 6] =
+  [(31_536_000, "year"), (2_592_000, "month"), (604_800, "week"), (86_400, "day"), (3600, "hour"), (60, "minute")];
+
+/// Describe how long before `now` a timestamp was, e.g. `3 days ago`. Both are seconds since the unix epoch.
+pub fn format_relative_time(timestamp: i64, now: i64) -> String {
+  let elapsed = now - timestamp;
+  // A little in the future is just clock skew between machines, much more than that is worth calling out.
+  if elapsed < -3600 {
+    return String::from("in the future");
+  }
+  if elapsed < 60 {
+    return String::from("just now");
+  }
+  let (unit_seconds, unit) = RELATIVE_TIME_UNITS.iter().find(|(unit_seconds, _)| elapsed >= *unit_seconds).unwrap();
+  let count = elapsed / unit_seconds;
+  format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" })
+}
+
+/// Shorten `text` to at most `max_width` terminal cells, ending with an ellipsis when anything was cut off. Wide
+/// characters like CJK or emoji take two cells and are dropped whole rather than split, so the result can come up a
+/// cell short.
+pub fn truncate_with_ellipsis(text: &str, max_width: usize) -> String {
+  if text.width() <= max_width {
+    return String::from(text);
+  }
+  if max_width == 0 {
+    return String::new();
+  }
+  let mut truncated = String::new();
+  let mut width = 0;
+  for c in text.chars() {
+    width += c.width().unwrap_or(0);
+    if width > max_width - 1 {
+      break;
+    }
+    truncated.push(c);
+  }
+  truncated.push('…');
+  truncated
+}
+
+/// Turn a glob like `release/*` into an anchored regex, `*` stays within one path segment while `**` crosses them.
+pub fn glob_to_regex(glob: &str) -> Result<Regex, regex::Error> {
+  let mut pattern = String::from("^");
+  let mut chars = glob.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '*' if chars.peek() == Some(&'*') => {
+        chars.next();
+        pattern.push_str(".*");
+      },
+      '*' => pattern.push_str("[^/]*"),
+      '?' => pattern.push_str("[^/]"),
+      c => pattern.push_str(&regex::escape(&c.to_string())),
+    }
+  }
+  pattern.push('$');
+  Regex::new(&pattern)
+}
+
 pub fn version() -> String {
   let author = clap::crate_authors!();
 
@@ -160,3 +317,69 @@ Data directory: {data_dir_path}
 Log leve: {log_level}"
   )
 }
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn log_format_env_wins_over_the_config() {
+    let cases = vec![
+      (None, LogFormat::Json, LogFormat::Json),
+      (Some("json"), LogFormat::Text, LogFormat::Json),
+      (Some("TEXT"), LogFormat::Json, LogFormat::Text),
+      (Some("yaml"), LogFormat::Json, LogFormat::Json),
+    ];
+    for (env, configured, expected) in cases {
+      match env {
+        Some(value) => std::env::set_var(LOG_FORMAT_ENV.clone(), value),
+        None => std::env::remove_var(LOG_FORMAT_ENV.clone()),
+      }
+      assert_eq!(log_format(configured), expected, "{:?}", env);
+    }
+    std::env::remove_var(LOG_FORMAT_ENV.clone());
+  }
+
+  #[test]
+  fn format_relative_time_rounds_down_to_the_largest_unit() {
+    let now = 1_700_000_000;
+    assert_eq!(format_relative_time(now - 59, now), "just now");
+    assert_eq!(format_relative_time(now - 60, now), "1 minute ago");
+    assert_eq!(format_relative_time(now - 7199, now), "1 hour ago");
+    assert_eq!(format_relative_time(now - 2 * 86_400, now), "2 days ago");
+    assert_eq!(format_relative_time(now - 604_800, now), "1 week ago");
+    assert_eq!(format_relative_time(now - 2 * 31_536_000, now), "2 years ago");
+  }
+
+  #[test]
+  fn format_relative_time_allows_an_hour_of_clock_skew() {
+    let now = 1_700_000_000;
+    assert_eq!(format_relative_time(now + 3600, now), "just now");
+    assert_eq!(format_relative_time(now + 3601, now), "in the future");
+  }
+
+  #[test]
+  fn truncate_with_ellipsis_fits_max_width() {
+    assert_eq!(truncate_with_ellipsis("feature", 7), "feature");
+    assert_eq!(truncate_with_ellipsis("feature", 6), "featu…");
+    assert_eq!(truncate_with_ellipsis("feature", 1), "…");
+    assert_eq!(truncate_with_ellipsis("feature", 0), "");
+  }
+
+  #[test]
+  fn glob_to_regex_matches_whole_names() {
+    let matches = |glob: &str, name: &str| glob_to_regex(glob).unwrap().is_match(name);
+    assert!(matches("release/*", "release/1.0"));
+    assert!(!matches("release/*", "release/1.0/hotfix"));
+    assert!(matches("release/**", "release/1.0/hotfix"));
+    assert!(matches("v?", "v1"));
+    assert!(!matches("a?b", "a/b"));
+    // Anything else is literal, even where it means something in a regex.
+    assert!(!matches("v1.0", "v1x0"));
+    assert!(matches("fix[1]", "fix[1]"));
+    assert!(!matches("main", "not-main"));
+    assert!(!matches("main", "main-old"));
+  }
+}